@@ -0,0 +1,9 @@
+#![no_main]
+
+use b3d::B3D;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|b3d: B3D| {
+    let _ = b3d.extract_meshes();
+    let _ = b3d::export::obj::export(&b3d, &Default::default());
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use b3d::{Limits, ParseOptions, B3D};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let options = ParseOptions {
+        limits: Limits::untrusted(),
+        ..Default::default()
+    };
+    let _ = B3D::read_with_options(data, &options);
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use b3d::B3D;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|b3d: B3D| {
+    // Writing drops what the parser doesn't keep, so compare against the file written again
+    // from what was read rather than against `b3d` itself.
+    let bytes = b3d.to_bytes();
+    let read = B3D::read(&bytes).expect("written files should parse");
+    assert_eq!(read.to_bytes(), bytes);
+});
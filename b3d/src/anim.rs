@@ -0,0 +1,344 @@
+use std::ops::RangeInclusive;
+
+use crate::math::{self, Mat4};
+use crate::utils::{Vec3, Vec4};
+use crate::{coords, Animation, Key, Keys, Node, Sequence, Vertice, B3D};
+
+/// How an animation is meant to play, named after the modes of Blitz3D's `Animate` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlaybackMode {
+    /// Restart from the first frame after the last.
+    #[default]
+    Loop,
+    /// Play forwards then backwards, repeatedly.
+    PingPong,
+    /// Play once and hold the last frame.
+    OneShot,
+}
+
+/// A named span of frames played as one animation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    pub name: String,
+    pub frames: RangeInclusive<u32>,
+    pub mode: PlaybackMode,
+}
+
+impl Animation {
+    /// The frame rate Blitz3D plays animations at when a file leaves `fps` at zero.
+    pub const DEFAULT_FPS: f32 = 60.0;
+
+    /// Returns `fps`, or [`Self::DEFAULT_FPS`] if the file doesn't set one.
+    pub fn fps_or_default(&self) -> f32 {
+        if self.fps > 0.0 {
+            self.fps
+        } else {
+            Self::DEFAULT_FPS
+        }
+    }
+
+    /// Decodes the intended playback mode from `flags`.
+    ///
+    /// Blitz3D ignores the flags and writes 0. Tools that record a mode use the values of
+    /// `Animate`: 1 to loop, 2 to ping-pong and 3 to play once. Anything else plays as a loop,
+    /// which is `Animate`'s default.
+    pub fn playback_mode(&self) -> PlaybackMode {
+        match self.flags {
+            2 => PlaybackMode::PingPong,
+            3 => PlaybackMode::OneShot,
+            _ => PlaybackMode::Loop,
+        }
+    }
+
+    /// Returns the length of the animation in seconds.
+    pub fn duration_secs(&self) -> f32 {
+        self.frames as f32 / self.fps_or_default()
+    }
+
+    /// Converts a frame number into seconds from the start of the animation.
+    pub fn frame_to_secs(&self, frame: f32) -> f32 {
+        frame / self.fps_or_default()
+    }
+
+    /// Converts seconds from the start of the animation into a (fractional) frame number.
+    pub fn secs_to_frame(&self, secs: f32) -> f32 {
+        secs * self.fps_or_default()
+    }
+}
+
+impl Sequence {
+    /// Returns the number of frames from `first_frame` to `last_frame`, inclusive, capped at
+    /// `u32::MAX`.
+    pub fn frame_count(&self) -> u32 {
+        self.last_frame.saturating_sub(self.first_frame).saturating_add(1)
+    }
+
+    /// Returns the time taken to play from `first_frame` to `last_frame` at the rate of `animation`.
+    pub fn duration_secs(&self, animation: &Animation) -> f32 {
+        animation.frame_to_secs(self.last_frame.saturating_sub(self.first_frame) as f32)
+    }
+}
+
+impl Keys {
+    /// Interpolates the keys at a (fractional) frame, linearly for position and scale and
+    /// spherically for rotation. Frames outside the keys hold the first or last key.
+    ///
+    /// The returned key's `frame` is `frame` rounded to the nearest whole frame. Returns `None`
+    /// if there are no keys.
+    pub fn sample(&self, frame: f32) -> Option<Key> {
+        let next = self.keys.partition_point(|key| key.frame as f32 <= frame);
+        let previous = next.checked_sub(1).and_then(|index| self.keys.get(index));
+
+        let mut key = match (previous, self.keys.get(next)) {
+            (Some(a), Some(b)) => {
+                let span = b.frame as f32 - a.frame as f32;
+                let t = if span > 0.0 { ((frame - a.frame as f32) / span).clamp(0.0, 1.0) } else { 0.0 };
+                let mut key = a.clone();
+                if self.flags & 1 != 0 {
+                    key.position = math::lerp(a.position, b.position, t);
+                }
+                if self.flags & 2 != 0 {
+                    key.scale = math::lerp(a.scale, b.scale, t);
+                }
+                if self.flags & 4 != 0 {
+                    key.rotation = slerp_wxyz(a.rotation, b.rotation, t);
+                }
+                key
+            }
+            (Some(key), None) | (None, Some(key)) => key.clone(),
+            (None, None) => return None,
+        };
+        key.frame = frame.max(0.0).round() as u32;
+        Some(key)
+    }
+
+    /// Resamples the keys to one per frame at `fps`, given the `source_fps` they were authored
+    /// at, interpolating as [`Self::sample`] does.
+    ///
+    /// The new keys span the same time as the old ones, with frames counted at the new rate, so
+    /// keys from 10 to 20 at 30 fps become keys from 20 to 40 at 60 fps. The keys are returned
+    /// unchanged if either rate isn't positive.
+    pub fn resample(&self, source_fps: f32, fps: f32) -> Keys {
+        let ratio = fps / source_fps;
+        let (Some(first), Some(last)) = (self.keys.first(), self.keys.last()) else {
+            return self.clone();
+        };
+        if !(ratio.is_finite() && ratio > 0.0) {
+            return self.clone();
+        }
+
+        let start = (first.frame as f32 * ratio).round() as u32;
+        let end = (last.frame as f32 * ratio).round() as u32;
+        let keys = (start..=end)
+            .filter_map(|frame| {
+                let mut key = self.sample(frame as f32 / ratio)?;
+                key.frame = frame;
+                Some(key)
+            })
+            .collect();
+
+        Keys {
+            flags: self.flags,
+            keys,
+        }
+    }
+}
+
+/// Slerps quaternions stored as `[w, x, y, z]`.
+fn slerp_wxyz(a: Vec4, b: Vec4, t: f32) -> Vec4 {
+    let [x, y, z, w] = math::slerp([a[1], a[2], a[3], a[0]], [b[1], b[2], b[3], b[0]], t);
+    [w, x, y, z]
+}
+
+impl Node {
+    /// Returns the node's local position, scale and rotation at `frame`, taking each animated
+    /// channel from the keys and the rest from the node's rest pose.
+    pub fn pose_at(&self, frame: f32) -> (Vec3, Vec3, Vec4) {
+        let (mut position, mut scale, mut rotation) = (self.position, self.scale, self.rotation);
+        for keys in &self.keys {
+            let Some(key) = keys.sample(frame) else {
+                continue;
+            };
+            if keys.flags & 1 != 0 {
+                position = key.position;
+            }
+            if keys.flags & 2 != 0 {
+                scale = key.scale;
+            }
+            if keys.flags & 4 != 0 {
+                rotation = key.rotation;
+            }
+        }
+        (position, scale, rotation)
+    }
+
+    /// Returns the column-major matrix placing the node in its parent in its rest pose.
+    pub fn rest_transform(&self) -> [f32; 16] {
+        math::compose(self.position, coords::rotation(self.rotation), self.scale)
+    }
+
+    /// Returns the column-major matrix placing the node in its parent at `frame`.
+    pub fn local_transform_at(&self, frame: f32) -> [f32; 16] {
+        let (position, scale, rotation) = self.pose_at(frame);
+        math::compose(position, coords::rotation(rotation), scale)
+    }
+}
+
+impl B3D {
+    /// Returns the `ANIM` chunk driving the model's keys, which exporters put on the root node.
+    pub fn animation(&self) -> Option<&Animation> {
+        self.nodes()
+            .map(|node| &node.animation)
+            .find(|animation| animation.frames > 0 || animation.fps > 0.0)
+    }
+
+    /// Returns the first and last frames that have keys anywhere in the hierarchy.
+    pub fn animation_frame_range(&self) -> Option<RangeInclusive<u32>> {
+        let mut frames = self.nodes().flat_map(|node| &node.keys).flat_map(|keys| &keys.keys).map(|key| key.frame);
+        let first = frames.next()?;
+        let (min, max) = frames.fold((first, first), |(min, max), frame| (min.min(frame), max.max(frame)));
+        Some(min..=max)
+    }
+
+    /// Bakes the global transform of every node at each frame in `frames`, composing the animated
+    /// local transforms down the hierarchy.
+    ///
+    /// The result is indexed by [`NodeId`](crate::NodeId) and then by frame from the start of
+    /// `frames`. Matrices are column-major and in Blitz3D's left-handed space, like
+    /// [`MeshData::transform`](crate::MeshData::transform).
+    pub fn bake_poses(&self, frames: RangeInclusive<u32>) -> Vec<Vec<[f32; 16]>> {
+        let root = vec![math::IDENTITY; frames.clone().count()];
+        let mut poses = vec![];
+        bake_node(&self.node, &root, &frames, &mut poses);
+        poses
+    }
+
+    /// Splits the animation into clips, one per `SEQS` chunk, or a single clip named `Animation0`
+    /// from frame 0 to the last key when there are none. Every clip plays in the mode of
+    /// [`Self::animation`]. Sequences ending before they start are cut down to their first frame.
+    ///
+    /// Returns no clips if the model has no `ANIM` chunk.
+    pub fn clips(&self) -> Vec<Clip> {
+        let Some(animation) = self.animation() else {
+            return vec![];
+        };
+        let mode = animation.playback_mode();
+
+        let clips: Vec<Clip> = self
+            .nodes()
+            .flat_map(|node| &node.sequences)
+            .map(|sequence| Clip {
+                name: sequence.name.clone(),
+                frames: sequence.first_frame..=sequence.last_frame.max(sequence.first_frame),
+                mode,
+            })
+            .collect();
+        if !clips.is_empty() {
+            return clips;
+        }
+
+        self.animation_frame_range()
+            .map(|range| Clip {
+                name: "Animation0".to_owned(),
+                frames: 0..=*range.end(),
+                mode,
+            })
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the vertex positions of the mesh on the node at `path` (see
+    /// [`Self::node_by_path`]) `time` seconds into the animation, in Blitz3D's left-handed model
+    /// space.
+    ///
+    /// Vertices are placed by the animated transforms of the node and its ancestors, or deformed
+    /// by the bones beneath the node when they carry weights for them. Returns `None` if there is
+    /// no node at `path`.
+    pub fn sample_world_vertices(&self, path: &str, time: f32) -> Option<Vec<Vec3>> {
+        let chain = self.node_chain(path)?;
+        let frame = self.animation().cloned().unwrap_or_default().secs_to_frame(time);
+
+        let (mut global, mut rest) = (math::IDENTITY, math::IDENTITY);
+        for node in &chain {
+            global = math::mul(&global, &node.local_transform_at(frame));
+            rest = math::mul(&rest, &node.rest_transform());
+        }
+        let mesh = chain[chain.len() - 1];
+        let vertices = &mesh.mesh.vertices.vertices;
+
+        let mut skinned = vec![([0.0; 3], 0.0); vertices.len()];
+        for child in &mesh.children {
+            skin(child, &global, &rest, &rest, frame, vertices, &mut skinned);
+        }
+
+        let positions = vertices
+            .iter()
+            .zip(skinned)
+            .map(|(vertex, (position, weight))| {
+                if weight > 0.0 {
+                    position.map(|c| c / weight)
+                } else {
+                    math::transform_point(&global, vertex.position)
+                }
+            })
+            .collect();
+        Some(positions)
+    }
+
+    /// Returns every sequence in the hierarchy with its duration in seconds.
+    pub fn sequence_durations(&self) -> Vec<(&Sequence, f32)> {
+        let animation = self.animation().cloned().unwrap_or_default();
+        self.nodes()
+            .flat_map(|node| &node.sequences)
+            .map(|sequence| (sequence, sequence.duration_secs(&animation)))
+            .collect()
+    }
+}
+
+fn bake_node(node: &Node, parent: &[Mat4], frames: &RangeInclusive<u32>, poses: &mut Vec<Vec<Mat4>>) {
+    let pose: Vec<Mat4> = frames
+        .clone()
+        .zip(parent)
+        .map(|(frame, parent)| math::mul(parent, &node.local_transform_at(frame as f32)))
+        .collect();
+    poses.push(pose.clone());
+    for child in &node.children {
+        bake_node(child, &pose, frames, poses);
+    }
+}
+
+/// Accumulates the weighted positions that `bone` and its descendants move vertices to.
+/// `mesh_rest` is the rest transform of the skinned mesh, and `parent` and `parent_rest` the
+/// animated and rest transforms of the bone's parent.
+fn skin(
+    bone: &Node,
+    parent: &Mat4,
+    parent_rest: &Mat4,
+    mesh_rest: &Mat4,
+    frame: f32,
+    vertices: &[Vertice],
+    skinned: &mut [(Vec3, f32)],
+) {
+    let global = math::mul(parent, &bone.local_transform_at(frame));
+    let rest = math::mul(parent_rest, &bone.rest_transform());
+
+    if !bone.bones.is_empty() {
+        let matrix = math::mul(&global, &math::mul(&math::inverse(&rest), mesh_rest));
+        for weight in &bone.bones {
+            let index = weight.vertex_id as usize;
+            let (Some(vertex), Some((position, total))) = (vertices.get(index), skinned.get_mut(index)) else {
+                continue;
+            };
+            let moved = math::transform_point(&matrix, vertex.position);
+            for (c, m) in position.iter_mut().zip(moved) {
+                *c += m * weight.weight;
+            }
+            *total += weight.weight;
+        }
+    }
+
+    for child in &bone.children {
+        skin(child, &global, &rest, mesh_rest, frame, vertices, skinned);
+    }
+}
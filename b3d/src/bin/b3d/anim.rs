@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::bail;
+use b3d::{Node, B3D};
+use clap::ValueEnum;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The model to read the animation of.
+    path: PathBuf,
+    /// Only print these nodes, by name or by path such as `ROOT/Bip01/Bip01 Head`. By default
+    /// every animated node is printed.
+    #[arg(long)]
+    node: Vec<String>,
+    /// Print the keys as stored instead of the pose sampled at every frame.
+    #[arg(long)]
+    raw: bool,
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Readable columns, preceded by the animation header and its sequences.
+    Text,
+    /// Comma separated values with a header row.
+    Csv,
+}
+
+const COLUMNS: [&str; 12] = ["node", "frame", "px", "py", "pz", "sx", "sy", "sz", "rw", "rx", "ry", "rz"];
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let b3d = crate::load(&args.path)?;
+
+    let mut nodes = vec![];
+    collect(&b3d.node, "", &mut nodes);
+    if args.node.is_empty() {
+        nodes.retain(|(_, node)| node.is_animated());
+    } else {
+        for wanted in &args.node {
+            if !nodes.iter().any(|(path, node)| matches(path, node, wanted)) {
+                bail!("no node named {wanted}");
+            }
+        }
+        nodes.retain(|(path, node)| args.node.iter().any(|wanted| matches(path, node, wanted)));
+    }
+
+    if args.format == Format::Text {
+        print_header(&b3d);
+    }
+    let header: Vec<String> = COLUMNS.iter().map(ToString::to_string).collect();
+    print_row(args.format, &header);
+
+    for (path, node) in nodes {
+        if args.raw {
+            for keys in &node.keys {
+                for key in &keys.keys {
+                    let mut row = vec![path.clone(), key.frame.to_string()];
+                    row.extend(optional(keys.flags & 1 != 0, &key.position));
+                    row.extend(optional(keys.flags & 2 != 0, &key.scale));
+                    row.extend(optional(keys.flags & 4 != 0, &key.rotation));
+                    print_row(args.format, &row);
+                }
+            }
+        } else if let Some(frames) = b3d.animation_frame_range() {
+            for frame in frames {
+                let (position, scale, rotation) = node.pose_at(frame as f32);
+                let mut row = vec![path.clone(), frame.to_string()];
+                row.extend(optional(true, &position));
+                row.extend(optional(true, &scale));
+                row.extend(optional(true, &rotation));
+                print_row(args.format, &row);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_header(b3d: &B3D) {
+    match b3d.animation() {
+        Some(animation) => println!("animation: {} frames at {} fps", animation.frames, animation.fps_or_default()),
+        None => println!("animation: none"),
+    }
+    for (sequence, secs) in b3d.sequence_durations() {
+        println!(
+            "sequence {}: frames {} to {} ({secs:.3} s)",
+            sequence.name, sequence.first_frame, sequence.last_frame
+        );
+    }
+}
+
+fn collect<'a>(node: &'a Node, parent: &str, nodes: &mut Vec<(String, &'a Node)>) {
+    let path = if parent.is_empty() {
+        node.name.to_string()
+    } else {
+        format!("{parent}/{}", node.name)
+    };
+    nodes.push((path.clone(), node));
+    for child in &node.children {
+        collect(child, &path, nodes);
+    }
+}
+
+fn matches(path: &str, node: &Node, wanted: &str) -> bool {
+    path == wanted || node.name == wanted
+}
+
+/// Formats `values`, or leaves their columns empty when the key doesn't store them.
+fn optional(present: bool, values: &[f32]) -> Vec<String> {
+    values.iter().map(|value| if present { value.to_string() } else { String::new() }).collect()
+}
+
+/// Prints a row whose first field is the node.
+fn print_row(format: Format, row: &[String]) {
+    match format {
+        Format::Text => {
+            let values: Vec<String> = row[1..].iter().map(|value| format!("{value:>10}")).collect();
+            println!("{:<24} {}", row[0], values.join(" "));
+        }
+        Format::Csv => {
+            let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            println!("{}", row.join(","));
+        }
+    }
+}
+
+/// Quotes fields holding commas or quotes, which node names may.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::{bail, Context};
+use b3d::export::bvh::{self, BvhOptions};
+use b3d::export::collada::{self, ColladaOptions};
+use b3d::export::gltf::{self, GltfOptions};
+use b3d::export::obj::{self, ObjOptions};
+use b3d::B3D;
+use clap::ValueEnum;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The model to convert.
+    input: PathBuf,
+    /// Where to write the result. A `.gltf` gets its buffer in a `.bin`, and an `.obj` its
+    /// materials in a `.mtl`, next to it.
+    output: PathBuf,
+    /// The output format, by default taken from the output's extension.
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+    /// Keep Blitz3D's left-handed coordinates instead of converting to right-handed ones.
+    #[arg(long)]
+    keep_axes: bool,
+    /// Uniform factor applied to positions and translations.
+    #[arg(long, default_value_t = 1.0)]
+    scale: f32,
+    /// Leave out the animation keys (glTF and COLLADA only).
+    #[arg(long)]
+    no_animations: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    Glb,
+    Gltf,
+    Obj,
+    Dae,
+    /// The skeleton and animation only.
+    Bvh,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Glb => "glb",
+            Self::Gltf => "gltf",
+            Self::Obj => "obj",
+            Self::Dae => "dae",
+            Self::Bvh => "bvh",
+        }
+    }
+
+    fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "glb" => Some(Self::Glb),
+            "gltf" => Some(Self::Gltf),
+            "obj" => Some(Self::Obj),
+            "dae" => Some(Self::Dae),
+            "bvh" => Some(Self::Bvh),
+            _ => None,
+        }
+    }
+}
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let Some(format) = args.format.or_else(|| Format::from_extension(&args.output)) else {
+        bail!("can't tell the format of {}, pass --format", args.output.display());
+    };
+    let b3d = crate::load(&args.input)?;
+    let options = GltfOptions {
+        convert_coordinates: !args.keep_axes,
+        scale: args.scale,
+        animations: !args.no_animations,
+    };
+    export(&b3d, format, &args.output, &options)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Writes `b3d` to `output` in `format`, along with its buffer or material library. OBJ
+/// and BVH output ignore `options.animations`.
+pub fn export(b3d: &B3D, format: Format, output: &Path, options: &GltfOptions) -> anyhow::Result<()> {
+    match format {
+        Format::Glb | Format::Gltf => {
+            let document = gltf::export(b3d, options);
+            if let Format::Glb = format {
+                write(output, document.to_glb())?;
+            } else {
+                let buffer = output.with_extension("bin");
+                write(output, document.to_gltf(&file_name(&buffer)))?;
+                write(&buffer, &document.buffer)?;
+            }
+        }
+        Format::Obj => {
+            let options = ObjOptions {
+                convert_coordinates: options.convert_coordinates,
+                scale: options.scale,
+            };
+            let document = obj::export(b3d, &options);
+            let mtl = output.with_extension("mtl");
+            write(output, document.to_obj(&file_name(&mtl)))?;
+            write(&mtl, &document.mtl)?;
+        }
+        Format::Dae => {
+            let options = ColladaOptions {
+                convert_coordinates: options.convert_coordinates,
+                scale: options.scale,
+                animations: options.animations,
+            };
+            write(output, collada::export(b3d, &options))?;
+        }
+        Format::Bvh => {
+            let options = BvhOptions {
+                convert_coordinates: options.convert_coordinates,
+                scale: options.scale,
+            };
+            write(output, bvh::export(b3d, &options))?;
+        }
+    }
+    Ok(())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+fn write(path: &Path, contents: impl AsRef<[u8]>) -> anyhow::Result<()> {
+    std::fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use b3d::{Node, B3D};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The model before the change.
+    old: PathBuf,
+    /// The model after the change.
+    new: PathBuf,
+    /// Exit with a failure when the models differ.
+    #[arg(long)]
+    fail_on_change: bool,
+    /// Largest difference between floats that still counts as equal.
+    #[arg(long, default_value_t = 1e-5)]
+    tolerance: f32,
+}
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let old = crate::load(&args.old)?;
+    let new = crate::load(&args.new)?;
+
+    let mut diff = Diff { tolerance: args.tolerance, changes: vec![] };
+    diff.compare(&old, &new);
+
+    for change in &diff.changes {
+        println!("{change}");
+    }
+    if diff.changes.is_empty() {
+        println!("no changes");
+        return Ok(ExitCode::SUCCESS);
+    }
+    println!("{} changes", diff.changes.len());
+    Ok(if args.fail_on_change { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+}
+
+/// Collects one line per difference: `+` for additions, `-` for removals and `~` for changes.
+struct Diff {
+    tolerance: f32,
+    changes: Vec<String>,
+}
+
+impl Diff {
+    fn compare(&mut self, old: &B3D, new: &B3D) {
+        self.value("version", &old.version, &new.version);
+
+        let textures = old.textures.len().max(new.textures.len());
+        for index in 0..textures {
+            let what = format!("texture {index}");
+            match (old.textures.get(index), new.textures.get(index)) {
+                (Some(old), Some(new)) => {
+                    self.value(&format!("{what} file"), &old.file, &new.file);
+                    self.value(&format!("{what} flags"), &old.flags, &new.flags);
+                    self.value(&format!("{what} blend"), &old.blend, &new.blend);
+                    self.floats(&format!("{what} position"), &old.position, &new.position);
+                    self.floats(&format!("{what} scale"), &old.scale, &new.scale);
+                    self.floats(&format!("{what} rotation"), &[old.rotation], &[new.rotation]);
+                }
+                (Some(old), None) => self.changes.push(format!("- {what} {}", old.file)),
+                (None, Some(new)) => self.changes.push(format!("+ {what} {}", new.file)),
+                (None, None) => {}
+            }
+        }
+
+        let brushes = old.brushes.len().max(new.brushes.len());
+        for index in 0..brushes {
+            let what = format!("brush {index}");
+            match (old.brushes.get(index), new.brushes.get(index)) {
+                (Some(old), Some(new)) => {
+                    self.value(&format!("{what} name"), &old.name, &new.name);
+                    self.floats(&format!("{what} color"), &old.color, &new.color);
+                    self.floats(&format!("{what} shininess"), &[old.shininess], &[new.shininess]);
+                    self.value(&format!("{what} blend"), &old.blend, &new.blend);
+                    self.value(&format!("{what} fx"), &old.fx, &new.fx);
+                    self.value(&format!("{what} textures"), &old.texture_id, &new.texture_id);
+                }
+                (Some(old), None) => self.changes.push(format!("- {what} {}", old.name)),
+                (None, Some(new)) => self.changes.push(format!("+ {what} {}", new.name)),
+                (None, None) => {}
+            }
+        }
+
+        let old_nodes = paths(&old.node);
+        let new_nodes = paths(&new.node);
+        let new_by_path: HashMap<&str, &Node> = new_nodes.iter().map(|(path, node)| (path.as_str(), *node)).collect();
+        let old_by_path: HashMap<&str, &Node> = old_nodes.iter().map(|(path, node)| (path.as_str(), *node)).collect();
+        for (path, old) in &old_nodes {
+            match new_by_path.get(path.as_str()) {
+                Some(new) => self.node(path, old, new),
+                None => self.changes.push(format!("- node {path}")),
+            }
+        }
+        for (path, _) in &new_nodes {
+            if !old_by_path.contains_key(path.as_str()) {
+                self.changes.push(format!("+ node {path}"));
+            }
+        }
+    }
+
+    fn node(&mut self, path: &str, old: &Node, new: &Node) {
+        self.value(&format!("{path}: kind"), &old.kind(), &new.kind());
+        self.floats(&format!("{path}: position"), &old.position, &new.position);
+        self.floats(&format!("{path}: scale"), &old.scale, &new.scale);
+        self.floats(&format!("{path}: rotation"), &old.rotation, &new.rotation);
+
+        let (old_mesh, new_mesh) = (&old.mesh, &new.mesh);
+        self.value(&format!("{path}: mesh brush"), &old_mesh.brush_id, &new_mesh.brush_id);
+        self.value(&format!("{path}: vertex flags"), &old_mesh.vertices.flags, &new_mesh.vertices.flags);
+        self.value(
+            &format!("{path}: texture coordinate sets"),
+            &old_mesh.vertices.tex_coord_sets,
+            &new_mesh.vertices.tex_coord_sets,
+        );
+        let (old_vertices, new_vertices) = (&old_mesh.vertices.vertices, &new_mesh.vertices.vertices);
+        if old_vertices.len() != new_vertices.len() {
+            self.count(&format!("{path}: vertices"), old_vertices.len(), new_vertices.len());
+        } else {
+            let moved = old_vertices
+                .iter()
+                .zip(new_vertices)
+                .filter(|(old, new)| !self.close(&old.position, &new.position))
+                .count();
+            if moved > 0 {
+                self.changes.push(format!("~ {path}: {moved} vertex positions changed"));
+            }
+        }
+        let triangles = |node: &Node| node.mesh.triangles.iter().map(|tris| tris.indices.len()).sum();
+        self.count(&format!("{path}: triangles"), triangles(old), triangles(new));
+        let brushes = |node: &Node| node.mesh.triangles.iter().map(|tris| tris.brush_id).collect::<Vec<_>>();
+        self.value(&format!("{path}: triangle brushes"), &brushes(old), &brushes(new));
+
+        self.count(&format!("{path}: bone weights"), old.bones.len(), new.bones.len());
+
+        let flags = |node: &Node| node.keys.iter().map(|keys| keys.flags).collect::<Vec<_>>();
+        self.value(&format!("{path}: key channels"), &flags(old), &flags(new));
+        for (index, (old_keys, new_keys)) in old.keys.iter().zip(&new.keys).enumerate() {
+            let what = format!("{path}: keys {index}");
+            if old_keys.keys.len() != new_keys.keys.len() {
+                self.count(&what, old_keys.keys.len(), new_keys.keys.len());
+                continue;
+            }
+            let changed = old_keys
+                .keys
+                .iter()
+                .zip(&new_keys.keys)
+                .filter(|(old, new)| {
+                    old.frame != new.frame
+                        || !self.close(&old.position, &new.position)
+                        || !self.close(&old.scale, &new.scale)
+                        || !self.close(&old.rotation, &new.rotation)
+                })
+                .count();
+            if changed > 0 {
+                self.changes.push(format!("~ {what}: {changed} of {} keys changed", old_keys.keys.len()));
+            }
+        }
+
+        let animation = |node: &Node| (node.animation.flags, node.animation.frames, node.animation.fps);
+        self.value(&format!("{path}: animation"), &animation(old), &animation(new));
+        let sequences = |node: &Node| {
+            node.sequences
+                .iter()
+                .map(|sequence| (sequence.name.clone(), sequence.first_frame, sequence.last_frame))
+                .collect::<Vec<_>>()
+        };
+        self.value(&format!("{path}: sequences"), &sequences(old), &sequences(new));
+    }
+
+    fn value<T: PartialEq + Debug>(&mut self, what: &str, old: &T, new: &T) {
+        if old != new {
+            self.changes.push(format!("~ {what}: {old:?} -> {new:?}"));
+        }
+    }
+
+    fn floats(&mut self, what: &str, old: &[f32], new: &[f32]) {
+        if !self.close(old, new) {
+            self.changes.push(format!("~ {what}: {old:?} -> {new:?}"));
+        }
+    }
+
+    fn count(&mut self, what: &str, old: usize, new: usize) {
+        if old != new {
+            self.changes.push(format!("~ {what}: {old} -> {new} ({:+})", new as i64 - old as i64));
+        }
+    }
+
+    fn close(&self, old: &[f32], new: &[f32]) -> bool {
+        old.iter().zip(new).all(|(old, new)| (old - new).abs() <= self.tolerance || old.to_bits() == new.to_bits())
+    }
+}
+
+/// Lists every node with its path. Siblings sharing a name get `#n` appended from the second
+/// on, so each path is unique.
+fn paths(root: &Node) -> Vec<(String, &Node)> {
+    fn walk<'a>(node: &'a Node, path: String, nodes: &mut Vec<(String, &'a Node)>) {
+        nodes.push((path.clone(), node));
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for child in &node.children {
+            let count = seen.entry(child.name.as_str()).or_default();
+            let name = if *count == 0 { child.name.to_string() } else { format!("{}#{count}", child.name) };
+            *count += 1;
+            walk(child, format!("{path}/{name}"), nodes);
+        }
+    }
+    let mut nodes = vec![];
+    walk(root, root.name.to_string(), &mut nodes);
+    nodes
+}
@@ -0,0 +1,48 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use b3d::Node;
+use clap::ValueEnum;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The model to dump.
+    path: PathBuf,
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+    /// Leave out vertices and triangles, keeping the rest of each mesh.
+    #[arg(long)]
+    no_geometry: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Ron,
+}
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let mut b3d = crate::load(&args.path)?;
+    if args.no_geometry {
+        strip_geometry(&mut b3d.node);
+    }
+
+    let mut out = std::io::stdout().lock();
+    match args.format {
+        Format::Json => serde_json::to_writer_pretty(&mut out, &b3d)?,
+        Format::Ron => out.write_all(ron::ser::to_string_pretty(&b3d, Default::default())?.as_bytes())?,
+    }
+    writeln!(out)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn strip_geometry(node: &mut Node) {
+    node.mesh.vertices.vertices.clear();
+    for tris in &mut node.mesh.triangles {
+        tris.indices.clear();
+    }
+    for child in &mut node.children {
+        strip_geometry(child);
+    }
+}
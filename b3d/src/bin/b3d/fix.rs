@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::Context;
+use b3d::{repair, validate, ChunkTree, B3D};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The model to repair.
+    path: PathBuf,
+    /// Where to write the result, by default over the input.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Print the repairs without writing anything.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let mut data = std::fs::read(&args.path).with_context(|| format!("failed to read {}", args.path.display()))?;
+
+    let mut repairs = vec![];
+    if let Some((fixed, repair)) = repair::truncation(&data) {
+        data = fixed;
+        repairs.push(repair.to_string());
+    }
+    // Writing the model back puts every chunk in canonical order.
+    if let Ok(tree) = ChunkTree::read(&data) {
+        repairs.extend(validate::chunk_order(&tree).iter().map(|violation| format!("file: reordered {violation}")));
+    }
+
+    let mut b3d = B3D::read(&data).with_context(|| format!("failed to parse {}", args.path.display()))?;
+    repairs.extend(repair::references(&mut b3d).iter().map(ToString::to_string));
+    repairs.extend(repair::rotations(&mut b3d).iter().map(ToString::to_string));
+
+    for repair in &repairs {
+        println!("{repair}");
+    }
+    println!("{} repairs", repairs.len());
+
+    if !args.dry_run {
+        let output = args.output.as_ref().unwrap_or(&args.path);
+        std::fs::write(output, b3d.to_bytes()).with_context(|| format!("failed to write {}", output.display()))?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,184 @@
+//! Small edits made by patching the file's bytes, so everything else stays as the exporter
+//! wrote it.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::{bail, Context};
+use b3d::{ChunkTree, Tag, B3D};
+
+#[derive(clap::Args)]
+pub struct Output {
+    /// The model to edit.
+    path: PathBuf,
+    /// Where to write the result, by default over the input.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+pub struct SetVersionArgs {
+    #[command(flatten)]
+    file: Output,
+    /// The new version, either as stored (`1`) or as written in the spec (`0.01`).
+    version: String,
+}
+
+#[derive(clap::Args)]
+pub struct RenameRootArgs {
+    #[command(flatten)]
+    file: Output,
+    /// The new name of the root node.
+    name: String,
+}
+
+#[derive(clap::Args)]
+pub struct SetScaleArgs {
+    #[command(flatten)]
+    file: Output,
+    /// Factor the root node's scale is multiplied by.
+    factor: f32,
+}
+
+pub fn set_version(args: SetVersionArgs) -> anyhow::Result<ExitCode> {
+    let version = parse_version(&args.version)?;
+    edit(&args.file, |data, _| {
+        write_version(data, version)?;
+        Ok(format!("version set to {version}"))
+    })
+}
+
+pub fn rename_root(args: RenameRootArgs) -> anyhow::Result<ExitCode> {
+    if args.name.contains('\0') {
+        bail!("names can't contain NUL");
+    }
+    edit(&args.file, |data, root| {
+        let name_start = root.start + 8;
+        let name_len = name_len(data, name_start, &root)?;
+        let delta = args.name.len() as i64 - name_len as i64;
+        data.splice(name_start..name_start + name_len, args.name.bytes());
+        // The file and root node chunks grow or shrink with the name.
+        for chunk in [0, root.start] {
+            let size = u32::from_le_bytes(data[chunk + 4..chunk + 8].try_into()?) as i64 + delta;
+            data[chunk + 4..chunk + 8].copy_from_slice(&u32::try_from(size)?.to_le_bytes());
+        }
+        Ok(format!("root node renamed to {}", args.name))
+    })
+}
+
+pub fn set_scale(args: SetScaleArgs) -> anyhow::Result<ExitCode> {
+    edit(&args.file, |data, root| {
+        scale_root(data, &root, args.factor)?;
+        Ok(format!("root node scaled by {}", args.factor))
+    })
+}
+
+fn write_version(data: &mut [u8], version: u32) -> anyhow::Result<()> {
+    data.get_mut(8..12)
+        .context("file is too short to hold a version")?
+        .copy_from_slice(&version.to_le_bytes());
+    Ok(())
+}
+
+/// Multiplies the scale of the root `NODE` chunk spanning `root` by `factor`.
+fn scale_root(data: &mut [u8], root: &Range<usize>, factor: f32) -> anyhow::Result<()> {
+    let name_start = root.start + 8;
+    // The scale follows the name and the position, and the rotation follows it.
+    let scale = name_start + name_len(data, name_start, root)? + 1 + 12;
+    if scale + 12 + 16 > root.end {
+        bail!("root node is too short to hold a transform");
+    }
+    for axis in 0..3 {
+        let at = scale + axis * 4;
+        let bytes = data.get_mut(at..at + 4).context("root node is too short to hold a transform")?;
+        let value = f32::from_le_bytes((&*bytes).try_into()?) * factor;
+        bytes.copy_from_slice(&value.to_le_bytes());
+    }
+    Ok(())
+}
+
+/// Parses a stored version, or a spec version such as `0.01` which is stored as 1.
+fn parse_version(version: &str) -> anyhow::Result<u32> {
+    if let Ok(version) = version.parse::<u32>() {
+        return Ok(version);
+    }
+    let spec: f64 = version.parse().with_context(|| format!("invalid version {version}"))?;
+    let stored = (spec * 100.0).round();
+    if !(0.0..=u32::MAX as f64).contains(&stored) {
+        bail!("invalid version {version}");
+    }
+    Ok(stored as u32)
+}
+
+/// Returns the length of the name at `start`, which has to end within the `root` chunk.
+fn name_len(data: &[u8], start: usize, root: &Range<usize>) -> anyhow::Result<usize> {
+    data.get(start..root.end)
+        .and_then(|rest| rest.iter().position(|&byte| byte == 0))
+        .context("root node name is not terminated")
+}
+
+/// Applies `patch` to the file, given its bytes and the byte range of the root `NODE` chunk,
+/// then checks that the result still parses before writing it.
+fn edit<F>(file: &Output, patch: F) -> anyhow::Result<ExitCode>
+where
+    F: FnOnce(&mut Vec<u8>, Range<usize>) -> anyhow::Result<String>,
+{
+    let mut data = std::fs::read(&file.path).with_context(|| format!("failed to read {}", file.path.display()))?;
+    let tree = ChunkTree::read(&data).with_context(|| format!("failed to read chunks of {}", file.path.display()))?;
+    let Some(root) = tree.children_with(Tag::NODE).next() else {
+        bail!("{} has no root node", file.path.display());
+    };
+
+    let message = patch(&mut data, root.chunk.position as usize..root.chunk.next as usize)?;
+    B3D::read(&data).context("the edited file no longer parses")?;
+
+    let output: &Path = file.output.as_ref().unwrap_or(&file.path);
+    std::fs::write(output, &data).with_context(|| format!("failed to write {}", output.display()))?;
+    println!("{message}");
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use b3d::{Node, B3D};
+
+    use super::*;
+
+    fn root(data: &[u8]) -> Range<usize> {
+        let tree = ChunkTree::read(data).unwrap();
+        let root = tree.children_with(Tag::NODE).next().unwrap();
+        root.chunk.position as usize..root.chunk.next as usize
+    }
+
+    #[test]
+    fn scales_the_root_node() {
+        let b3d = B3D {
+            version: 1,
+            textures: vec![],
+            brushes: vec![],
+            node: Node {
+                name: "root".into(),
+                scale: [1.0, 2.0, 3.0],
+                ..Default::default()
+            },
+        };
+        let mut data = b3d.to_bytes();
+        let root = root(&data);
+        scale_root(&mut data, &root, 2.0).unwrap();
+        assert_eq!(B3D::read(&data).unwrap().node.scale, [2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn rejects_a_root_node_without_a_transform() {
+        // A root node holding only the name `ab`.
+        let mut data = b"BB3D\x0f\0\0\0\x01\0\0\0NODE\x03\0\0\0ab\0".to_vec();
+        let root = root(&data);
+        assert!(scale_root(&mut data, &root, 2.0).is_err());
+    }
+
+    #[test]
+    fn rejects_a_file_without_a_version() {
+        assert!(write_version(&mut b"BB3D\0\0\0\0".to_vec(), 1).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use b3d::{Node, NodeKind, B3D};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The model to describe.
+    path: PathBuf,
+}
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let b3d = crate::load(&args.path)?;
+
+    println!("{}", args.path.display());
+    println!("version: {}", b3d.version);
+
+    println!("textures: {}", b3d.textures.len());
+    for (index, texture) in b3d.textures.iter().enumerate() {
+        println!("  {index}: {} (flags {}, blend {})", texture.file, texture.flags, texture.blend);
+    }
+
+    println!("brushes: {}", b3d.brushes.len());
+    for (index, brush) in b3d.brushes.iter().enumerate() {
+        let textures: Vec<String> = brush.texture_id.iter().map(ToString::to_string).collect();
+        println!("  {index}: {} (textures: {})", brush.name, textures.join(", "));
+    }
+
+    print_animation(&b3d);
+
+    match b3d.bounds() {
+        Some((min, max)) => println!("bounds: {min:?} to {max:?}"),
+        None => println!("bounds: none"),
+    }
+
+    println!("nodes: {}", b3d.nodes().count());
+    print_node(&b3d.node, 1);
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_animation(b3d: &B3D) {
+    let Some(animation) = b3d.animation() else {
+        println!("animation: none");
+        return;
+    };
+    println!(
+        "animation: {} frames at {} fps ({:.3} s)",
+        animation.frames,
+        animation.fps_or_default(),
+        animation.duration_secs()
+    );
+    if let Some(range) = b3d.animation_frame_range() {
+        println!("  keys: frames {} to {}", range.start(), range.end());
+    }
+    for clip in b3d.clips() {
+        println!("  clip {}: frames {} to {} ({:?})", clip.name, clip.frames.start(), clip.frames.end(), clip.mode);
+    }
+}
+
+fn print_node(node: &Node, depth: usize) {
+    let mut details = vec![];
+    let mesh = &node.mesh;
+    match node.kind() {
+        NodeKind::Mesh => {
+            let triangles: usize = mesh.triangles.iter().map(|tris| tris.indices.len()).sum();
+            details.push(format!("mesh, {} vertices, {triangles} triangles", mesh.vertices.vertices.len()));
+        }
+        NodeKind::Bone => details.push(format!("bone, {} weights", node.bones.len())),
+        NodeKind::Pivot => details.push("pivot".to_owned()),
+    }
+    let keys: usize = node.keys.iter().map(|keys| keys.keys.len()).sum();
+    if keys > 0 {
+        details.push(format!("{keys} keys"));
+    }
+
+    println!("{:indent$}{} ({})", "", node.name, details.join(", "), indent = depth * 2);
+    for child in &node.children {
+        print_node(child, depth + 1);
+    }
+}
@@ -0,0 +1,144 @@
+//! Command line tools for inspecting and converting b3d files.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::Context;
+use b3d::B3D;
+use clap::{Parser, Subcommand};
+
+mod anim;
+mod convert;
+mod diff;
+mod dump;
+mod fix;
+mod header;
+mod info;
+mod merge;
+mod optimize;
+mod retex;
+mod stats;
+mod strip;
+mod textures;
+mod thumbnail;
+mod tree;
+mod validate;
+mod watch;
+
+#[derive(Parser)]
+#[command(name = "b3d", version, about = "Inspect and convert Blitz3D .b3d models")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print a summary of a model.
+    Info(info::Args),
+    /// Serialize the parsed model to stdout.
+    Dump(dump::Args),
+    /// Convert a model to glTF, OBJ, COLLADA or BVH.
+    Convert(convert::Args),
+    /// List the textures models use and check that they exist.
+    Textures(textures::Args),
+    /// Rewrite the texture paths stored in a model.
+    Retex(retex::Args),
+    /// Print the animation of a model, sampled per frame or as stored.
+    Anim(anim::Args),
+    /// Compare two models structurally.
+    Diff(diff::Args),
+    /// Weld vertices, drop degenerate triangles and unused materials, and reorder for the
+    /// vertex cache.
+    Optimize(optimize::Args),
+    /// Print the raw chunk structure of a file.
+    Tree(tree::Args),
+    /// Repair truncated files, broken references and non-unit rotations.
+    Fix(fix::Args),
+    /// Render a preview image of a model on the CPU.
+    Thumbnail(thumbnail::Args),
+    /// Total up the contents of many models.
+    Stats(stats::Args),
+    /// Remove animation or bones from a model.
+    Strip(strip::Args),
+    /// Combine several models under a new root node.
+    Merge(merge::Args),
+    /// Validate or convert models whenever they change.
+    Watch(watch::Args),
+    /// Change the version stored in a file.
+    SetVersion(header::SetVersionArgs),
+    /// Change the name of the root node.
+    RenameRoot(header::RenameRootArgs),
+    /// Multiply the scale of the root node.
+    SetScale(header::SetScaleArgs),
+    /// Check models for spec violations and broken references.
+    Validate(validate::Args),
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Info(args) => info::run(args),
+        Command::Dump(args) => dump::run(args),
+        Command::Convert(args) => convert::run(args),
+        Command::Textures(args) => textures::run(args),
+        Command::Retex(args) => retex::run(args),
+        Command::Anim(args) => anim::run(args),
+        Command::Diff(args) => diff::run(args),
+        Command::Optimize(args) => optimize::run(args),
+        Command::Tree(args) => tree::run(args),
+        Command::Fix(args) => fix::run(args),
+        Command::Thumbnail(args) => thumbnail::run(args),
+        Command::Stats(args) => stats::run(args),
+        Command::Strip(args) => strip::run(args),
+        Command::Merge(args) => merge::run(args),
+        Command::Watch(args) => watch::run(args),
+        Command::SetVersion(args) => header::set_version(args),
+        Command::RenameRoot(args) => header::rename_root(args),
+        Command::SetScale(args) => header::set_scale(args),
+        Command::Validate(args) => validate::run(args),
+    };
+    match result {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Reads and parses the model at `path`.
+fn load(path: &Path) -> anyhow::Result<B3D> {
+    let data = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    B3D::read(&data).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Expands `paths` into model files, searching directories for `.b3d` files, and their
+/// subdirectories too if `recursive` is set.
+fn collect_models(paths: &[PathBuf], recursive: bool) -> anyhow::Result<Vec<PathBuf>> {
+    let mut models = vec![];
+    for path in paths {
+        if path.is_dir() {
+            walk(path, recursive, &mut models).with_context(|| format!("failed to search {}", path.display()))?;
+        } else {
+            models.push(path.clone());
+        }
+    }
+    Ok(models)
+}
+
+fn walk(dir: &Path, recursive: bool, models: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.path());
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            if recursive {
+                walk(&path, recursive, models)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("b3d")) {
+            models.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{bail, Context};
+use b3d::{Name, B3D};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The models to combine. Each becomes a child of the new root, named after its file.
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+    /// Where to write the result.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Name of the new root node.
+    #[arg(long, default_value = "ROOT")]
+    name: String,
+    /// Offset as `x,y,z`. The first applies to the first input, the second to the second
+    /// input, and so on.
+    #[arg(long, value_parser = parse_vec3, allow_hyphen_values = true)]
+    translate: Vec<[f32; 3]>,
+    /// Uniform scale, applied to the inputs in order like `--translate`.
+    #[arg(long)]
+    scale: Vec<f32>,
+}
+
+fn parse_vec3(value: &str) -> Result<[f32; 3], String> {
+    let parts: Vec<&str> = value.split(',').collect();
+    let [x, y, z] = parts[..] else {
+        return Err("expected x,y,z".to_owned());
+    };
+    let parse = |part: &str| part.trim().parse::<f32>().map_err(|err| format!("{part}: {err}"));
+    Ok([parse(x)?, parse(y)?, parse(z)?])
+}
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    if args.translate.len() > args.inputs.len() || args.scale.len() > args.inputs.len() {
+        bail!("more --translate or --scale values than inputs");
+    }
+
+    let mut models = vec![];
+    for (index, path) in args.inputs.iter().enumerate() {
+        let mut model = crate::load(path)?;
+        let root = &mut model.node;
+        if let Some(stem) = path.file_stem() {
+            root.name = Name::from(stem.to_string_lossy().as_ref());
+        }
+        let scale = args.scale.get(index).copied().unwrap_or(1.0);
+        let offset = args.translate.get(index).copied().unwrap_or_default();
+        root.position = std::array::from_fn(|axis| root.position[axis] * scale + offset[axis]);
+        root.scale = root.scale.map(|s| s * scale);
+        models.push(model);
+    }
+
+    let merged = B3D::merge(&args.name, models);
+    println!(
+        "merged {} models: {} textures, {} brushes, {} nodes",
+        args.inputs.len(),
+        merged.textures.len(),
+        merged.brushes.len(),
+        merged.nodes().count()
+    );
+    std::fs::write(&args.output, merged.to_bytes())
+        .with_context(|| format!("failed to write {}", args.output.display()))?;
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::Context;
+use b3d::Name;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The model to rewrite.
+    path: PathBuf,
+    /// Where to write the result, by default over the input.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Drop directories, keeping only file names.
+    #[arg(long)]
+    strip_dirs: bool,
+    /// Replace each file's extension, e.g. `dds`.
+    #[arg(long, value_name = "EXT")]
+    replace_ext: Option<String>,
+    /// Lowercase every path.
+    #[arg(long)]
+    lowercase: bool,
+    /// Print the changes without writing anything.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let mut b3d = crate::load(&args.path)?;
+
+    let mut changed = 0;
+    for texture in &mut b3d.textures {
+        let file = rewrite(&texture.file, &args);
+        if file != texture.file {
+            println!("{} -> {file}", texture.file);
+            texture.file = Name::from(file);
+            changed += 1;
+        }
+    }
+    println!("{changed} of {} texture paths changed", b3d.textures.len());
+
+    if !args.dry_run {
+        let output = args.output.as_ref().unwrap_or(&args.path);
+        std::fs::write(output, b3d.to_bytes()).with_context(|| format!("failed to write {}", output.display()))?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Applies the requested edits to `file`, treating both `/` and `\` as separators since the
+/// paths usually come from Windows.
+fn rewrite(file: &str, args: &Args) -> String {
+    let mut file = file.to_owned();
+    if args.strip_dirs {
+        if let Some(separator) = file.rfind(['/', '\\']) {
+            file.drain(..=separator);
+        }
+    }
+    if let Some(extension) = &args.replace_ext {
+        let name_start = file.rfind(['/', '\\']).map_or(0, |separator| separator + 1);
+        if let Some(dot) = file[name_start..].rfind('.') {
+            file.truncate(name_start + dot);
+        }
+        file.push('.');
+        file.push_str(extension.trim_start_matches('.'));
+    }
+    if args.lowercase {
+        file = file.to_lowercase();
+    }
+    file
+}
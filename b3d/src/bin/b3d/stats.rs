@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use b3d::{Summary, B3D};
+use clap::ValueEnum;
+use serde_json::json;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Models or directories of models to summarize.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Search directories recursively instead of only their top level.
+    #[arg(short, long)]
+    recursive: bool,
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// How many of the models with the most triangles to list.
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let mut models = vec![];
+    let mut failed = vec![];
+    for path in crate::collect_models(&args.paths, args.recursive)? {
+        let summary = std::fs::read(&path).map_err(b3d::Error::from).and_then(|data| B3D::scan(&data));
+        match summary {
+            Ok(summary) => models.push((path, summary)),
+            Err(err) => failed.push((path, err.to_string())),
+        }
+    }
+
+    let mut total = Summary::default();
+    for (_, summary) in &models {
+        total.bytes += summary.bytes;
+        total.textures += summary.textures;
+        total.brushes += summary.brushes;
+        total.nodes += summary.nodes;
+        total.meshes += summary.meshes;
+        total.bones += summary.bones;
+        total.vertices += summary.vertices;
+        total.triangles += summary.triangles;
+        total.keys += summary.keys;
+    }
+    models.sort_by(|(a_path, a), (b_path, b)| b.triangles.cmp(&a.triangles).then_with(|| a_path.cmp(b_path)));
+    let top = &models[..models.len().min(args.top)];
+
+    match args.format {
+        Format::Text => {
+            println!("models: {} ({} failed)", models.len() + failed.len(), failed.len());
+            println!("bytes: {}", total.bytes);
+            println!("textures: {}, brushes: {}", total.textures, total.brushes);
+            println!("nodes: {}, meshes: {}, bones: {}", total.nodes, total.meshes, total.bones);
+            println!("vertices: {}, triangles: {}, keys: {}", total.vertices, total.triangles, total.keys);
+            println!("most triangles:");
+            for (path, summary) in top {
+                println!("  {:>10}  {}", summary.triangles, path.display());
+            }
+            for (path, err) in &failed {
+                println!("failed: {}: {err}", path.display());
+            }
+        }
+        Format::Json => {
+            let report = json!({
+                "models": models.len() + failed.len(),
+                "total": {
+                    "bytes": total.bytes,
+                    "textures": total.textures,
+                    "brushes": total.brushes,
+                    "nodes": total.nodes,
+                    "meshes": total.meshes,
+                    "bones": total.bones,
+                    "vertices": total.vertices,
+                    "triangles": total.triangles,
+                    "keys": total.keys,
+                },
+                "top": top.iter().map(|(path, summary)| json!({ "path": path, "summary": summary })).collect::<Vec<_>>(),
+                "failed": failed.iter().map(|(path, err)| json!({ "path": path, "error": err })).collect::<Vec<_>>(),
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{bail, Context};
+use b3d::{Animation, Node};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The model to strip.
+    path: PathBuf,
+    /// Where to write the result, by default over the input.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Remove `KEYS`, `ANIM` and `SEQS` chunks.
+    #[arg(long)]
+    animations: bool,
+    /// Remove `BONE` chunks, leaving meshes in their rest pose.
+    #[arg(long)]
+    bones: bool,
+}
+
+/// What was removed, summed over every node.
+#[derive(Default)]
+struct Removed {
+    keys: usize,
+    animations: usize,
+    sequences: usize,
+    weights: usize,
+}
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    if !args.animations && !args.bones {
+        bail!("nothing to strip, pass --animations and/or --bones");
+    }
+    let mut b3d = crate::load(&args.path)?;
+
+    let mut removed = Removed::default();
+    strip(&mut b3d.node, &args, &mut removed);
+    if args.animations {
+        println!(
+            "removed {} keys, {} ANIM and {} SEQS chunks",
+            removed.keys, removed.animations, removed.sequences
+        );
+    }
+    if args.bones {
+        println!("removed {} bone weights", removed.weights);
+    }
+
+    let output = args.output.as_ref().unwrap_or(&args.path);
+    std::fs::write(output, b3d.to_bytes()).with_context(|| format!("failed to write {}", output.display()))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn strip(node: &mut Node, args: &Args, removed: &mut Removed) {
+    if args.animations {
+        removed.keys += node.keys.iter().map(|keys| keys.keys.len()).sum::<usize>();
+        node.keys.clear();
+        if node.animation.frames != 0 || node.animation.fps != 0.0 {
+            removed.animations += 1;
+        }
+        node.animation = Animation::default();
+        removed.sequences += node.sequences.len();
+        node.sequences.clear();
+    }
+    if args.bones {
+        removed.weights += node.bones.len();
+        node.bones.clear();
+    }
+    for child in &mut node.children {
+        strip(child, args, removed);
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::Context;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Models to list the textures of. Directories are searched for `.b3d` files.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Check that every texture exists on disk, failing if any doesn't.
+    #[arg(long)]
+    check_files: bool,
+    /// Directory texture paths are relative to, by default the directory of each model.
+    #[arg(long)]
+    root: Option<PathBuf>,
+    /// Write the textures found on disk to this file, one path per line, for build systems
+    /// to track as dependencies.
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+}
+
+/// Where a texture path led.
+enum Lookup {
+    Found(PathBuf),
+    /// Only a file whose name differs in case exists, which breaks on case-sensitive systems.
+    WrongCase(PathBuf),
+    /// Nothing matched, with files elsewhere under the root sharing the texture's file name.
+    Missing(Vec<PathBuf>),
+}
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let mut found = BTreeSet::new();
+    let mut missing = 0;
+
+    for path in crate::collect_models(&args.paths, true)? {
+        let b3d = crate::load(&path)?;
+        let root = match &args.root {
+            Some(root) => root.clone(),
+            None => path.parent().unwrap_or(Path::new("")).to_owned(),
+        };
+
+        println!("{}: {} textures", path.display(), b3d.textures.len());
+        for texture in &b3d.textures {
+            if !args.check_files && args.manifest.is_none() {
+                println!("  {}", texture.file);
+                continue;
+            }
+            match lookup(&root, &texture.file) {
+                Lookup::Found(file) => {
+                    println!("  {} -> {}", texture.file, file.display());
+                    found.insert(file);
+                }
+                Lookup::WrongCase(file) => {
+                    println!("  {}: missing, did you mean {}?", texture.file, file.display());
+                    missing += 1;
+                }
+                Lookup::Missing(similar) if similar.is_empty() => {
+                    println!("  {}: missing", texture.file);
+                    missing += 1;
+                }
+                Lookup::Missing(similar) => {
+                    let similar: Vec<String> = similar.iter().map(|file| file.display().to_string()).collect();
+                    println!("  {}: missing, did you mean {}?", texture.file, similar.join(" or "));
+                    missing += 1;
+                }
+            }
+        }
+    }
+
+    if let Some(manifest) = &args.manifest {
+        let contents: String = found.iter().map(|file| format!("{}\n", file.display())).collect();
+        std::fs::write(manifest, contents).with_context(|| format!("failed to write {}", manifest.display()))?;
+    }
+
+    if args.check_files && missing > 0 {
+        println!("{missing} textures missing");
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Resolves `file` the way Blitz3D does, trying the full path under `root` before the bare
+/// file name next to the model.
+fn lookup(root: &Path, file: &str) -> Lookup {
+    // Files exported on Windows use backslashes and may start with a drive letter.
+    let relative = PathBuf::from(file.replace('\\', "/"));
+    let relative: PathBuf = relative
+        .components()
+        .filter(|c| matches!(c, Component::Normal(c) if !c.to_string_lossy().ends_with(':')))
+        .collect();
+    let name = relative.file_name().map(PathBuf::from).unwrap_or_default();
+
+    let candidates = [root.join(&relative), root.join(&name)];
+    if let Some(found) = candidates.iter().find(|candidate| candidate.is_file()) {
+        return Lookup::Found(found.clone());
+    }
+    let insensitive = [&relative, &name].into_iter().find_map(|path| find_ignoring_case(root, path));
+    if let Some(found) = insensitive {
+        return Lookup::WrongCase(found);
+    }
+
+    let mut similar = vec![];
+    if let Some(name) = name.to_str() {
+        find_named(root, name, &mut similar);
+    }
+    Lookup::Missing(similar)
+}
+
+/// Follows `relative` from `dir`, matching each component regardless of case.
+fn find_ignoring_case(dir: &Path, relative: &Path) -> Option<PathBuf> {
+    let mut path = dir.to_owned();
+    for component in relative.iter() {
+        let component = component.to_str()?;
+        let entry = std::fs::read_dir(&path)
+            .ok()?
+            .flatten()
+            .find(|entry| entry.file_name().to_str().is_some_and(|n| n.eq_ignore_ascii_case(component)))?;
+        path = entry.path();
+    }
+    path.is_file().then_some(path)
+}
+
+/// Collects files under `dir` named `name`, regardless of case.
+fn find_named(dir: &Path, name: &str, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            find_named(&path, name, found);
+        } else if path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.eq_ignore_ascii_case(name)) {
+            found.push(path);
+        }
+    }
+}
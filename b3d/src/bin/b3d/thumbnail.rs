@@ -0,0 +1,268 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The model to render.
+    path: PathBuf,
+    /// Where to write the PNG.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Width and height of the image in pixels.
+    #[arg(long, default_value_t = 256)]
+    size: u32,
+    #[arg(long, value_enum, default_value_t = Shading::Flat)]
+    shading: Shading,
+    /// Degrees the camera is turned around the model, from the front.
+    #[arg(long, default_value_t = 35.0)]
+    yaw: f32,
+    /// Degrees the camera looks down on the model.
+    #[arg(long, default_value_t = 25.0)]
+    pitch: f32,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Shading {
+    /// Brush colors lit from the camera's upper left.
+    Flat,
+    /// Normals mapped to colors, showing smoothing and flipped normals.
+    Normal,
+}
+
+/// Pixels rendered per output pixel along each axis, averaged to smooth edges.
+const SUPERSAMPLING: usize = 2;
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    if args.size == 0 {
+        bail!("--size must be positive");
+    }
+    let b3d = crate::load(&args.path)?;
+
+    let size = args.size as usize * SUPERSAMPLING;
+    let camera = Camera::new(args.yaw, args.pitch);
+    let mut triangles = vec![];
+    for mesh in b3d.extract_meshes() {
+        let world = |p: [f32; 3]| transform(&mesh.transform, p);
+        let color = mesh.material.color;
+        for face in mesh.indices.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|i| camera.rotate(world(mesh.positions[face[i] as usize])));
+            let normals = [0, 1, 2].map(|i| {
+                let normal = mesh.normals[face[i] as usize];
+                let tip = world([0, 1, 2].map(|c| mesh.positions[face[i] as usize][c] + normal[c]));
+                let base = world(mesh.positions[face[i] as usize]);
+                normalize(camera.rotate([0, 1, 2].map(|c| tip[c] - base[c])))
+            });
+            triangles.push(Triangle { corners, normals, color });
+        }
+    }
+
+    let mut image = Image::new(size);
+    if let Some(frame) = Frame::fit(&triangles, size) {
+        for triangle in &triangles {
+            image.draw(triangle, &frame, args.shading);
+        }
+    }
+
+    let file = File::create(&args.output).with_context(|| format!("failed to create {}", args.output.display()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), args.size, args.size);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.downsample())?;
+    writer.finish()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Applies a column-major matrix to a point.
+fn transform(m: &[f32; 16], p: [f32; 3]) -> [f32; 3] {
+    [0, 1, 2].map(|r| m[r] * p[0] + m[4 + r] * p[1] + m[8 + r] * p[2] + m[12 + r])
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if length > 0.0 {
+        v.map(|c| c / length)
+    } else {
+        v
+    }
+}
+
+/// An orthographic camera orbiting the model. Blitz3D is left-handed, so after rotating, x
+/// points right, y up and z away from the viewer.
+struct Camera {
+    yaw: (f32, f32),
+    pitch: (f32, f32),
+}
+
+impl Camera {
+    fn new(yaw: f32, pitch: f32) -> Self {
+        Self {
+            yaw: yaw.to_radians().sin_cos(),
+            pitch: pitch.to_radians().sin_cos(),
+        }
+    }
+
+    fn rotate(&self, [x, y, z]: [f32; 3]) -> [f32; 3] {
+        let (sin, cos) = self.yaw;
+        let (x, z) = (x * cos - z * sin, x * sin + z * cos);
+        let (sin, cos) = self.pitch;
+        let (y, z) = (y * cos - z * sin, y * sin + z * cos);
+        [x, y, z]
+    }
+}
+
+struct Triangle {
+    /// Corners in view space.
+    corners: [[f32; 3]; 3],
+    /// Normals in view space.
+    normals: [[f32; 3]; 3],
+    color: [f32; 4],
+}
+
+/// Maps view space onto pixels, centering the model and leaving a margin around it.
+struct Frame {
+    center: [f32; 2],
+    scale: f32,
+    half: f32,
+}
+
+impl Frame {
+    fn fit(triangles: &[Triangle], size: usize) -> Option<Self> {
+        let mut corners = triangles.iter().flat_map(|triangle| triangle.corners).filter(|c| c.iter().all(|v| v.is_finite()));
+        let first = corners.next()?;
+        let (mut min, mut max) = ([first[0], first[1]], [first[0], first[1]]);
+        for corner in corners {
+            for axis in 0..2 {
+                min[axis] = min[axis].min(corner[axis]);
+                max[axis] = max[axis].max(corner[axis]);
+            }
+        }
+        let extent = (max[0] - min[0]).max(max[1] - min[1]).max(f32::EPSILON);
+        let half = size as f32 / 2.0;
+        Some(Self {
+            center: [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0],
+            scale: size as f32 * 0.9 / extent,
+            half,
+        })
+    }
+
+    fn pixel(&self, [x, y, z]: [f32; 3]) -> [f32; 3] {
+        [
+            self.half + (x - self.center[0]) * self.scale,
+            self.half - (y - self.center[1]) * self.scale,
+            z,
+        ]
+    }
+}
+
+struct Image {
+    size: usize,
+    pixels: Vec<[f32; 4]>,
+    depth: Vec<f32>,
+}
+
+impl Image {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            pixels: vec![[0.0; 4]; size * size],
+            depth: vec![f32::INFINITY; size * size],
+        }
+    }
+
+    fn draw(&mut self, triangle: &Triangle, frame: &Frame, shading: Shading) {
+        let [a, b, c] = triangle.corners.map(|corner| frame.pixel(corner));
+        let area = edge(a, b, c);
+        if area == 0.0 || !area.is_finite() {
+            return;
+        }
+
+        let face = normalize(cross(sub(triangle.corners[1], triangle.corners[0]), sub(triangle.corners[2], triangle.corners[0])));
+        let light = normalize([-0.4, 0.6, -0.7]);
+        let min_x = a[0].min(b[0]).min(c[0]).floor().max(0.0) as usize;
+        let max_x = (a[0].max(b[0]).max(c[0]).ceil() as usize).min(self.size);
+        let min_y = a[1].min(b[1]).min(c[1]).floor().max(0.0) as usize;
+        let max_y = (a[1].max(b[1]).max(c[1]).ceil() as usize).min(self.size);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = [x as f32 + 0.5, y as f32 + 0.5, 0.0];
+                let weights = [edge(b, c, p) / area, edge(c, a, p) / area, edge(a, b, p) / area];
+                if weights.iter().any(|&w| w < 0.0) {
+                    continue;
+                }
+                let depth = weights[0] * a[2] + weights[1] * b[2] + weights[2] * c[2];
+                let index = y * self.size + x;
+                if depth >= self.depth[index] {
+                    continue;
+                }
+                self.depth[index] = depth;
+
+                let normal = normalize([0, 1, 2].map(|axis| {
+                    (0..3).map(|corner| weights[corner] * triangle.normals[corner][axis]).sum::<f32>()
+                }));
+                let color = match shading {
+                    Shading::Flat => {
+                        // Both sides are lit alike, as winding varies between exporters.
+                        let lambert = dot(face, light).abs();
+                        let [r, g, b, _] = triangle.color;
+                        [r, g, b].map(|channel| channel * (0.25 + 0.75 * lambert))
+                    }
+                    Shading::Normal => normal.map(|n| n * 0.5 + 0.5),
+                };
+                self.pixels[index] = [color[0], color[1], color[2], 1.0];
+            }
+        }
+    }
+
+    /// Averages blocks of [`SUPERSAMPLING`] pixels into 8-bit RGBA.
+    fn downsample(&self) -> Vec<u8> {
+        let size = self.size / SUPERSAMPLING;
+        let mut bytes = Vec::with_capacity(size * size * 4);
+        for y in 0..size {
+            for x in 0..size {
+                let mut sum = [0.0; 4];
+                for dy in 0..SUPERSAMPLING {
+                    for dx in 0..SUPERSAMPLING {
+                        let pixel = self.pixels[(y * SUPERSAMPLING + dy) * self.size + x * SUPERSAMPLING + dx];
+                        // Weight colors by coverage so edges don't darken against the
+                        // transparent background.
+                        for channel in 0..3 {
+                            sum[channel] += pixel[channel] * pixel[3];
+                        }
+                        sum[3] += pixel[3];
+                    }
+                }
+                let alpha = sum[3] / (SUPERSAMPLING * SUPERSAMPLING) as f32;
+                for channel in 0..3 {
+                    let value = if sum[3] > 0.0 { sum[channel] / sum[3] } else { 0.0 };
+                    bytes.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+                }
+                bytes.push((alpha * 255.0).round() as u8);
+            }
+        }
+        bytes
+    }
+}
+
+/// Twice the signed area of `a`, `b`, `p` in the image plane.
+fn edge(a: [f32; 3], b: [f32; 3], p: [f32; 3]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::Context;
+use b3d::{ChunkTree, Tag};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The model to inspect.
+    path: PathBuf,
+    /// Hexdump the contents of chunks with unknown tags.
+    #[arg(long)]
+    hex: bool,
+    /// Bytes of each chunk to hexdump.
+    #[arg(long, default_value_t = 256)]
+    hex_limit: usize,
+}
+
+const KNOWN: [Tag; 12] = [
+    Tag::BB3D,
+    Tag::TEXS,
+    Tag::BRUS,
+    Tag::NODE,
+    Tag::MESH,
+    Tag::VRTS,
+    Tag::TRIS,
+    Tag::BONE,
+    Tag::KEYS,
+    Tag::ANIM,
+    Tag::SEQS,
+    Tag::PIVO,
+];
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let data = std::fs::read(&args.path).with_context(|| format!("failed to read {}", args.path.display()))?;
+    let tree = ChunkTree::read(&data).with_context(|| format!("failed to read chunks of {}", args.path.display()))?;
+    print_chunk(&tree, &data, &args, 0);
+    let end = tree.chunk.next as usize;
+    if end < data.len() {
+        println!("{} trailing bytes at offset {end}", data.len() - end);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_chunk(tree: &ChunkTree, data: &[u8], args: &Args, depth: usize) {
+    let chunk = &tree.chunk;
+    let start = chunk.position as usize + 8;
+    let contents = &data[start..(chunk.next as usize).min(data.len())];
+
+    let detail = match chunk.tag {
+        Tag::BB3D => contents.get(..4).map(|v| format!(", version {}", u32::from_le_bytes([v[0], v[1], v[2], v[3]]))),
+        Tag::NODE => {
+            let name = contents.split(|&byte| byte == 0).next().unwrap_or_default();
+            Some(format!(", {:?}", String::from_utf8_lossy(name)))
+        }
+        _ => None,
+    };
+    println!(
+        "{:indent$}{} at {} ({:#x}), {} bytes{}",
+        "",
+        chunk.tag,
+        chunk.position,
+        chunk.position,
+        chunk.size,
+        detail.unwrap_or_default(),
+        indent = depth * 2
+    );
+
+    if args.hex && !KNOWN.contains(&chunk.tag) {
+        hexdump(&contents[..contents.len().min(args.hex_limit)], start, depth + 1);
+        if contents.len() > args.hex_limit {
+            println!("{:indent$}... {} more bytes", "", contents.len() - args.hex_limit, indent = (depth + 1) * 2);
+        }
+    }
+    for child in &tree.children {
+        print_chunk(child, data, args, depth + 1);
+    }
+}
+
+/// Prints 16 bytes per line, with their file offset and ASCII rendering.
+fn hexdump(bytes: &[u8], offset: usize, depth: usize) {
+    for (line, row) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = row.iter().map(|byte| format!("{byte:02x}")).collect();
+        let ascii: String = row
+            .iter()
+            .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+            .collect();
+        println!("{:indent$}{:08x}  {:<47}  {ascii}", "", offset + line * 16, hex.join(" "), indent = depth * 2);
+    }
+}
@@ -0,0 +1,80 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use b3d::{validate, ChunkTree, B3D};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Models to check. Directories are searched for `.b3d` files.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Fail on warnings as well as errors.
+    #[arg(long)]
+    deny_warnings: bool,
+}
+
+/// What was found in one file.
+#[derive(Default)]
+pub struct Findings {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let paths = crate::collect_models(&args.paths, true)?;
+    let (mut failed, mut warned) = (0, 0);
+
+    for path in &paths {
+        let findings = check(path);
+        findings.print(path);
+        if !findings.errors.is_empty() {
+            failed += 1;
+        } else if !findings.warnings.is_empty() {
+            warned += 1;
+        }
+    }
+
+    println!("checked {} files: {failed} with errors, {warned} with only warnings", paths.len());
+    let ok = failed == 0 && (warned == 0 || !args.deny_warnings);
+    Ok(if ok { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+impl Findings {
+    pub fn print(&self, path: &Path) {
+        if self.errors.is_empty() && self.warnings.is_empty() {
+            println!("{}: ok", path.display());
+            return;
+        }
+        println!("{}:", path.display());
+        for error in &self.errors {
+            println!("  error: {error}");
+        }
+        for warning in &self.warnings {
+            println!("  warning: {warning}");
+        }
+    }
+}
+
+pub fn check(path: &Path) -> Findings {
+    let mut findings = Findings::default();
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(err) => {
+            findings.errors.push(format!("failed to read: {err}"));
+            return findings;
+        }
+    };
+
+    match ChunkTree::read(&data) {
+        Ok(tree) => findings.errors.extend(validate::chunk_order(&tree).iter().map(ToString::to_string)),
+        Err(err) => findings.errors.push(format!("failed to read chunks: {err}")),
+    }
+    match B3D::read(&data) {
+        Ok(b3d) => {
+            findings.errors.extend(validate::references(&b3d).iter().map(ToString::to_string));
+            findings.warnings.extend(validate::sanity(&b3d).iter().map(ToString::to_string));
+        }
+        Err(err) => findings.errors.push(format!("failed to parse: {err}")),
+    }
+    findings
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime};
+
+use anyhow::bail;
+use b3d::export::gltf::GltfOptions;
+use clap::ValueEnum;
+
+use crate::convert::{self, Format};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The directory to watch, including its subdirectories.
+    path: PathBuf,
+    /// Validate models when they change.
+    #[arg(long)]
+    validate: bool,
+    /// Convert models when they change, as `format:directory`, e.g. `glb:out/`.
+    #[arg(long, value_parser = parse_convert)]
+    convert: Option<(Format, PathBuf)>,
+    /// Milliseconds between checks for changes.
+    #[arg(long, default_value_t = 500)]
+    interval: u64,
+}
+
+fn parse_convert(value: &str) -> Result<(Format, PathBuf), String> {
+    let (format, dir) = value.split_once(':').ok_or("expected format:directory")?;
+    Ok((Format::from_str(format, true)?, PathBuf::from(dir)))
+}
+
+/// When a file was last modified, and its size.
+type Stamp = (SystemTime, u64);
+
+pub fn run(args: Args) -> anyhow::Result<ExitCode> {
+    if !args.validate && args.convert.is_none() {
+        bail!("nothing to do, pass --validate and/or --convert");
+    }
+    if let Some((_, dir)) = &args.convert {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut seen = snapshot(&args.path)?;
+    let mut pending: HashMap<PathBuf, Stamp> = HashMap::new();
+    println!("watching {} models in {}", seen.len(), args.path.display());
+
+    loop {
+        std::thread::sleep(Duration::from_millis(args.interval));
+        let current = snapshot(&args.path)?;
+        for (path, &stamp) in &current {
+            if seen.get(path) == Some(&stamp) {
+                pending.remove(path);
+                continue;
+            }
+            // Exporters write in several steps, so wait for a file to stay the same for one
+            // interval before reading it.
+            if pending.get(path) == Some(&stamp) {
+                pending.remove(path);
+                seen.insert(path.clone(), stamp);
+                process(path, &args);
+            } else {
+                pending.insert(path.clone(), stamp);
+            }
+        }
+        seen.retain(|path, _| current.contains_key(path));
+        pending.retain(|path, _| current.contains_key(path));
+    }
+}
+
+fn snapshot(dir: &Path) -> anyhow::Result<HashMap<PathBuf, Stamp>> {
+    let mut stamps = HashMap::new();
+    for path in crate::collect_models(&[dir.to_owned()], true)? {
+        // Files may disappear between listing and reading them.
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            stamps.insert(path, (metadata.modified()?, metadata.len()));
+        }
+    }
+    Ok(stamps)
+}
+
+fn process(path: &Path, args: &Args) {
+    if args.validate {
+        crate::validate::check(path).print(path);
+    }
+    if let Some((format, dir)) = &args.convert {
+        let output = dir.join(path.file_stem().unwrap_or_default()).with_extension(format.extension());
+        let result = crate::load(path)
+            .and_then(|b3d| convert::export(&b3d, *format, &output, &GltfOptions::default()));
+        match result {
+            Ok(()) => println!("{}: converted to {}", path.display(), output.display()),
+            Err(err) => println!("{}: error: {err:#}", path.display()),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Conversions into and out of Blitz3D's coordinate conventions.
+//!
+//! Blitz3D uses a left-handed, Y-up space with clockwise front faces. Rotations are stored as
+//! `w, x, y, z` quaternions that rotate the opposite way to the usual (`q v q*`) convention.
+//! The helpers here return standard `[x, y, z, w]` quaternions, the order used by glTF and glam.
+
+use crate::utils::{Vec3, Vec4};
+
+/// Converts a b3d rotation to a standard quaternion in the same left-handed space.
+pub fn rotation(rotation: Vec4) -> Vec4 {
+    let [w, x, y, z] = rotation;
+    [-x, -y, -z, w]
+}
+
+/// Converts a position, direction or normal to right-handed, Y-up space by flipping Z.
+pub fn to_right_handed(v: Vec3) -> Vec3 {
+    [v[0], v[1], -v[2]]
+}
+
+/// Converts a b3d rotation to a standard quaternion in right-handed, Y-up space.
+pub fn rotation_to_right_handed(rotation: Vec4) -> Vec4 {
+    let [w, x, y, z] = rotation;
+    [x, y, -z, w]
+}
+
+/// Converts a position, direction or normal from right-handed, Y-up space by flipping Z, the
+/// inverse of [`to_right_handed`].
+pub fn to_left_handed(v: Vec3) -> Vec3 {
+    [v[0], v[1], -v[2]]
+}
+
+/// Turns the `-0` left by flipping an axis of a zero component into `0`, so text formats don't
+/// write it out as `-0`.
+pub fn positive_zero(value: f32) -> f32 {
+    value + 0.0
+}
+
+/// Converts a standard `[x, y, z, w]` quaternion in right-handed, Y-up space to a b3d rotation,
+/// the inverse of [`rotation_to_right_handed`].
+pub fn rotation_to_left_handed(rotation: Vec4) -> Vec4 {
+    let [x, y, z, w] = rotation;
+    [w, x, y, -z]
+}
+
+/// Reverses a triangle's winding, turning Blitz3D's clockwise front faces counter-clockwise
+/// once the geometry has been flipped into right-handed space.
+pub fn triangle_to_right_handed(triangle: [u32; 3]) -> [u32; 3] {
+    [triangle[0], triangle[2], triangle[1]]
+}
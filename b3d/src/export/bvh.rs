@@ -0,0 +1,142 @@
+//! Converts the skeleton and animation of a parsed [`B3D`] into a Biovision Hierarchy (BVH)
+//! file for motion capture and retargeting tools.
+//!
+//! The hierarchy is made of the bones and animated nodes along with the nodes joining them to
+//! the root, so static meshes and pivots hanging off the skeleton are left out. Each joint is
+//! offset by its rest position and rotates through `Zrotation Xrotation Yrotation` channels; the
+//! root and joints with position keys get position channels too. BVH has no scale, so scale
+//! keys are dropped. Every frame between the first and last key is sampled, or just the rest
+//! pose if the model has no keys.
+
+use crate::math;
+use crate::utils::{Vec3, Vec4};
+use crate::{coords, Node, NodeKind, B3D};
+
+/// Options for the BVH export.
+#[derive(Debug, Clone)]
+pub struct BvhOptions {
+    /// Convert from Blitz3D's left-handed space into the right-handed space most tools expect.
+    pub convert_coordinates: bool,
+    /// Uniform factor applied to offsets and positions.
+    pub scale: f32,
+}
+
+impl Default for BvhOptions {
+    fn default() -> Self {
+        Self {
+            convert_coordinates: true,
+            scale: 1.0,
+        }
+    }
+}
+
+/// Converts the skeleton and animation of `b3d` into a BVH file.
+pub fn export(b3d: &B3D, options: &BvhOptions) -> String {
+    let mut exporter = Exporter {
+        options,
+        bvh: "HIERARCHY\n".to_owned(),
+        joints: vec![],
+    };
+    exporter.joint(&b3d.node, 0);
+
+    let frames = b3d.animation_frame_range().unwrap_or(0..=0);
+    let fps = b3d.animation().map_or(60.0, |animation| animation.fps_or_default());
+    let mut bvh = exporter.bvh;
+    bvh.push_str("MOTION\n");
+    bvh.push_str(&format!("Frames: {}\n", frames.clone().count()));
+    bvh.push_str(&format!("Frame Time: {}\n", 1.0 / fps));
+    for frame in frames {
+        let mut values = vec![];
+        for joint in &exporter.joints {
+            let (position, _, rotation) = joint.node.pose_at(frame as f32);
+            if joint.positions {
+                values.extend(offset(options, position));
+            }
+            values.extend(euler_zxy(rotation, options.convert_coordinates));
+        }
+        let values: Vec<String> = values.iter().map(|&value| coords::positive_zero(value).to_string()).collect();
+        bvh.push_str(&values.join(" "));
+        bvh.push('\n');
+    }
+    bvh
+}
+
+struct Joint<'a> {
+    node: &'a Node,
+    /// Whether the joint has position channels as well as rotation ones.
+    positions: bool,
+}
+
+struct Exporter<'a> {
+    options: &'a BvhOptions,
+    bvh: String,
+    /// Joints in the order their channels appear in each frame.
+    joints: Vec<Joint<'a>>,
+}
+
+impl<'a> Exporter<'a> {
+    fn joint(&mut self, node: &'a Node, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let positions = depth == 0 || node.keys.iter().any(|keys| keys.flags & 1 != 0 && !keys.keys.is_empty());
+        let keyword = if depth == 0 { "ROOT" } else { "JOINT" };
+        let mut name = sanitize(&node.name);
+        if name.is_empty() {
+            name = format!("Joint{}", self.joints.len());
+        }
+
+        self.bvh.push_str(&format!("{indent}{keyword} {name}\n{indent}{{\n"));
+        let [x, y, z] = offset(self.options, node.position);
+        self.bvh.push_str(&format!("{indent}  OFFSET {x} {y} {z}\n"));
+        if positions {
+            self.bvh.push_str(&format!("{indent}  CHANNELS 6 Xposition Yposition Zposition Zrotation Xrotation Yrotation\n"));
+        } else {
+            self.bvh.push_str(&format!("{indent}  CHANNELS 3 Zrotation Xrotation Yrotation\n"));
+        }
+        self.joints.push(Joint { node, positions });
+
+        let children: Vec<_> = node.children.iter().filter(|child| in_skeleton(child)).collect();
+        if children.is_empty() {
+            // BVH leaves end in a site marking where the last bone ends, which b3d doesn't store.
+            self.bvh.push_str(&format!("{indent}  End Site\n{indent}  {{\n{indent}    OFFSET 0 0 0\n{indent}  }}\n"));
+        }
+        for child in children {
+            self.joint(child, depth + 1);
+        }
+        self.bvh.push_str(&format!("{indent}}}\n"));
+    }
+}
+
+/// Returns whether the node or any of its descendants is a bone or animated.
+fn in_skeleton(node: &Node) -> bool {
+    node.iter().any(|node| node.kind() == NodeKind::Bone || node.is_animated())
+}
+
+fn sanitize(name: &str) -> String {
+    name.trim().replace(char::is_whitespace, "_")
+}
+
+fn offset(options: &BvhOptions, position: Vec3) -> Vec3 {
+    let position = if options.convert_coordinates {
+        coords::to_right_handed(position)
+    } else {
+        position
+    };
+    position.map(|c| coords::positive_zero(c * options.scale))
+}
+
+/// Decomposes a b3d rotation into the Z, X and Y angles in degrees of the BVH channels, which
+/// apply as `Rz * Rx * Ry`.
+fn euler_zxy(rotation: Vec4, convert_coordinates: bool) -> Vec3 {
+    let rotation = if convert_coordinates {
+        coords::rotation_to_right_handed(rotation)
+    } else {
+        coords::rotation(rotation)
+    };
+    let m = math::compose([0.0; 3], rotation, [1.0; 3]);
+    // Column-major, so row `r` and column `c` is `m[c * 4 + r]`.
+    let at = |row: usize, col: usize| m[col * 4 + row];
+    let x = at(2, 1).clamp(-1.0, 1.0).asin();
+    let y = (-at(2, 0)).atan2(at(2, 2));
+    let z = (-at(0, 1)).atan2(at(1, 1));
+    [z.to_degrees(), x.to_degrees(), y.to_degrees()]
+}
@@ -0,0 +1,527 @@
+//! Converts a parsed [`B3D`] into a COLLADA 1.4.1 (`.dae`) document.
+//!
+//! Nodes keep their hierarchy and names, each mesh becomes a geometry with one `<triangles>`
+//! element per brush, brushes become common-profile effects, bones become skin controllers and
+//! the keys become animations. Some conversions are necessarily approximate:
+//!
+//! - Brushes are Phong shaded, or constant if fullbright, using only their first texture layer.
+//! - Animations are sampled at every frame into node matrices instead of keeping the keys, and
+//!   each `SEQS` chunk becomes an animation clip over them.
+//! - Texture transforms, blend modes and vertex alpha beyond the color source are dropped.
+
+use crate::math::{self, Mat4};
+use crate::utils::{Vec3, Vec4};
+use crate::{coords, Animation, Brush, BrushId, Node, TextureLayer, B3D};
+
+/// Options for the COLLADA export.
+#[derive(Debug, Clone)]
+pub struct ColladaOptions {
+    /// Convert from Blitz3D's left-handed space into the right-handed space COLLADA uses.
+    pub convert_coordinates: bool,
+    /// Uniform factor applied to positions and translations.
+    pub scale: f32,
+    /// Sample the keys into animations.
+    pub animations: bool,
+}
+
+impl Default for ColladaOptions {
+    fn default() -> Self {
+        Self {
+            convert_coordinates: true,
+            scale: 1.0,
+            animations: true,
+        }
+    }
+}
+
+/// Converts `b3d` into a COLLADA document.
+pub fn export(b3d: &B3D, options: &ColladaOptions) -> String {
+    let mut exporter = Exporter {
+        b3d,
+        options,
+        nodes: vec![],
+        children: vec![],
+        globals: vec![],
+    };
+    exporter.flatten(&b3d.node, &math::IDENTITY);
+    exporter.run()
+}
+
+struct Exporter<'a> {
+    b3d: &'a B3D,
+    options: &'a ColladaOptions,
+    nodes: Vec<&'a Node>,
+    children: Vec<Vec<usize>>,
+    globals: Vec<Mat4>,
+}
+
+impl<'a> Exporter<'a> {
+    /// Assigns node indices in depth-first order and computes rest-pose global transforms.
+    fn flatten(&mut self, node: &'a Node, parent: &Mat4) -> usize {
+        let index = self.nodes.len();
+        let global = math::mul(parent, &self.transform(node.position, node.rotation, node.scale));
+
+        self.nodes.push(node);
+        self.children.push(vec![]);
+        self.globals.push(global);
+
+        for child in &node.children {
+            let child = self.flatten(child, &global);
+            self.children[index].push(child);
+        }
+        index
+    }
+
+    fn position(&self, v: Vec3) -> Vec3 {
+        self.direction(v).map(|c| c * self.options.scale)
+    }
+
+    fn direction(&self, v: Vec3) -> Vec3 {
+        let v = if self.options.convert_coordinates {
+            coords::to_right_handed(v)
+        } else {
+            v
+        };
+        v.map(coords::positive_zero)
+    }
+
+    fn triangle(&self, t: [u32; 3]) -> [u32; 3] {
+        if self.options.convert_coordinates {
+            coords::triangle_to_right_handed(t)
+        } else {
+            t
+        }
+    }
+
+    fn transform(&self, position: Vec3, rotation: Vec4, scale: Vec3) -> Mat4 {
+        let rotation = if self.options.convert_coordinates {
+            coords::rotation_to_right_handed(rotation)
+        } else {
+            coords::rotation(rotation)
+        };
+        math::compose(self.position(position), rotation, scale)
+    }
+
+    fn run(&self) -> String {
+        let mut dae = String::new();
+        dae.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        dae.push_str("<COLLADA xmlns=\"http://www.collada.org/2005/11/COLLADASchema\" version=\"1.4.1\">\n");
+        dae.push_str("  <asset>\n");
+        dae.push_str("    <contributor><authoring_tool>b3d</authoring_tool></contributor>\n");
+        dae.push_str("    <unit name=\"meter\" meter=\"1\"/>\n");
+        dae.push_str("    <up_axis>Y_UP</up_axis>\n");
+        dae.push_str("  </asset>\n");
+
+        self.images(&mut dae);
+        self.effects(&mut dae);
+        self.materials(&mut dae);
+
+        let meshes: Vec<usize> = (0..self.nodes.len())
+            .filter(|&index| !self.nodes[index].mesh.vertices.vertices.is_empty())
+            .collect();
+        if !meshes.is_empty() {
+            dae.push_str("  <library_geometries>\n");
+            for &index in &meshes {
+                self.geometry(&mut dae, index);
+            }
+            dae.push_str("  </library_geometries>\n");
+        }
+        let skins: Vec<(usize, Vec<usize>)> = meshes
+            .iter()
+            .map(|&index| (index, self.joints(index)))
+            .filter(|(_, joints)| !joints.is_empty())
+            .collect();
+        if !skins.is_empty() {
+            dae.push_str("  <library_controllers>\n");
+            for (index, joints) in &skins {
+                self.controller(&mut dae, *index, joints);
+            }
+            dae.push_str("  </library_controllers>\n");
+        }
+        if self.options.animations {
+            self.animations(&mut dae);
+        }
+
+        dae.push_str("  <library_visual_scenes>\n");
+        dae.push_str("    <visual_scene id=\"scene\" name=\"scene\">\n");
+        self.node(&mut dae, 0, &skins, 3);
+        dae.push_str("    </visual_scene>\n");
+        dae.push_str("  </library_visual_scenes>\n");
+        dae.push_str("  <scene>\n    <instance_visual_scene url=\"#scene\"/>\n  </scene>\n");
+        dae.push_str("</COLLADA>\n");
+        dae
+    }
+
+    fn images(&self, dae: &mut String) {
+        if self.b3d.textures.is_empty() {
+            return;
+        }
+        dae.push_str("  <library_images>\n");
+        for (index, texture) in self.b3d.textures.iter().enumerate() {
+            let file = escape(&texture.file.replace('\\', "/"));
+            dae.push_str(&format!("    <image id=\"image{index}\"><init_from>{file}</init_from></image>\n"));
+        }
+        dae.push_str("  </library_images>\n");
+    }
+
+    fn effects(&self, dae: &mut String) {
+        if self.b3d.brushes.is_empty() {
+            return;
+        }
+        dae.push_str("  <library_effects>\n");
+        for (index, brush) in self.b3d.brushes.iter().enumerate() {
+            let layer = self.layer(brush);
+            dae.push_str(&format!("    <effect id=\"effect{index}\">\n      <profile_COMMON>\n"));
+            if let Some(layer) = &layer {
+                let image = layer.texture_id.raw();
+                dae.push_str(&format!(
+                    "        <newparam sid=\"surface{image}\"><surface type=\"2D\"><init_from>image{image}</init_from></surface></newparam>\n"
+                ));
+                dae.push_str(&format!(
+                    "        <newparam sid=\"sampler{image}\"><sampler2D><source>surface{image}</source></sampler2D></newparam>\n"
+                ));
+            }
+
+            // Fullbright brushes ignore lighting.
+            let shader = if brush.fx & 1 != 0 { "constant" } else { "phong" };
+            dae.push_str(&format!("        <technique sid=\"common\">\n          <{shader}>\n"));
+            let [r, g, b, a] = brush.color;
+            let color = match &layer {
+                Some(layer) => {
+                    let image = layer.texture_id.raw();
+                    format!("<texture texture=\"sampler{image}\" texcoord=\"UVSET0\"/>")
+                }
+                None => format!("<color>{r} {g} {b} 1</color>"),
+            };
+            if shader == "constant" {
+                dae.push_str(&format!("            <emission>{color}</emission>\n"));
+            } else {
+                dae.push_str(&format!("            <diffuse>{color}</diffuse>\n"));
+                let s = brush.shininess;
+                dae.push_str(&format!("            <specular><color>{s} {s} {s} 1</color></specular>\n"));
+                dae.push_str(&format!("            <shininess><float>{}</float></shininess>\n", s * 128.0));
+            }
+            dae.push_str(&format!("            <transparency><float>{a}</float></transparency>\n"));
+            dae.push_str(&format!("          </{shader}>\n        </technique>\n"));
+            if brush.fx & 16 != 0 {
+                dae.push_str("        <extra><technique profile=\"GOOGLEEARTH\"><double_sided>1</double_sided></technique></extra>\n");
+            }
+            dae.push_str("      </profile_COMMON>\n    </effect>\n");
+        }
+        dae.push_str("  </library_effects>\n");
+    }
+
+    fn materials(&self, dae: &mut String) {
+        if self.b3d.brushes.is_empty() {
+            return;
+        }
+        dae.push_str("  <library_materials>\n");
+        for (index, brush) in self.b3d.brushes.iter().enumerate() {
+            let name = escape(&brush.name);
+            dae.push_str(&format!(
+                "    <material id=\"material{index}\" name=\"{name}\"><instance_effect url=\"#effect{index}\"/></material>\n"
+            ));
+        }
+        dae.push_str("  </library_materials>\n");
+    }
+
+    /// Returns the first texture layer of `brush`, the only one the export keeps.
+    fn layer(&self, brush: &Brush) -> Option<TextureLayer<'a>> {
+        brush.resolve_layers(&self.b3d.textures).into_iter().next()
+    }
+
+    fn brush_index(&self, id: BrushId) -> Option<usize> {
+        id.get().filter(|&id| id < self.b3d.brushes.len())
+    }
+
+    fn geometry(&self, dae: &mut String, index: usize) {
+        let node = self.nodes[index];
+        let mesh = &node.mesh;
+        let verts = &mesh.vertices;
+        let id = format!("geometry{index}");
+
+        dae.push_str(&format!("    <geometry id=\"{id}\" name=\"{}\">\n      <mesh>\n", escape(&node.name)));
+        let positions: Vec<f32> = verts.vertices.iter().flat_map(|v| self.position(v.position)).collect();
+        source(dae, "        ", &format!("{id}-positions"), &positions, &["X", "Y", "Z"]);
+        let mut inputs = vec![];
+        if let Some(normals) = verts.normals() {
+            let normals: Vec<f32> = normals.into_iter().flat_map(|normal| self.direction(normal)).collect();
+            source(dae, "        ", &format!("{id}-normals"), &normals, &["X", "Y", "Z"]);
+            inputs.push(format!("<input semantic=\"NORMAL\" source=\"#{id}-normals\" offset=\"0\"/>"));
+        }
+        for (set, uvs) in (0..).map_while(|set| Some(set).zip(verts.uv_set(set))) {
+            // COLLADA puts the origin of texture space at the bottom left, Blitz3D at the top left.
+            let uvs: Vec<f32> = uvs.into_iter().flat_map(|[u, v]| [u, 1.0 - v]).collect();
+            source(dae, "        ", &format!("{id}-uv{set}"), &uvs, &["S", "T"]);
+            inputs.push(format!("<input semantic=\"TEXCOORD\" source=\"#{id}-uv{set}\" offset=\"0\" set=\"{set}\"/>"));
+        }
+        if let Some(colors) = verts.colors() {
+            let colors: Vec<f32> = colors.concat();
+            source(dae, "        ", &format!("{id}-colors"), &colors, &["R", "G", "B", "A"]);
+            inputs.push(format!("<input semantic=\"COLOR\" source=\"#{id}-colors\" offset=\"0\"/>"));
+        }
+        dae.push_str(&format!(
+            "        <vertices id=\"{id}-vertices\"><input semantic=\"POSITION\" source=\"#{id}-positions\"/></vertices>\n"
+        ));
+
+        for (brush, indices) in self.groups(node) {
+            let material = brush.map(|brush| format!(" material=\"material{brush}\"")).unwrap_or_default();
+            dae.push_str(&format!("        <triangles{material} count=\"{}\">\n", indices.len() / 3));
+            dae.push_str(&format!("          <input semantic=\"VERTEX\" source=\"#{id}-vertices\" offset=\"0\"/>\n"));
+            for input in &inputs {
+                dae.push_str(&format!("          {input}\n"));
+            }
+            dae.push_str(&format!("          <p>{}</p>\n", join(&indices)));
+            dae.push_str("        </triangles>\n");
+        }
+        dae.push_str("      </mesh>\n    </geometry>\n");
+    }
+
+    /// Merges the TRIS chunks of `node` sharing a brush.
+    fn groups(&self, node: &Node) -> Vec<(Option<usize>, Vec<u32>)> {
+        let mesh = &node.mesh;
+        let mut groups: Vec<(Option<usize>, Vec<u32>)> = vec![];
+        for tris in &mesh.triangles {
+            let brush = self.brush_index(tris.brush_id).or(self.brush_index(mesh.brush_id));
+            let indices = tris.indices.iter().flat_map(|t| self.triangle(*t));
+            match groups.iter_mut().find(|(b, _)| *b == brush) {
+                Some((_, group)) => group.extend(indices),
+                None => groups.push((brush, indices.collect())),
+            }
+        }
+        groups
+    }
+
+    /// Collects the bone nodes skinning the mesh of node `index`, stopping at nested meshes.
+    fn joints(&self, index: usize) -> Vec<usize> {
+        let mut joints = vec![];
+        let mut stack = self.children[index].clone();
+        while let Some(child) = stack.pop() {
+            let node = self.nodes[child];
+            if !node.mesh.vertices.vertices.is_empty() {
+                continue;
+            }
+            if !node.bones.is_empty() {
+                joints.push(child);
+            }
+            stack.extend(self.children[child].iter().rev());
+        }
+        joints.sort_unstable();
+        joints
+    }
+
+    fn controller(&self, dae: &mut String, index: usize, joints: &[usize]) {
+        let id = format!("controller{index}");
+        let vertex_count = self.nodes[index].mesh.vertices.vertices.len();
+
+        let mut influences: Vec<Vec<(usize, usize)>> = vec![vec![]; vertex_count];
+        let mut weights = vec![];
+        for (joint, &node) in joints.iter().enumerate() {
+            for bone in &self.nodes[node].bones {
+                if let Some(influence) = influences.get_mut(bone.vertex_id as usize) {
+                    influence.push((joint, weights.len()));
+                    weights.push(bone.weight);
+                }
+            }
+        }
+
+        let mesh_global = self.globals[index];
+        let inverse_binds: Vec<f32> = joints
+            .iter()
+            .flat_map(|&joint| row_major(&math::mul(&math::inverse(&self.globals[joint]), &mesh_global)))
+            .collect();
+
+        dae.push_str(&format!("    <controller id=\"{id}\">\n      <skin source=\"#geometry{index}\">\n"));
+        dae.push_str(&format!("        <bind_shape_matrix>{}</bind_shape_matrix>\n", floats(&row_major(&math::IDENTITY))));
+        let names: Vec<String> = joints.iter().map(|joint| format!("node{joint}")).collect();
+        dae.push_str(&format!("        <source id=\"{id}-joints\">\n"));
+        dae.push_str(&format!(
+            "          <Name_array id=\"{id}-joints-array\" count=\"{}\">{}</Name_array>\n",
+            names.len(),
+            names.join(" ")
+        ));
+        dae.push_str(&format!(
+            "          <technique_common><accessor source=\"#{id}-joints-array\" count=\"{}\" stride=\"1\"><param name=\"JOINT\" type=\"name\"/></accessor></technique_common>\n",
+            names.len()
+        ));
+        dae.push_str("        </source>\n");
+        source_typed(dae, "        ", &format!("{id}-binds"), &inverse_binds, &[("TRANSFORM", "float4x4")]);
+        source(dae, "        ", &format!("{id}-weights"), &weights, &["WEIGHT"]);
+        dae.push_str("        <joints>\n");
+        dae.push_str(&format!("          <input semantic=\"JOINT\" source=\"#{id}-joints\"/>\n"));
+        dae.push_str(&format!("          <input semantic=\"INV_BIND_MATRIX\" source=\"#{id}-binds\"/>\n"));
+        dae.push_str("        </joints>\n");
+        dae.push_str(&format!("        <vertex_weights count=\"{vertex_count}\">\n"));
+        dae.push_str(&format!("          <input semantic=\"JOINT\" source=\"#{id}-joints\" offset=\"0\"/>\n"));
+        dae.push_str(&format!("          <input semantic=\"WEIGHT\" source=\"#{id}-weights\" offset=\"1\"/>\n"));
+        let counts: Vec<usize> = influences.iter().map(Vec::len).collect();
+        let pairs: Vec<usize> = influences.iter().flatten().flat_map(|&(joint, weight)| [joint, weight]).collect();
+        dae.push_str(&format!("          <vcount>{}</vcount>\n", join(&counts)));
+        dae.push_str(&format!("          <v>{}</v>\n", join(&pairs)));
+        dae.push_str("        </vertex_weights>\n      </skin>\n    </controller>\n");
+    }
+
+    fn animations(&self, dae: &mut String) {
+        let Some(fps) = self.b3d.animation().map(Animation::fps_or_default) else {
+            return;
+        };
+        let Some(frames) = self.b3d.animation_frame_range() else {
+            return;
+        };
+        let animated: Vec<usize> = (0..self.nodes.len()).filter(|&index| self.nodes[index].is_animated()).collect();
+        if animated.is_empty() {
+            return;
+        }
+
+        let times: Vec<f32> = frames.clone().map(|frame| frame as f32 / fps).collect();
+        dae.push_str("  <library_animations>\n");
+        for &index in &animated {
+            let node = self.nodes[index];
+            let id = format!("animation{index}");
+            let matrices: Vec<f32> = frames
+                .clone()
+                .flat_map(|frame| {
+                    let (position, scale, rotation) = node.pose_at(frame as f32);
+                    row_major(&self.transform(position, rotation, scale))
+                })
+                .collect();
+            let interpolations = vec!["LINEAR"; times.len()].join(" ");
+
+            dae.push_str(&format!("    <animation id=\"{id}\">\n"));
+            source(dae, "      ", &format!("{id}-input"), &times, &["TIME"]);
+            source_typed(dae, "      ", &format!("{id}-output"), &matrices, &[("TRANSFORM", "float4x4")]);
+            dae.push_str(&format!("      <source id=\"{id}-interpolation\">\n"));
+            dae.push_str(&format!(
+                "        <Name_array id=\"{id}-interpolation-array\" count=\"{}\">{interpolations}</Name_array>\n",
+                times.len()
+            ));
+            dae.push_str(&format!(
+                "        <technique_common><accessor source=\"#{id}-interpolation-array\" count=\"{}\" stride=\"1\"><param name=\"INTERPOLATION\" type=\"name\"/></accessor></technique_common>\n",
+                times.len()
+            ));
+            dae.push_str("      </source>\n");
+            dae.push_str(&format!("      <sampler id=\"{id}-sampler\">\n"));
+            dae.push_str(&format!("        <input semantic=\"INPUT\" source=\"#{id}-input\"/>\n"));
+            dae.push_str(&format!("        <input semantic=\"OUTPUT\" source=\"#{id}-output\"/>\n"));
+            dae.push_str(&format!("        <input semantic=\"INTERPOLATION\" source=\"#{id}-interpolation\"/>\n"));
+            dae.push_str("      </sampler>\n");
+            dae.push_str(&format!("      <channel source=\"#{id}-sampler\" target=\"node{index}/transform\"/>\n"));
+            dae.push_str("    </animation>\n");
+        }
+        dae.push_str("  </library_animations>\n");
+
+        let clips = self.b3d.clips();
+        if clips.is_empty() {
+            return;
+        }
+        dae.push_str("  <library_animation_clips>\n");
+        for (index, clip) in clips.iter().enumerate() {
+            let start = *clip.frames.start() as f32 / fps;
+            let end = *clip.frames.end() as f32 / fps;
+            dae.push_str(&format!(
+                "    <animation_clip id=\"clip{index}\" name=\"{}\" start=\"{start}\" end=\"{end}\">\n",
+                escape(&clip.name)
+            ));
+            for &animated in &animated {
+                dae.push_str(&format!("      <instance_animation url=\"#animation{animated}\"/>\n"));
+            }
+            dae.push_str("    </animation_clip>\n");
+        }
+        dae.push_str("  </library_animation_clips>\n");
+    }
+
+    fn node(&self, dae: &mut String, index: usize, skins: &[(usize, Vec<usize>)], depth: usize) {
+        let node = self.nodes[index];
+        let indent = "  ".repeat(depth);
+        let kind = if node.bones.is_empty() { "NODE" } else { "JOINT" };
+        dae.push_str(&format!(
+            "{indent}<node id=\"node{index}\" sid=\"node{index}\" name=\"{}\" type=\"{kind}\">\n",
+            escape(&node.name)
+        ));
+        let transform = self.transform(node.position, node.rotation, node.scale);
+        dae.push_str(&format!("{indent}  <matrix sid=\"transform\">{}</matrix>\n", floats(&row_major(&transform))));
+
+        if !node.mesh.vertices.vertices.is_empty() {
+            let bindings = self.bind_material(node, &indent);
+            if skins.iter().any(|(skinned, _)| *skinned == index) {
+                dae.push_str(&format!("{indent}  <instance_controller url=\"#controller{index}\">\n"));
+                dae.push_str(&format!("{indent}    <skeleton>#node{index}</skeleton>\n"));
+                dae.push_str(&bindings);
+                dae.push_str(&format!("{indent}  </instance_controller>\n"));
+            } else {
+                dae.push_str(&format!("{indent}  <instance_geometry url=\"#geometry{index}\">\n"));
+                dae.push_str(&bindings);
+                dae.push_str(&format!("{indent}  </instance_geometry>\n"));
+            }
+        }
+
+        for &child in &self.children[index] {
+            self.node(dae, child, skins, depth + 1);
+        }
+        dae.push_str(&format!("{indent}</node>\n"));
+    }
+
+    /// Binds the materials used by the triangles of `node`, along with the UV set their
+    /// textures read.
+    fn bind_material(&self, node: &Node, indent: &str) -> String {
+        let brushes: Vec<usize> = self.groups(node).into_iter().filter_map(|(brush, _)| brush).collect();
+        if brushes.is_empty() {
+            return String::new();
+        }
+        let mut bindings = format!("{indent}    <bind_material><technique_common>\n");
+        for brush in brushes {
+            let set = self.layer(&self.b3d.brushes[brush]).map_or(0, |layer| layer.texture.uv_set());
+            bindings.push_str(&format!(
+                "{indent}      <instance_material symbol=\"material{brush}\" target=\"#material{brush}\"><bind_vertex_input semantic=\"UVSET0\" input_semantic=\"TEXCOORD\" input_set=\"{set}\"/></instance_material>\n"
+            ));
+        }
+        bindings.push_str(&format!("{indent}    </technique_common></bind_material>\n"));
+        bindings
+    }
+}
+
+/// Writes a float source with one `float` param per name in `params`.
+fn source(dae: &mut String, indent: &str, id: &str, values: &[f32], params: &[&str]) {
+    let params: Vec<(&str, &str)> = params.iter().map(|&name| (name, "float")).collect();
+    source_typed(dae, indent, id, values, &params);
+}
+
+fn source_typed(dae: &mut String, indent: &str, id: &str, values: &[f32], params: &[(&str, &str)]) {
+    let stride: usize = params.iter().map(|(_, ty)| if *ty == "float4x4" { 16 } else { 1 }).sum();
+    let count = values.len() / stride;
+    dae.push_str(&format!("{indent}<source id=\"{id}\">\n"));
+    dae.push_str(&format!(
+        "{indent}  <float_array id=\"{id}-array\" count=\"{}\">{}</float_array>\n",
+        values.len(),
+        floats(values)
+    ));
+    dae.push_str(&format!(
+        "{indent}  <technique_common><accessor source=\"#{id}-array\" count=\"{count}\" stride=\"{stride}\">"
+    ));
+    for (name, ty) in params {
+        dae.push_str(&format!("<param name=\"{name}\" type=\"{ty}\"/>"));
+    }
+    dae.push_str("</accessor></technique_common>\n");
+    dae.push_str(&format!("{indent}</source>\n"));
+}
+
+/// Reorders a column-major matrix into the row-major order COLLADA stores.
+fn row_major(m: &Mat4) -> Mat4 {
+    math::transpose(m)
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    values.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ")
+}
+
+fn floats(values: &[f32]) -> String {
+    values.iter().map(|&value| coords::positive_zero(value).to_string()).collect::<Vec<_>>().join(" ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
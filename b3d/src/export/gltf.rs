@@ -0,0 +1,623 @@
+//! Converts a parsed [`B3D`] into a glTF 2.0 document.
+//!
+//! Nodes keep their hierarchy and names, meshes are split into one primitive per brush,
+//! brushes become metallic-roughness materials, bones become skins and the `KEYS`/`SEQS`
+//! chunks become animations. Some conversions are necessarily approximate:
+//!
+//! - Brush shininess maps to `1 - roughness`, the fullbright fx flag to `KHR_materials_unlit`.
+//! - Only the first texture layer of a brush is used, as the base color texture.
+//! - Texture position/scale/rotation map to `KHR_texture_transform`.
+//! - Vertices are limited to the four strongest bone weights.
+
+use serde_json::{json, Value};
+
+use crate::math::{self, Mat4};
+use crate::utils::{Vec3, Vec4};
+use crate::{coords, Animation, Brush, BrushId, Node, Texture, TextureLayer, B3D};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+const CLAMP_TO_EDGE: u32 = 33071;
+const REPEAT: u32 = 10497;
+
+/// Keyframes of a single animated property, as `(frame, value)` pairs.
+type Track = Vec<(u32, Vec<f32>)>;
+
+/// Options for the glTF export.
+#[derive(Debug, Clone)]
+pub struct GltfOptions {
+    /// Convert from Blitz3D's left-handed space into glTF's right-handed space.
+    pub convert_coordinates: bool,
+    /// Uniform factor applied to vertex positions and node translations.
+    pub scale: f32,
+    /// Export the animation keys.
+    pub animations: bool,
+}
+
+impl Default for GltfOptions {
+    fn default() -> Self {
+        Self {
+            convert_coordinates: true,
+            scale: 1.0,
+            animations: true,
+        }
+    }
+}
+
+/// A glTF document with its single binary buffer.
+#[derive(Debug, Clone)]
+pub struct Gltf {
+    pub json: Value,
+    pub buffer: Vec<u8>,
+}
+
+impl Gltf {
+    /// Serializes the document as a `.gltf` file referencing the buffer at `buffer_uri`.
+    pub fn to_gltf(&self, buffer_uri: &str) -> String {
+        let mut json = self.json.clone();
+        if let Some(buffer) = json["buffers"].get_mut(0) {
+            buffer["uri"] = json!(buffer_uri);
+        }
+        serde_json::to_string_pretty(&json).unwrap_or_default()
+    }
+
+    /// Serializes the document and its buffer as a single `.glb` file.
+    pub fn to_glb(&self) -> Vec<u8> {
+        let mut json = serde_json::to_vec(&self.json).unwrap_or_default();
+        pad(&mut json, b' ');
+        let mut bin = self.buffer.clone();
+        pad(&mut bin, 0);
+
+        let mut length = 12 + 8 + json.len();
+        if !bin.is_empty() {
+            length += 8 + bin.len();
+        }
+
+        let mut glb = Vec::with_capacity(length);
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"JSON");
+        glb.extend_from_slice(&json);
+        if !bin.is_empty() {
+            glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+            glb.extend_from_slice(b"BIN\0");
+            glb.extend_from_slice(&bin);
+        }
+        glb
+    }
+}
+
+/// Converts `b3d` into a glTF document.
+pub fn export(b3d: &B3D, options: &GltfOptions) -> Gltf {
+    Exporter::new(b3d, options).run()
+}
+
+/// Converts `b3d` straight into the bytes of a `.glb` file.
+pub fn to_glb(b3d: &B3D, options: &GltfOptions) -> Vec<u8> {
+    export(b3d, options).to_glb()
+}
+
+fn pad(bytes: &mut Vec<u8>, with: u8) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(with);
+    }
+}
+
+struct Exporter<'a> {
+    b3d: &'a B3D,
+    options: &'a GltfOptions,
+    nodes: Vec<&'a Node>,
+    children: Vec<Vec<usize>>,
+    globals: Vec<Mat4>,
+    buffer: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl<'a> Exporter<'a> {
+    fn new(b3d: &'a B3D, options: &'a GltfOptions) -> Self {
+        let mut exporter = Self {
+            b3d,
+            options,
+            nodes: vec![],
+            children: vec![],
+            globals: vec![],
+            buffer: vec![],
+            buffer_views: vec![],
+            accessors: vec![],
+        };
+        exporter.flatten(&b3d.node, &math::IDENTITY);
+        exporter
+    }
+
+    /// Assigns glTF node indices in depth-first order and computes rest-pose global transforms.
+    fn flatten(&mut self, node: &'a Node, parent: &Mat4) -> usize {
+        let index = self.nodes.len();
+        let (translation, rotation, scale) = self.trs(node.position, node.rotation, node.scale);
+        let global = math::mul(parent, &math::compose(translation, rotation, scale));
+
+        self.nodes.push(node);
+        self.children.push(vec![]);
+        self.globals.push(global);
+
+        for child in &node.children {
+            let child = self.flatten(child, &global);
+            self.children[index].push(child);
+        }
+        index
+    }
+
+    fn position(&self, v: Vec3) -> Vec3 {
+        let v = if self.options.convert_coordinates {
+            coords::to_right_handed(v)
+        } else {
+            v
+        };
+        v.map(|c| c * self.options.scale)
+    }
+
+    fn direction(&self, v: Vec3) -> Vec3 {
+        if self.options.convert_coordinates {
+            coords::to_right_handed(v)
+        } else {
+            v
+        }
+    }
+
+    fn rotation(&self, q: Vec4) -> Vec4 {
+        if self.options.convert_coordinates {
+            coords::rotation_to_right_handed(q)
+        } else {
+            coords::rotation(q)
+        }
+    }
+
+    fn triangle(&self, t: [u32; 3]) -> [u32; 3] {
+        if self.options.convert_coordinates {
+            coords::triangle_to_right_handed(t)
+        } else {
+            t
+        }
+    }
+
+    fn trs(&self, position: Vec3, rotation: Vec4, scale: Vec3) -> (Vec3, Vec4, Vec3) {
+        (self.position(position), self.rotation(rotation), scale)
+    }
+
+    fn run(mut self) -> Gltf {
+        let mut json_nodes = vec![];
+        let mut meshes = vec![];
+        let mut skins = vec![];
+
+        for index in 0..self.nodes.len() {
+            let node = self.nodes[index];
+            let (translation, rotation, scale) = self.trs(node.position, node.rotation, node.scale);
+            let mut json_node = json!({
+                "name": node.name.as_str(),
+                "translation": translation,
+                "rotation": rotation,
+                "scale": scale,
+            });
+            if !self.children[index].is_empty() {
+                json_node["children"] = json!(self.children[index]);
+            }
+            // glTF meshes need at least one primitive, so vertices without triangles are dropped.
+            let has_triangles = node.mesh.triangles.iter().any(|tris| !tris.indices.is_empty());
+            if !node.mesh.vertices.vertices.is_empty() && has_triangles {
+                let joints = self.joints(index);
+                let skin = (!joints.is_empty()).then(|| {
+                    skins.push(self.skin(index, &joints));
+                    skins.len() - 1
+                });
+                meshes.push(self.mesh(node, &joints));
+                json_node["mesh"] = json!(meshes.len() - 1);
+                if let Some(skin) = skin {
+                    json_node["skin"] = json!(skin);
+                }
+            }
+            json_nodes.push(json_node);
+        }
+
+        let animations = if self.options.animations {
+            self.animations()
+        } else {
+            vec![]
+        };
+
+        let mut extensions_used = vec![];
+        let materials: Vec<Value> = self.b3d.brushes.iter().map(|b| self.material(b)).collect();
+        if materials.iter().any(|m| m.pointer("/extensions/KHR_materials_unlit").is_some()) {
+            extensions_used.push("KHR_materials_unlit");
+        }
+        let textures = self.b3d.textures.iter().enumerate().map(|(i, _)| {
+            json!({ "source": i, "sampler": i })
+        });
+        let images = self.b3d.textures.iter().map(|t| json!({ "uri": texture_uri(t) }));
+        let samplers = self.b3d.textures.iter().map(|t| {
+            let clamp = |flag: u32| if t.flags & flag != 0 { CLAMP_TO_EDGE } else { REPEAT };
+            json!({ "wrapS": clamp(16), "wrapT": clamp(32) })
+        });
+        if self.b3d.textures.iter().any(has_transform) {
+            extensions_used.push("KHR_texture_transform");
+        }
+
+        let mut json = json!({
+            "asset": { "version": "2.0", "generator": "b3d" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": json_nodes,
+        });
+        let mut set = |key: &str, values: Vec<Value>| {
+            if !values.is_empty() {
+                json[key] = Value::Array(values);
+            }
+        };
+        set("meshes", meshes);
+        set("skins", skins);
+        set("animations", animations);
+        set("materials", materials);
+        set("textures", textures.collect());
+        set("images", images.collect());
+        set("samplers", samplers.collect());
+        set("accessors", self.accessors);
+        set("bufferViews", self.buffer_views);
+        set("extensionsUsed", extensions_used.into_iter().map(Value::from).collect());
+        if !self.buffer.is_empty() {
+            json["buffers"] = json!([{ "byteLength": self.buffer.len() }]);
+        }
+
+        Gltf {
+            json,
+            buffer: self.buffer,
+        }
+    }
+
+    fn buffer_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        pad(&mut self.buffer, 0);
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.buffer.extend_from_slice(bytes);
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    fn float_accessor<const N: usize>(&mut self, values: &[[f32; N]], target: Option<u32>, bounds: bool) -> usize {
+        let bytes: Vec<u8> = values.iter().flatten().flat_map(|v| v.to_le_bytes()).collect();
+        let view = self.buffer_view(&bytes, target);
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len(),
+            "type": accessor_type(N),
+        });
+        if bounds && !values.is_empty() {
+            let min: [f32; N] = std::array::from_fn(|i| values.iter().map(|v| v[i]).fold(f32::INFINITY, f32::min));
+            let max: [f32; N] = std::array::from_fn(|i| values.iter().map(|v| v[i]).fold(f32::NEG_INFINITY, f32::max));
+            accessor["min"] = json!(min.to_vec());
+            accessor["max"] = json!(max.to_vec());
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn index_accessor(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let view = self.buffer_view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn joint_accessor(&mut self, joints: &[[u16; 4]]) -> usize {
+        let bytes: Vec<u8> = joints.iter().flatten().flat_map(|v| v.to_le_bytes()).collect();
+        let view = self.buffer_view(&bytes, Some(ARRAY_BUFFER));
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_SHORT,
+            "count": joints.len(),
+            "type": "VEC4",
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn mesh(&mut self, node: &Node, joints: &[usize]) -> Value {
+        let mesh = &node.mesh;
+        let verts = &mesh.vertices;
+
+        let positions: Vec<Vec3> = verts.vertices.iter().map(|v| self.position(v.position)).collect();
+        let mut attributes = json!({
+            "POSITION": self.float_accessor(&positions, Some(ARRAY_BUFFER), true),
+        });
+        if let Some(normals) = verts.normals() {
+            let normals: Vec<Vec3> = normals.into_iter().map(|normal| self.direction(normal)).collect();
+            attributes["NORMAL"] = json!(self.float_accessor(&normals, Some(ARRAY_BUFFER), false));
+        }
+        if let Some(colors) = verts.colors() {
+            attributes["COLOR_0"] = json!(self.float_accessor(&colors, Some(ARRAY_BUFFER), false));
+        }
+        for (set, uvs) in (0..).map_while(|set| Some(set).zip(verts.uv_set(set))) {
+            attributes[format!("TEXCOORD_{set}")] = json!(self.float_accessor(&uvs, Some(ARRAY_BUFFER), false));
+        }
+        if !joints.is_empty() {
+            let (indices, weights) = self.weights(verts.vertices.len(), joints);
+            attributes["JOINTS_0"] = json!(self.joint_accessor(&indices));
+            attributes["WEIGHTS_0"] = json!(self.float_accessor(&weights, Some(ARRAY_BUFFER), false));
+        }
+
+        // Merge the TRIS chunks sharing a brush into a single primitive.
+        let mut groups: Vec<(Option<usize>, Vec<u32>)> = vec![];
+        for tris in mesh.triangles.iter().filter(|tris| !tris.indices.is_empty()) {
+            let brush = self.brush_index(tris.brush_id).or(self.brush_index(mesh.brush_id));
+            let indices = tris.indices.iter().flat_map(|t| self.triangle(*t));
+            match groups.iter_mut().find(|(b, _)| *b == brush) {
+                Some((_, group)) => group.extend(indices),
+                None => groups.push((brush, indices.collect())),
+            }
+        }
+
+        let primitives: Vec<Value> = groups
+            .into_iter()
+            .map(|(brush, indices)| {
+                let mut primitive = json!({
+                    "attributes": attributes,
+                    "indices": self.index_accessor(&indices),
+                });
+                if let Some(brush) = brush {
+                    primitive["material"] = json!(brush);
+                }
+                primitive
+            })
+            .collect();
+
+        json!({ "name": node.name.as_str(), "primitives": primitives })
+    }
+
+    fn brush_index(&self, id: BrushId) -> Option<usize> {
+        id.get().filter(|&id| id < self.b3d.brushes.len())
+    }
+
+    /// Collects the bone nodes skinning the mesh of node `index`, stopping at nested meshes.
+    fn joints(&self, index: usize) -> Vec<usize> {
+        let mut joints = vec![];
+        let mut stack = self.children[index].clone();
+        while let Some(child) = stack.pop() {
+            let node = self.nodes[child];
+            if !node.mesh.vertices.vertices.is_empty() {
+                continue;
+            }
+            if !node.bones.is_empty() {
+                joints.push(child);
+            }
+            stack.extend(self.children[child].iter().rev());
+        }
+        joints.sort_unstable();
+        joints
+    }
+
+    fn skin(&mut self, mesh_index: usize, joints: &[usize]) -> Value {
+        let mesh_global = self.globals[mesh_index];
+        let inverse_binds: Vec<Mat4> = joints
+            .iter()
+            .map(|&joint| math::mul(&math::inverse(&self.globals[joint]), &mesh_global))
+            .collect();
+        let inverse_binds = self.float_accessor(&inverse_binds, None, false);
+        json!({
+            "joints": joints,
+            "skeleton": mesh_index,
+            "inverseBindMatrices": inverse_binds,
+        })
+    }
+
+    /// Builds the four strongest, normalized joint weights of every vertex.
+    fn weights(&self, vertex_count: usize, joints: &[usize]) -> (Vec<[u16; 4]>, Vec<Vec4>) {
+        let mut influences: Vec<Vec<(u16, f32)>> = vec![vec![]; vertex_count];
+        for (joint, &node) in joints.iter().enumerate() {
+            for bone in &self.nodes[node].bones {
+                if let Some(influence) = influences.get_mut(bone.vertex_id as usize) {
+                    influence.push((joint as u16, bone.weight));
+                }
+            }
+        }
+
+        influences
+            .into_iter()
+            .map(|mut influence| {
+                influence.sort_by(|a, b| b.1.total_cmp(&a.1));
+                influence.truncate(4);
+                let total: f32 = influence.iter().map(|(_, w)| w).sum();
+                let mut indices = [0; 4];
+                let mut weights = [0.0; 4];
+                for (i, (joint, weight)) in influence.into_iter().enumerate() {
+                    indices[i] = joint;
+                    weights[i] = if total > 0.0 { weight / total } else { 0.0 };
+                }
+                (indices, weights)
+            })
+            .unzip()
+    }
+
+    fn material(&self, brush: &Brush) -> Value {
+        let mut pbr = json!({
+            "baseColorFactor": brush.color,
+            "metallicFactor": 0.0,
+            "roughnessFactor": (1.0 - brush.shininess).clamp(0.0, 1.0),
+        });
+        let layer = brush.resolve_layers(&self.b3d.textures).into_iter().next();
+
+        let mut material = json!({ "name": brush.name.as_str() });
+        let mut alpha_mode = if brush.color[3] < 1.0 || brush.fx & 32 != 0 {
+            "BLEND"
+        } else {
+            "OPAQUE"
+        };
+
+        if let Some(TextureLayer { texture_id, texture, .. }) = layer {
+            let mut info = json!({ "index": texture_id.raw() });
+            if texture.uses_secondary_uvs() {
+                info["texCoord"] = json!(texture.uv_set());
+            }
+            if has_transform(texture) {
+                info["extensions"] = json!({ "KHR_texture_transform": texture_transform(texture) });
+            }
+            pbr["baseColorTexture"] = info;
+            if texture.flags & 4 != 0 {
+                alpha_mode = "MASK";
+                material["alphaCutoff"] = json!(0.5);
+            } else if texture.flags & 2 != 0 {
+                alpha_mode = "BLEND";
+            }
+        }
+
+        material["pbrMetallicRoughness"] = pbr;
+        material["alphaMode"] = json!(alpha_mode);
+        if brush.fx & 16 != 0 {
+            material["doubleSided"] = json!(true);
+        }
+        if brush.fx & 1 != 0 {
+            material["extensions"] = json!({ "KHR_materials_unlit": {} });
+        }
+        material
+    }
+
+    fn animations(&mut self) -> Vec<Value> {
+        let Some(fps) = self.b3d.animation().map(Animation::fps_or_default) else {
+            return vec![];
+        };
+
+        self.b3d
+            .clips()
+            .into_iter()
+            .map(|clip| self.animation(&clip.name, *clip.frames.start(), *clip.frames.end(), fps))
+            .filter(|animation| animation["channels"].as_array().is_some_and(|c| !c.is_empty()))
+            .collect()
+    }
+
+    /// Builds an animation from the keys between `first` and `last`, inclusive.
+    fn animation(&mut self, name: &str, first: u32, last: u32, fps: f32) -> Value {
+        let mut channels = vec![];
+        let mut samplers = vec![];
+
+        for index in 0..self.nodes.len() {
+            let node = self.nodes[index];
+            let channel = |flag: u32| node.keys.iter().filter(move |keys| keys.flags & flag != 0).flat_map(|keys| &keys.keys);
+
+            let tracks: [(&str, Track); 3] = [
+                ("translation", channel(1).map(|k| (k.frame, self.position(k.position).to_vec())).collect()),
+                ("scale", channel(2).map(|k| (k.frame, k.scale.to_vec())).collect()),
+                ("rotation", channel(4).map(|k| (k.frame, self.rotation(k.rotation).to_vec())).collect()),
+            ];
+
+            for (path, keys) in tracks {
+                let keys = slice_track(&keys, first, last, path == "rotation");
+                if keys.is_empty() {
+                    continue;
+                }
+                let times: Vec<[f32; 1]> = keys.iter().map(|(f, _)| [(f - first) as f32 / fps]).collect();
+                let input = self.float_accessor(&times, None, true);
+                let output = match path {
+                    "rotation" => {
+                        let values: Vec<Vec4> = keys.iter().map(|(_, v)| [v[0], v[1], v[2], v[3]]).collect();
+                        self.float_accessor(&values, None, false)
+                    }
+                    _ => {
+                        let values: Vec<Vec3> = keys.iter().map(|(_, v)| [v[0], v[1], v[2]]).collect();
+                        self.float_accessor(&values, None, false)
+                    }
+                };
+                samplers.push(json!({ "input": input, "output": output, "interpolation": "LINEAR" }));
+                channels.push(json!({
+                    "sampler": samplers.len() - 1,
+                    "target": { "node": index, "path": path },
+                }));
+            }
+        }
+
+        json!({ "name": name, "channels": channels, "samplers": samplers })
+    }
+}
+
+/// Keeps the keys between `first` and `last`, interpolating keys at both ends when missing.
+fn slice_track(keys: &[(u32, Vec<f32>)], first: u32, last: u32, rotation: bool) -> Track {
+    let mut keys = keys.to_vec();
+    keys.sort_by_key(|(frame, _)| *frame);
+    // Animation inputs have to increase strictly, so only the first key of a frame is kept.
+    keys.dedup_by_key(|(frame, _)| *frame);
+    if keys.is_empty() {
+        return keys;
+    }
+
+    let sample = |frame: u32| -> Vec<f32> {
+        let next = keys.iter().position(|(f, _)| *f >= frame);
+        match next {
+            None => keys[keys.len() - 1].1.clone(),
+            Some(0) => keys[0].1.clone(),
+            Some(i) => {
+                let (f0, a) = &keys[i - 1];
+                let (f1, b) = &keys[i];
+                let t = (frame - f0) as f32 / (f1 - f0) as f32;
+                if rotation {
+                    math::slerp([a[0], a[1], a[2], a[3]], [b[0], b[1], b[2], b[3]], t).to_vec()
+                } else {
+                    math::lerp([a[0], a[1], a[2]], [b[0], b[1], b[2]], t).to_vec()
+                }
+            }
+        }
+    };
+
+    let mut sliced: Track = keys
+        .iter()
+        .filter(|(f, _)| (first..=last).contains(f))
+        .cloned()
+        .collect();
+    if sliced.first().map(|(f, _)| *f) != Some(first) {
+        sliced.insert(0, (first, sample(first)));
+    }
+    if sliced.last().map(|(f, _)| *f) != Some(last) && last > first {
+        sliced.push((last, sample(last)));
+    }
+    sliced
+}
+
+fn accessor_type(components: usize) -> &'static str {
+    match components {
+        1 => "SCALAR",
+        2 => "VEC2",
+        3 => "VEC3",
+        4 => "VEC4",
+        _ => "MAT4",
+    }
+}
+
+fn texture_uri(texture: &Texture) -> String {
+    texture.file.replace('\\', "/").replace(' ', "%20")
+}
+
+fn has_transform(texture: &Texture) -> bool {
+    texture.position != [0.0, 0.0] || texture.scale != [1.0, 1.0] || texture.rotation != 0.0
+}
+
+fn texture_transform(texture: &Texture) -> Value {
+    let scale = texture.scale.map(|s| if s != 0.0 { 1.0 / s } else { 1.0 });
+    json!({
+        "offset": texture.position.map(|p| -p),
+        "scale": scale,
+        "rotation": texture.rotation.to_radians(),
+    })
+}
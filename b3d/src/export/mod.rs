@@ -0,0 +1,8 @@
+//! Conversions from [`B3D`](crate::B3D) into other model formats.
+
+pub mod bvh;
+#[cfg(feature = "collada")]
+pub mod collada;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+pub mod obj;
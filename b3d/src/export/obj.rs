@@ -0,0 +1,204 @@
+//! Converts a parsed [`B3D`] into a Wavefront OBJ file and its MTL material library.
+//!
+//! OBJ only describes static geometry, so the result is the rest pose of the model: every mesh
+//! is written as an object with its node transforms baked into the vertices, and bones, keys
+//! and sequences are ignored. Each `TRIS` chunk becomes a group using its brush's material.
+//! Vertex colors, secondary texture layers and brush blend modes are dropped.
+
+use crate::math::{self, Mat4};
+use crate::utils::Vec3;
+use crate::{coords, Brush, Node, B3D};
+
+/// Options for the OBJ export.
+#[derive(Debug, Clone)]
+pub struct ObjOptions {
+    /// Convert from Blitz3D's left-handed space into the right-handed space most tools expect.
+    pub convert_coordinates: bool,
+    /// Uniform factor applied to vertex positions.
+    pub scale: f32,
+}
+
+impl Default for ObjOptions {
+    fn default() -> Self {
+        Self {
+            convert_coordinates: true,
+            scale: 1.0,
+        }
+    }
+}
+
+/// An OBJ file along with the MTL file holding its materials.
+#[derive(Debug, Clone)]
+pub struct Obj {
+    pub obj: String,
+    pub mtl: String,
+}
+
+impl Obj {
+    /// Returns the OBJ file, referencing the material library at `mtl_uri`.
+    pub fn to_obj(&self, mtl_uri: &str) -> String {
+        format!("mtllib {mtl_uri}\n{}", self.obj)
+    }
+}
+
+/// Converts `b3d` into an OBJ file and its material library.
+pub fn export(b3d: &B3D, options: &ObjOptions) -> Obj {
+    let materials = material_names(&b3d.brushes);
+    let mut exporter = Exporter {
+        options,
+        materials: &materials,
+        obj: String::new(),
+        vertex_count: 0,
+    };
+    exporter.node(&b3d.node, &math::IDENTITY);
+
+    Obj {
+        obj: exporter.obj,
+        mtl: mtl(b3d, &materials),
+    }
+}
+
+/// Gives every brush a unique material name without whitespace, which OBJ can't represent.
+fn material_names(brushes: &[Brush]) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for (index, brush) in brushes.iter().enumerate() {
+        let mut name = sanitize(&brush.name);
+        if name.is_empty() || names.contains(&name) {
+            name = format!("{name}_{index}");
+        }
+        names.push(name);
+    }
+    names
+}
+
+fn sanitize(name: &str) -> String {
+    name.trim().replace(char::is_whitespace, "_")
+}
+
+fn mtl(b3d: &B3D, materials: &[String]) -> String {
+    let mut mtl = String::new();
+    for (brush, name) in b3d.brushes.iter().zip(materials) {
+        let [r, g, b, a] = brush.color;
+        mtl.push_str(&format!("newmtl {name}\n"));
+        mtl.push_str(&format!("Kd {r} {g} {b}\n"));
+        mtl.push_str(&format!("Ks {0} {0} {0}\n", brush.shininess));
+        mtl.push_str(&format!("Ns {}\n", brush.shininess * 128.0));
+        mtl.push_str(&format!("d {a}\n"));
+        // Fullbright brushes ignore lighting.
+        let illum = if brush.fx & 1 != 0 { 0 } else { 2 };
+        mtl.push_str(&format!("illum {illum}\n"));
+
+        let texture = brush
+            .texture_id
+            .first()
+            .and_then(|&id| b3d.texture(id));
+        if let Some(texture) = texture {
+            let file = texture.file.replace('\\', "/");
+            mtl.push_str(&format!("map_Kd {file}\n"));
+            // Alpha and masked textures carry the brush's transparency.
+            if texture.flags & (2 | 4) != 0 {
+                mtl.push_str(&format!("map_d {file}\n"));
+            }
+        }
+        mtl.push('\n');
+    }
+    mtl
+}
+
+struct Exporter<'a> {
+    options: &'a ObjOptions,
+    materials: &'a [String],
+    obj: String,
+    /// Number of vertices written so far, as OBJ indices are global to the file.
+    vertex_count: u32,
+}
+
+impl Exporter<'_> {
+    fn node(&mut self, node: &Node, parent: &Mat4) {
+        let rotation = coords::rotation(node.rotation);
+        let global = math::mul(parent, &math::compose(node.position, rotation, node.scale));
+
+        if !node.mesh.vertices.vertices.is_empty() {
+            self.mesh(node, &global);
+        }
+        for child in &node.children {
+            self.node(child, &global);
+        }
+    }
+
+    fn mesh(&mut self, node: &Node, global: &Mat4) {
+        let verts = &node.mesh.vertices;
+        let has_normals = verts.has_normals();
+        let uvs = verts.uv_set(0);
+        let normal_matrix = math::transpose(&math::inverse(global));
+
+        let mut name = sanitize(&node.name);
+        if name.is_empty() {
+            name = "Mesh".to_owned();
+        }
+        self.obj.push_str(&format!("o {name}\n"));
+
+        for vertex in &verts.vertices {
+            let [x, y, z] = self.position(math::transform_point(global, vertex.position));
+            self.obj.push_str(&format!("v {x} {y} {z}\n"));
+        }
+        if let Some(uvs) = &uvs {
+            for &[u, v] in uvs {
+                // OBJ puts the origin of texture space at the bottom left, Blitz3D at the top left.
+                self.obj.push_str(&format!("vt {u} {}\n", 1.0 - v));
+            }
+        }
+        if has_normals {
+            for vertex in &verts.vertices {
+                let normal = math::normalize(math::transform_vector(&normal_matrix, vertex.normal));
+                let [x, y, z] = self.direction(normal);
+                self.obj.push_str(&format!("vn {x} {y} {z}\n"));
+            }
+        }
+
+        for (group, tris) in node.mesh.triangles.iter().enumerate() {
+            self.obj.push_str(&format!("g {name}_{group}\n"));
+            let brush_id = tris.brush_id.or(node.mesh.brush_id);
+            if let Some(material) = brush_id.get().and_then(|id| self.materials.get(id)) {
+                self.obj.push_str(&format!("usemtl {material}\n"));
+            }
+
+            for &triangle in &tris.indices {
+                if triangle.iter().any(|&i| i as usize >= verts.vertices.len()) {
+                    continue;
+                }
+                let triangle = if self.options.convert_coordinates {
+                    coords::triangle_to_right_handed(triangle)
+                } else {
+                    triangle
+                };
+                self.obj.push('f');
+                for index in triangle {
+                    let index = self.vertex_count + index + 1;
+                    match (uvs.is_some(), has_normals) {
+                        (true, true) => self.obj.push_str(&format!(" {index}/{index}/{index}")),
+                        (true, false) => self.obj.push_str(&format!(" {index}/{index}")),
+                        (false, true) => self.obj.push_str(&format!(" {index}//{index}")),
+                        (false, false) => self.obj.push_str(&format!(" {index}")),
+                    }
+                }
+                self.obj.push('\n');
+            }
+        }
+
+        self.vertex_count += verts.vertices.len() as u32;
+    }
+
+    fn position(&self, v: Vec3) -> Vec3 {
+        self.direction(v).map(|c| c * self.options.scale)
+    }
+
+    fn direction(&self, v: Vec3) -> Vec3 {
+        let v = if self.options.convert_coordinates {
+            coords::to_right_handed(v)
+        } else {
+            v
+        };
+        v.map(coords::positive_zero)
+    }
+}
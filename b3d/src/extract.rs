@@ -0,0 +1,214 @@
+//! Flattens the chunk model into plain per-material meshes for renderers.
+
+use std::collections::HashMap;
+
+use crate::math::{self, Mat4};
+use crate::utils::{Vec2, Vec3, Vec4};
+use crate::{Brush, BrushId, Name, Node, Texture, B3D};
+
+/// A mesh ready to upload to a GPU: every vertex attribute is filled in and only the vertices
+/// used by `indices` are included.
+///
+/// Positions, normals and `transform` are in Blitz3D's left-handed space with clockwise front
+/// faces; see [`coords`](crate::coords) for conversions.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeshData {
+    /// Name of the node the mesh belongs to.
+    pub name: Name,
+    /// Column-major matrix placing the mesh in the model, from the node's rest pose.
+    pub transform: [f32; 16],
+    pub positions: Vec<Vec3>,
+    /// Normals from the file, or smooth normals computed from the triangles.
+    pub normals: Vec<Vec3>,
+    /// The first texture coordinate set, or zeros.
+    pub uvs: Vec<Vec2>,
+    /// The second texture coordinate set, or zeros, for textures that
+    /// [use secondary uvs](Texture::uses_secondary_uvs).
+    pub secondary_uvs: Vec<Vec2>,
+    /// Vertex colors, or white.
+    pub colors: Vec<Vec4>,
+    /// Triangle list indices into the vertex attributes.
+    pub indices: Vec<u32>,
+    pub material: MaterialData,
+}
+
+/// The brush a [`MeshData`] is drawn with, with its fx flags spelled out.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaterialData {
+    pub name: Name,
+    pub color: Vec4,
+    pub shininess: f32,
+    /// Texture layers, from the first to the last blended.
+    pub textures: Vec<Texture>,
+    /// Ignore lighting.
+    pub fullbright: bool,
+    /// Multiply by the vertex colors.
+    pub vertex_colors: bool,
+    pub flat_shaded: bool,
+    pub disable_fog: bool,
+    /// Draw back faces too.
+    pub double_sided: bool,
+    /// Alpha blend even when the color and textures are opaque.
+    pub force_alpha: bool,
+}
+
+impl Default for MaterialData {
+    fn default() -> Self {
+        Self {
+            name: Name::default(),
+            color: [1.0; 4],
+            shininess: 0.0,
+            textures: vec![],
+            fullbright: false,
+            vertex_colors: false,
+            flat_shaded: false,
+            disable_fog: false,
+            double_sided: false,
+            force_alpha: false,
+        }
+    }
+}
+
+impl MaterialData {
+    fn new(brush: &Brush, textures: &[Texture]) -> Self {
+        Self {
+            name: brush.name.clone(),
+            color: brush.color,
+            shininess: brush.shininess,
+            textures: brush
+                .texture_id
+                .iter()
+                .filter_map(|id| textures.get(id.get()?))
+                .cloned()
+                .collect(),
+            fullbright: brush.fx & 1 != 0,
+            vertex_colors: brush.fx & 2 != 0,
+            flat_shaded: brush.fx & 4 != 0,
+            disable_fog: brush.fx & 8 != 0,
+            double_sided: brush.fx & 16 != 0,
+            force_alpha: brush.fx & 32 != 0,
+        }
+    }
+}
+
+impl B3D {
+    /// Returns one [`MeshData`] per node and brush, so that each can be drawn in a single call.
+    pub fn extract_meshes(&self) -> Vec<MeshData> {
+        let mut meshes = vec![];
+        self.extract_node(&self.node, &math::IDENTITY, &mut meshes);
+        meshes
+    }
+
+    fn extract_node(&self, node: &Node, parent: &Mat4, meshes: &mut Vec<MeshData>) {
+        let transform = math::mul(parent, &node.rest_transform());
+
+        let verts = &node.mesh.vertices;
+        let normals = verts.normals().unwrap_or_else(|| smooth_normals(node));
+
+        // TRIS chunks sharing a brush are drawn together.
+        let mut groups: Vec<(BrushId, Vec<[u32; 3]>)> = vec![];
+        for tris in &node.mesh.triangles {
+            let brush_id = tris.brush_id.or(node.mesh.brush_id);
+            match groups.iter_mut().find(|(id, _)| *id == brush_id) {
+                Some((_, triangles)) => triangles.extend(&tris.indices),
+                None => groups.push((brush_id, tris.indices.clone())),
+            }
+        }
+
+        for (brush_id, triangles) in groups {
+            let mut mesh = MeshData {
+                name: node.name.clone(),
+                transform,
+                positions: vec![],
+                normals: vec![],
+                uvs: vec![],
+                secondary_uvs: vec![],
+                colors: vec![],
+                indices: vec![],
+                material: self
+                    .brush(brush_id)
+                    .map(|brush| MaterialData::new(brush, &self.textures))
+                    .unwrap_or_default(),
+            };
+
+            let mut remap = HashMap::new();
+            for triangle in triangles {
+                if triangle.iter().any(|&i| i as usize >= verts.vertices.len()) {
+                    continue;
+                }
+                for index in triangle {
+                    let next = mesh.positions.len() as u32;
+                    let remapped = *remap.entry(index).or_insert_with(|| {
+                        let vertex = &verts.vertices[index as usize];
+                        mesh.positions.push(vertex.position);
+                        mesh.normals.push(normals[index as usize]);
+                        mesh.uvs.push(vertex.tex_coords.first().copied().unwrap_or_default());
+                        mesh.secondary_uvs.push(vertex.tex_coords.get(1).copied().unwrap_or_default());
+                        mesh.colors.push(if verts.has_colors() { vertex.color } else { [1.0; 4] });
+                        next
+                    });
+                    mesh.indices.push(remapped);
+                }
+            }
+
+            if !mesh.indices.is_empty() {
+                meshes.push(mesh);
+            }
+        }
+
+        for child in &node.children {
+            self.extract_node(child, &transform, meshes);
+        }
+    }
+
+    /// Returns the minimum and maximum corners of the box around every vertex in the rest pose,
+    /// in model space, or `None` if there are no vertices.
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        let mut bounds: Option<(Vec3, Vec3)> = None;
+        grow_bounds(&self.node, &math::IDENTITY, &mut bounds);
+        bounds
+    }
+}
+
+fn grow_bounds(node: &Node, parent: &Mat4, bounds: &mut Option<(Vec3, Vec3)>) {
+    let transform = math::mul(parent, &node.rest_transform());
+    for vertex in &node.mesh.vertices.vertices {
+        let p = math::transform_point(&transform, vertex.position);
+        let (min, max) = bounds.get_or_insert((p, p));
+        *min = std::array::from_fn(|i| min[i].min(p[i]));
+        *max = std::array::from_fn(|i| max[i].max(p[i]));
+    }
+    for child in &node.children {
+        grow_bounds(child, &transform, bounds);
+    }
+}
+
+/// Averages the face normals around each vertex, for meshes saved without normals.
+fn smooth_normals(node: &Node) -> Vec<Vec3> {
+    let vertices = &node.mesh.vertices.vertices;
+    let mut normals = vec![[0.0; 3]; vertices.len()];
+    for triangle in node.mesh.triangles.iter().flat_map(|tris| &tris.indices) {
+        if triangle.iter().any(|&i| i as usize >= vertices.len()) {
+            continue;
+        }
+        let [a, b, c] = triangle.map(|i| vertices[i as usize].position);
+        let (u, v) = (sub(b, a), sub(c, a));
+        // Clockwise front faces in a left-handed space face along u × v.
+        let face = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+        for &i in triangle {
+            let normal = &mut normals[i as usize];
+            *normal = std::array::from_fn(|k| normal[k] + face[k]);
+        }
+    }
+    normals.into_iter().map(math::normalize).collect()
+}
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    std::array::from_fn(|i| a[i] - b[i])
+}
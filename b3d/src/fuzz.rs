@@ -0,0 +1,64 @@
+use arbitrary::{Arbitrary, Unstructured};
+
+use crate::{Mesh, Node, B3D};
+
+/// Builds models whose ids and indices point at things that exist, so that fuzzing code
+/// consuming a [`B3D`] gets past the obvious lookups. Everything else is left arbitrary.
+impl<'a> Arbitrary<'a> for B3D {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut b3d = Self {
+            version: 1,
+            textures: u.arbitrary()?,
+            brushes: u.arbitrary()?,
+            node: u.arbitrary()?,
+        };
+
+        let textures = b3d.textures.len();
+        for brush in &mut b3d.brushes {
+            for id in &mut brush.texture_id {
+                *id = fix_id(*id, textures);
+            }
+        }
+        fix_node(&mut b3d.node, b3d.brushes.len());
+        Ok(b3d)
+    }
+}
+
+/// Wraps `id` into `0..count`, keeping none ids as they are.
+fn fix_id<I>(id: I, count: usize) -> I
+where
+    I: From<Option<usize>> + Copy + Into<Option<usize>>
+{
+    let id: Option<usize> = id.into();
+    I::from(id.filter(|_| count > 0).map(|id| id % count))
+}
+
+fn fix_mesh(mesh: &mut Mesh, brushes: usize) {
+    let vertices = mesh.vertices.vertices.len() as u32;
+    // The limits `Verts::read` enforces.
+    mesh.vertices.tex_coord_sets %= 9;
+    mesh.vertices.tex_coord_set_size %= 5;
+    mesh.brush_id = fix_id(mesh.brush_id, brushes);
+    for tris in &mut mesh.triangles {
+        tris.brush_id = fix_id(tris.brush_id, brushes);
+        if vertices == 0 {
+            tris.indices.clear();
+        }
+        for index in tris.indices.iter_mut().flatten() {
+            *index %= vertices.max(1);
+        }
+    }
+}
+
+fn fix_node(node: &mut Node, brushes: usize) {
+    fix_mesh(&mut node.mesh, brushes);
+    // Names end at their first NUL once written, see `Name`'s `Arbitrary` implementation.
+    for sequence in &mut node.sequences {
+        if let Some(end) = sequence.name.find('\0') {
+            sequence.name.truncate(end);
+        }
+    }
+    for child in &mut node.children {
+        fix_node(child, brushes);
+    }
+}
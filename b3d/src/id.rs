@@ -0,0 +1,93 @@
+use std::fmt;
+
+macro_rules! id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        ///
+        /// Files store ids as 32-bit integers where -1 refers to nothing, which is
+        /// [`Self::NONE`] here.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+        #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+        pub struct $name(u32);
+
+        impl $name {
+            /// The id that refers to nothing.
+            pub const NONE: Self = Self(u32::MAX);
+
+            pub fn new(index: usize) -> Self {
+                Self(index as u32)
+            }
+
+            /// Wraps an id as stored in a file.
+            pub fn from_raw(raw: u32) -> Self {
+                Self(raw)
+            }
+
+            /// Returns the id as stored in a file.
+            pub fn raw(self) -> u32 {
+                self.0
+            }
+
+            /// Returns the index this id refers to, or `None` for [`Self::NONE`].
+            pub fn get(self) -> Option<usize> {
+                (self != Self::NONE).then_some(self.0 as usize)
+            }
+
+            pub fn is_none(self) -> bool {
+                self == Self::NONE
+            }
+
+            /// Returns `self`, or `other` if `self` is [`Self::NONE`].
+            pub fn or(self, other: Self) -> Self {
+                if self.is_none() {
+                    other
+                } else {
+                    self
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::NONE
+            }
+        }
+
+        impl From<Option<usize>> for $name {
+            fn from(index: Option<usize>) -> Self {
+                index.map_or(Self::NONE, Self::new)
+            }
+        }
+
+        impl From<$name> for Option<usize> {
+            fn from(id: $name) -> Self {
+                id.get()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.get() {
+                    Some(index) => write!(f, "{index}"),
+                    None => f.write_str("none"),
+                }
+            }
+        }
+    };
+}
+
+id! {
+    /// An index into [`B3D::brushes`](crate::B3D::brushes).
+    BrushId
+}
+
+id! {
+    /// An index into [`B3D::textures`](crate::B3D::textures).
+    TextureId
+}
+
+id! {
+    /// The index of a node in depth-first order, starting with [`B3D::node`](crate::B3D::node) at 0.
+    NodeId
+}
@@ -0,0 +1,740 @@
+//! Builds a [`B3D`] from a glTF 2.0 document.
+//!
+//! The conversion is lossy wherever glTF can express more than the b3d chunk model:
+//!
+//! - Only the default (or first) scene is imported. Several root nodes are wrapped in a new
+//!   `ROOT` node, since a b3d file has a single root.
+//! - All primitives of a mesh share one `VRTS` chunk, with one `TRIS` chunk per primitive.
+//!   Primitives that aren't triangle lists are skipped, as are morph targets.
+//! - Materials become brushes keeping only the base color, roughness (as shininess), the
+//!   base color texture, double-sidedness, unlit and blending. Masked alpha is set on the
+//!   texture, which b3d shares between every brush using it. Textures otherwise get Blitz3D's
+//!   default color and mipmap flags, plus clamping from their sampler.
+//! - Only the first color set and the first eight texture coordinate sets are kept. Base color
+//!   textures sampled with the second set get the secondary uv flag.
+//! - Skinned meshes that aren't ancestors of their joints are moved to the nearest common
+//!   ancestor of the joints, since Blitz3D only deforms meshes by bones beneath them.
+//! - Every animation is resampled to integer frames at 60 fps and placed one after another on
+//!   the single b3d timeline, each as a named `SEQS` sequence. Cubic spline tangents are dropped.
+//! - Buffers must be embedded (GLB or `data:` URIs); external buffer files are rejected.
+
+use std::collections::{BTreeSet, HashMap};
+
+use base64::Engine;
+use gltf::animation::util::ReadOutputs;
+use gltf::animation::Interpolation;
+use gltf::material::AlphaMode;
+use gltf::Semantic;
+use gltf::texture::WrappingMode;
+
+use crate::math::{self, Mat4};
+use crate::utils::{Vec3, Vec4};
+use crate::{
+    coords, Animation, Bone, Brush, BrushId, Error, Key, Keys, Mesh, Name, Node, Sequence, Texture, TextureId,
+    Tris, Vertice, Verts, B3D,
+};
+
+/// Frame rate used to place glTF keyframe times onto b3d frames.
+const FPS: f32 = 60.0;
+
+/// The key flags and keys of each animated node, by glTF node index.
+type Tracks = HashMap<usize, (u32, Vec<Key>)>;
+
+/// The joints influencing each vertex of a mesh, by skin joint index, with their weights.
+type Weights = Vec<Vec<(u16, f32)>>;
+
+impl B3D {
+    /// Converts a glTF 2.0 document (`.glb`, or `.gltf` with embedded buffers) into a b3d model.
+    ///
+    /// See the [module documentation](crate::import::gltf) for the lossy parts of the conversion.
+    pub fn from_gltf(bytes: &[u8]) -> Result<Self, Error> {
+        let gltf = gltf::Gltf::from_slice(bytes)?;
+        let buffers = load_buffers(&gltf)?;
+        Importer::new(&gltf.document, &buffers)?.run()
+    }
+}
+
+fn load_buffers(gltf: &gltf::Gltf) -> Result<Vec<Vec<u8>>, Error> {
+    gltf.document
+        .buffers()
+        .map(|buffer| match buffer.source() {
+            gltf::buffer::Source::Bin => gltf
+                .blob
+                .clone()
+                .ok_or(Error::UnsupportedGltf("missing binary chunk")),
+            gltf::buffer::Source::Uri(uri) => {
+                let data = uri
+                    .strip_prefix("data:")
+                    .and_then(|uri| uri.split_once(";base64,"))
+                    .ok_or(Error::UnsupportedGltf("external buffers"))?
+                    .1;
+                base64::engine::general_purpose::STANDARD
+                    .decode(data)
+                    .map_err(|_| Error::UnsupportedGltf("malformed data URI"))
+            }
+        })
+        .collect()
+}
+
+fn to_mat4(m: [[f32; 4]; 4]) -> Mat4 {
+    std::array::from_fn(|i| m[i / 4][i % 4])
+}
+
+struct Importer<'a> {
+    document: &'a gltf::Document,
+    buffers: &'a [Vec<u8>],
+    nodes: Vec<gltf::Node<'a>>,
+    parents: Vec<Option<usize>>,
+    globals: Vec<Mat4>,
+    /// The node each skinned mesh is moved to, with the transform into that node's space.
+    relocated: HashMap<usize, (usize, Mat4)>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(document: &'a gltf::Document, buffers: &'a [Vec<u8>]) -> Result<Self, Error> {
+        let nodes: Vec<gltf::Node> = document.nodes().collect();
+        let count = nodes.len();
+        let mut parents = vec![None; count];
+        for node in &nodes {
+            for child in node.children() {
+                parents[child.index()] = Some(node.index());
+            }
+        }
+
+        // Walk down from the nodes without a parent: a node reached twice has several parents or
+        // is its own ancestor, and one never reached is part of a cycle.
+        let mut globals = vec![math::IDENTITY; count];
+        let mut visited = vec![false; count];
+        let mut stack: Vec<(usize, Mat4)> = (0..count)
+            .filter(|&index| parents[index].is_none())
+            .map(|index| (index, math::IDENTITY))
+            .collect();
+        while let Some((index, parent)) = stack.pop() {
+            if std::mem::replace(&mut visited[index], true) {
+                return Err(Error::UnsupportedGltf("node hierarchy is not a tree"));
+            }
+            globals[index] = math::mul(&parent, &to_mat4(nodes[index].transform().matrix()));
+            stack.extend(nodes[index].children().map(|child| (child.index(), globals[index])));
+        }
+        if visited.contains(&false) {
+            return Err(Error::UnsupportedGltf("node hierarchy is not a tree"));
+        }
+
+        let mut importer = Self {
+            document,
+            buffers,
+            nodes,
+            parents,
+            globals,
+            relocated: HashMap::new(),
+        };
+        importer.relocate_skinned_meshes()?;
+        Ok(importer)
+    }
+
+    fn ancestors(&self, mut index: usize) -> Result<Vec<usize>, Error> {
+        let mut ancestors = vec![index];
+        while let Some(parent) = self.parents[index] {
+            if ancestors.len() > self.parents.len() {
+                return Err(Error::UnsupportedGltf("node hierarchy is not a tree"));
+            }
+            ancestors.push(parent);
+            index = parent;
+        }
+        Ok(ancestors)
+    }
+
+    fn relocate_skinned_meshes(&mut self) -> Result<(), Error> {
+        let get = |buffer: gltf::Buffer| self.buffers.get(buffer.index()).map(Vec::as_slice);
+        let mut taken: BTreeSet<usize> = self
+            .document
+            .nodes()
+            .filter(|n| n.mesh().is_some())
+            .map(|n| n.index())
+            .collect();
+
+        for node in self.document.nodes() {
+            let (Some(skin), Some(_)) = (node.skin(), node.mesh()) else {
+                continue;
+            };
+            let joints: Vec<usize> = skin.joints().map(|j| j.index()).collect();
+            let mut joint_ancestors = Vec::with_capacity(joints.len());
+            for &joint in &joints {
+                joint_ancestors.push(self.ancestors(joint)?);
+            }
+            if joints.is_empty() || joint_ancestors.iter().all(|a| a.contains(&node.index())) {
+                continue;
+            }
+
+            // The deepest node that is an ancestor of every joint, but not a joint itself.
+            let mut common = joint_ancestors[0].clone();
+            for ancestors in &joint_ancestors[1..] {
+                common.retain(|a| ancestors.contains(a));
+            }
+            let Some(&target) = common.iter().find(|a| !joints.contains(a)) else {
+                continue;
+            };
+            if !taken.insert(target) {
+                continue;
+            }
+            taken.remove(&node.index());
+
+            let inverse_bind = skin
+                .reader(get)
+                .read_inverse_bind_matrices()
+                .and_then(|mut m| m.next())
+                .map(to_mat4)
+                .unwrap_or(math::IDENTITY);
+            let bind = math::mul(&self.globals[joints[0]], &inverse_bind);
+            let transform = math::mul(&math::inverse(&self.globals[target]), &bind);
+            self.relocated.insert(node.index(), (target, transform));
+        }
+        Ok(())
+    }
+
+    fn run(&self) -> Result<B3D, Error> {
+        let textures = self.textures();
+        let brushes = self.brushes();
+
+        let scene = self.document.default_scene().or_else(|| self.document.scenes().next());
+        let roots: Vec<gltf::Node> = scene.map(|s| s.nodes().collect()).unwrap_or_default();
+
+        let mut meshes: HashMap<usize, Mesh> = HashMap::new();
+        let mut bones: HashMap<usize, Vec<Bone>> = HashMap::new();
+        for node in self.document.nodes() {
+            let Some(mesh) = node.mesh() else {
+                continue;
+            };
+            let (target, transform) = self
+                .relocated
+                .get(&node.index())
+                .copied()
+                .unwrap_or((node.index(), math::IDENTITY));
+            let (mesh, weights) = self.mesh(&mesh, &transform)?;
+            if let Some(skin) = node.skin() {
+                let joints: Vec<usize> = skin.joints().map(|j| j.index()).collect();
+                for (vertex, influences) in weights.into_iter().enumerate() {
+                    for (joint, weight) in influences {
+                        if let Some(&joint) = joints.get(joint as usize) {
+                            bones.entry(joint).or_default().push(Bone {
+                                vertex_id: vertex as u32,
+                                weight,
+                            });
+                        }
+                    }
+                }
+            }
+            meshes.insert(target, mesh);
+        }
+
+        let (tracks, sequences, frames) = self.animations()?;
+
+        let mut nodes: Vec<Node> = roots
+            .iter()
+            .map(|root| self.node(root, &mut meshes, &mut bones, &tracks))
+            .collect();
+        let mut node = if nodes.len() == 1 {
+            nodes.remove(0)
+        } else {
+            Node {
+                name: "ROOT".into(),
+                scale: [1.0; 3],
+                rotation: [1.0, 0.0, 0.0, 0.0],
+                children: nodes,
+                ..Default::default()
+            }
+        };
+        if !sequences.is_empty() {
+            node.animation = Animation {
+                flags: 0,
+                frames,
+                fps: FPS,
+            };
+            node.sequences = sequences;
+        }
+
+        Ok(B3D {
+            version: 1,
+            textures,
+            brushes,
+            node,
+        })
+    }
+
+    fn textures(&self) -> Vec<Texture> {
+        let mut masked = BTreeSet::new();
+        let mut secondary = BTreeSet::new();
+        let mut transforms = HashMap::new();
+        for material in self.document.materials() {
+            let Some(info) = material.pbr_metallic_roughness().base_color_texture() else {
+                continue;
+            };
+            if material.alpha_mode() == AlphaMode::Mask {
+                masked.insert(info.texture().index());
+            }
+            if info.tex_coord() == 1 {
+                secondary.insert(info.texture().index());
+            }
+            if let Some(transform) = info.texture_transform() {
+                transforms.entry(info.texture().index()).or_insert(transform);
+            }
+        }
+
+        self.document
+            .textures()
+            .map(|texture| {
+                let texture_index = texture.index();
+                let image = texture.source();
+                let file = match image.source() {
+                    gltf::image::Source::Uri { uri, .. } => uri.replace("%20", " "),
+                    gltf::image::Source::View { mime_type, .. } => {
+                        let extension = if mime_type == "image/jpeg" { "jpg" } else { "png" };
+                        match image.name() {
+                            Some(name) if name.contains('.') => name.to_owned(),
+                            Some(name) => format!("{name}.{extension}"),
+                            None => format!("texture{}.{extension}", image.index()),
+                        }
+                    }
+                };
+
+                let sampler = texture.sampler();
+                let mut texture = Texture::new(file);
+                if masked.contains(&texture_index) {
+                    texture.flags |= 4;
+                }
+                if secondary.contains(&texture_index) {
+                    texture.flags |= 0x10000;
+                }
+                if sampler.wrap_s() == WrappingMode::ClampToEdge {
+                    texture.flags |= 16;
+                }
+                if sampler.wrap_t() == WrappingMode::ClampToEdge {
+                    texture.flags |= 32;
+                }
+                if let Some(transform) = transforms.get(&texture_index) {
+                    texture.position = transform.offset().map(|o| -o);
+                    texture.scale = transform.scale().map(|s| if s != 0.0 { 1.0 / s } else { 1.0 });
+                    texture.rotation = transform.rotation().to_degrees();
+                }
+                texture
+            })
+            .collect()
+    }
+
+    fn brushes(&self) -> Vec<Brush> {
+        self.document
+            .materials()
+            .map(|material| {
+                let pbr = material.pbr_metallic_roughness();
+                let mut fx = 0;
+                if material.unlit() {
+                    fx |= 1;
+                }
+                if material.double_sided() {
+                    fx |= 16;
+                }
+                if material.alpha_mode() == AlphaMode::Blend {
+                    fx |= 32;
+                }
+                let texture_id = pbr
+                    .base_color_texture()
+                    .map(|info| vec![TextureId::new(info.texture().index())])
+                    .unwrap_or_default();
+
+                Brush {
+                    name: material.name().unwrap_or("Brush").into(),
+                    color: pbr.base_color_factor(),
+                    shininess: 1.0 - pbr.roughness_factor(),
+                    blend: 1,
+                    fx,
+                    texture_id,
+                }
+            })
+            .collect()
+    }
+
+    /// Converts a glTF mesh, returning it along with the joint weights of every vertex.
+    fn mesh(&self, mesh: &gltf::Mesh, transform: &Mat4) -> Result<(Mesh, Weights), Error> {
+        let get = |buffer: gltf::Buffer| self.buffers.get(buffer.index()).map(Vec::as_slice);
+        let mut vertices = Verts::default();
+        let mut weights = vec![];
+        let mut triangles = vec![];
+        let mut bases = HashMap::new();
+
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                continue;
+            }
+            let reader = primitive.reader(get);
+            let Some(accessor) = primitive.get(&Semantic::Positions) else {
+                continue;
+            };
+            let count = accessor.count();
+
+            // Primitives often share their vertex attributes, differing only in material.
+            let base = match bases.get(&accessor.index()) {
+                Some(&base) => base,
+                None => {
+                    let base = u32::try_from(vertices.vertices.len())
+                        .map_err(|_| Error::UnsupportedGltf("too many vertices"))?;
+                    bases.insert(accessor.index(), base);
+                    read_vertices(&primitive, self.buffers, transform, &mut vertices, &mut weights)?;
+                    base
+                }
+            };
+
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..count as u32).collect(),
+            };
+            if indices.iter().any(|&index| index as usize >= count) {
+                return Err(Error::UnsupportedGltf("vertex index out of range"));
+            }
+            let brush_id = BrushId::from(primitive.material().index());
+            triangles.push(Tris {
+                brush_id,
+                // Flipping Z turns counter-clockwise front faces clockwise, as Blitz3D expects.
+                indices: indices
+                    .chunks_exact(3)
+                    .map(|t| [base + t[0], base + t[2], base + t[1]])
+                    .collect(),
+            });
+        }
+
+        let mesh = Mesh {
+            brush_id: BrushId::NONE,
+            vertices,
+            triangles,
+        };
+        Ok((mesh, weights))
+    }
+
+    fn node(
+        &self,
+        node: &gltf::Node,
+        meshes: &mut HashMap<usize, Mesh>,
+        bones: &mut HashMap<usize, Vec<Bone>>,
+        tracks: &Tracks,
+    ) -> Node {
+        let (translation, rotation, scale) = node.transform().decomposed();
+        let keys = tracks.get(&node.index()).cloned().map(|(flags, keys)| Keys { flags, keys });
+        Node {
+            name: node.name().map_or_else(|| format!("Node{}", node.index()).into(), Name::from),
+            position: coords::to_left_handed(translation),
+            scale,
+            rotation: coords::rotation_to_left_handed(rotation),
+            mesh: meshes.remove(&node.index()).unwrap_or_default(),
+            bones: bones.remove(&node.index()).unwrap_or_default(),
+            keys: keys.into_iter().collect(),
+            children: node
+                .children()
+                .map(|child| self.node(&child, meshes, bones, tracks))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Places every animation one after another on a single timeline, returning the keys of
+    /// each node, one sequence per animation and the total frame count.
+    fn animations(&self) -> Result<(Tracks, Vec<Sequence>, u32), Error> {
+        let get = |buffer: gltf::Buffer| self.buffers.get(buffer.index()).map(Vec::as_slice);
+        let mut tracks = Tracks::new();
+        let mut sequences = vec![];
+        let mut first_frame = 0;
+
+        for animation in self.document.animations() {
+            let mut channels: HashMap<usize, Channels> = HashMap::new();
+            let mut last = 0.0f32;
+
+            for channel in animation.channels() {
+                let reader = channel.reader(get);
+                let (Some(inputs), Some(outputs)) = (reader.read_inputs(), reader.read_outputs()) else {
+                    continue;
+                };
+                let times: Vec<f32> = inputs.collect();
+                last = times.iter().copied().fold(last, f32::max);
+
+                let interpolation = channel.sampler().interpolation();
+                let channels = channels.entry(channel.target().node().index()).or_default();
+                match outputs {
+                    ReadOutputs::Translations(values) => {
+                        let values = values.map(coords::to_left_handed).map(|v| v.to_vec());
+                        channels.translation = Some(Track::new(times, values, interpolation)?);
+                    }
+                    ReadOutputs::Scales(values) => {
+                        let values = values.map(|v| v.to_vec());
+                        channels.scale = Some(Track::new(times, values, interpolation)?);
+                    }
+                    ReadOutputs::Rotations(values) => {
+                        let values = values.into_f32().map(|v| v.to_vec());
+                        channels.rotation = Some(Track::new(times, values, interpolation)?);
+                    }
+                    ReadOutputs::MorphTargetWeights(_) => {}
+                }
+            }
+
+            if channels.is_empty() {
+                continue;
+            }
+
+            let last_frame = first_frame + (last * FPS).round() as u32;
+            for (index, channels) in channels {
+                let node = &self.nodes[index];
+                let (rest_translation, rest_rotation, rest_scale) = node.transform().decomposed();
+                let (flags, keys) = tracks.entry(index).or_default();
+                *flags |= channels.flags();
+
+                let frames: BTreeSet<u32> = channels
+                    .iter()
+                    .flat_map(|track| track.times.iter())
+                    .map(|t| first_frame + (t * FPS).round() as u32)
+                    .collect();
+                for frame in frames {
+                    let time = (frame - first_frame) as f32 / FPS;
+                    let sample3 = |track: &Option<Track>, rest: Vec3| {
+                        track.as_ref().map_or(rest, |t| {
+                            let v = t.sample(time, false);
+                            [v[0], v[1], v[2]]
+                        })
+                    };
+                    let rotation = channels.rotation.as_ref().map_or(rest_rotation, |t| {
+                        let v = t.sample(time, true);
+                        [v[0], v[1], v[2], v[3]]
+                    });
+                    keys.push(Key {
+                        frame,
+                        position: sample3(&channels.translation, coords::to_left_handed(rest_translation)),
+                        scale: sample3(&channels.scale, rest_scale),
+                        rotation: coords::rotation_to_left_handed(rotation),
+                    });
+                }
+            }
+
+            sequences.push(Sequence {
+                name: animation
+                    .name()
+                    .map_or_else(|| format!("Animation{}", animation.index()), str::to_owned),
+                first_frame,
+                last_frame,
+                unused: 0,
+            });
+            first_frame = last_frame + 1;
+        }
+
+        Ok((tracks, sequences, first_frame.saturating_sub(1)))
+    }
+}
+
+/// Appends a primitive's vertices to `verts`, along with the joint weights of each vertex.
+fn read_vertices(
+    primitive: &gltf::Primitive,
+    buffers: &[Vec<u8>],
+    transform: &Mat4,
+    verts: &mut Verts,
+    weights: &mut Weights,
+) -> Result<(), Error> {
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+    let Some(positions) = reader.read_positions() else {
+        return Ok(());
+    };
+    let positions: Vec<Vec3> = positions.collect();
+    let normals: Option<Vec<Vec3>> = reader.read_normals().map(|n| n.collect());
+    let colors: Option<Vec<Vec4>> = reader.read_colors(0).map(|c| c.into_rgba_f32().collect());
+    let uv_sets: Vec<Vec<[f32; 2]>> = (0..8).map_while(|set| reader.read_tex_coords(set)).map(|t| t.into_f32().collect()).collect();
+    let joints: Option<Vec<[u16; 4]>> = reader.read_joints(0).map(|j| j.into_u16().collect());
+    let joint_weights: Option<Vec<[f32; 4]>> = reader.read_weights(0).map(|w| w.into_f32().collect());
+    // glTF doesn't require the attributes of a primitive to have as many entries as its
+    // positions, but every vertex needs one of each.
+    let mut counts: Vec<usize> = uv_sets.iter().map(Vec::len).collect();
+    counts.extend(normals.as_ref().map(Vec::len));
+    counts.extend(colors.as_ref().map(Vec::len));
+    counts.extend(joints.as_ref().map(Vec::len));
+    counts.extend(joint_weights.as_ref().map(Vec::len));
+    if counts.iter().any(|&count| count != positions.len()) {
+        return Err(Error::UnsupportedGltf("vertex attributes of different lengths"));
+    }
+
+    if normals.is_some() {
+        verts.flags |= 1;
+    }
+    if colors.is_some() {
+        verts.flags |= 2;
+    }
+    if !uv_sets.is_empty() {
+        verts.tex_coord_sets = verts.tex_coord_sets.max(uv_sets.len() as u32);
+        verts.tex_coord_set_size = 2;
+    }
+
+    for (i, position) in positions.into_iter().enumerate() {
+        let normal = normals.as_ref().map_or([0.0; 3], |n| n[i]);
+        verts.vertices.push(Vertice {
+            position: coords::to_left_handed(math::transform_point(transform, position)),
+            normal: coords::to_left_handed(math::normalize(math::transform_vector(transform, normal))),
+            color: colors.as_ref().map_or([1.0; 4], |c| c[i]),
+            tex_coords: uv_sets.iter().map(|set| set[i]).collect(),
+        });
+    }
+
+    match (joints, joint_weights) {
+        (Some(joints), Some(joint_weights)) => {
+            weights.extend(joints.into_iter().zip(joint_weights).map(|(joints, joint_weights)| {
+                joints
+                    .into_iter()
+                    .zip(joint_weights)
+                    .filter(|(_, w)| *w > 0.0)
+                    .collect()
+            }));
+        }
+        _ => weights.resize(verts.vertices.len(), vec![]),
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct Channels {
+    translation: Option<Track>,
+    rotation: Option<Track>,
+    scale: Option<Track>,
+}
+
+impl Channels {
+    fn flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.translation.is_some() {
+            flags |= 1;
+        }
+        if self.scale.is_some() {
+            flags |= 2;
+        }
+        if self.rotation.is_some() {
+            flags |= 4;
+        }
+        flags
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Track> {
+        [&self.translation, &self.rotation, &self.scale].into_iter().flatten()
+    }
+}
+
+struct Track {
+    times: Vec<f32>,
+    values: Vec<Vec<f32>>,
+    step: bool,
+}
+
+impl Track {
+    /// Fails unless the sampler has a value, or a cubic spline triple, for every time.
+    fn new(times: Vec<f32>, values: impl Iterator<Item = Vec<f32>>, interpolation: Interpolation) -> Result<Self, Error> {
+        let mut values: Vec<Vec<f32>> = values.collect();
+        let per_time = if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+        if values.len() != times.len() * per_time {
+            return Err(Error::UnsupportedGltf("animation sampler output of the wrong length"));
+        }
+        if interpolation == Interpolation::CubicSpline {
+            // Keep the value of each (in-tangent, value, out-tangent) triple.
+            values = values.chunks_exact(3).map(|v| v[1].clone()).collect();
+        }
+        Ok(Self {
+            times,
+            values,
+            step: interpolation == Interpolation::Step,
+        })
+    }
+
+    fn sample(&self, time: f32, rotation: bool) -> Vec<f32> {
+        if self.step {
+            // A step key holds its value until the next key, including at its own time.
+            let current = self.times.iter().rposition(|&t| t <= time).unwrap_or(0);
+            return self.values.get(current).cloned().unwrap_or_default();
+        }
+        let next = self.times.iter().position(|&t| t >= time);
+        let (i, j) = match next {
+            None => return self.values.last().cloned().unwrap_or_default(),
+            Some(0) => return self.values.first().cloned().unwrap_or_default(),
+            Some(i) => (i - 1, i),
+        };
+        let (a, b) = (&self.values[i], &self.values[j]);
+        let t = (time - self.times[i]) / (self.times[j] - self.times[i]);
+        if rotation {
+            math::slerp([a[0], a[1], a[2], a[3]], [b[0], b[1], b[2], b[3]], t).to_vec()
+        } else {
+            math::lerp([a[0], a[1], a[2]], [b[0], b[1], b[2]], t).to_vec()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use serde_json::json;
+
+    use crate::{Error, B3D};
+
+    fn floats(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    /// A triangle with `normals` normals, moved by a linear sampler with two times and
+    /// `outputs` translations.
+    fn document(normals: usize, outputs: usize) -> Vec<u8> {
+        let views = [
+            floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]),
+            floats(&[0.0, 0.0, 1.0].repeat(normals)),
+            floats(&[0.0, 1.0]),
+            floats(&[0.0, 0.0, 0.0].repeat(outputs)),
+        ];
+        let mut offset = 0;
+        let buffer_views: Vec<_> = views
+            .iter()
+            .map(|view| {
+                offset += view.len();
+                json!({ "buffer": 0, "byteOffset": offset - view.len(), "byteLength": view.len() })
+            })
+            .collect();
+        let buffer = base64::engine::general_purpose::STANDARD.encode(views.concat());
+        json!({
+            "asset": { "version": "2.0" },
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0, "NORMAL": 1 } }] }],
+            "buffers": [{
+                "byteLength": offset,
+                "uri": format!("data:application/octet-stream;base64,{buffer}"),
+            }],
+            "bufferViews": buffer_views,
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] },
+                { "bufferView": 1, "componentType": 5126, "count": normals, "type": "VEC3" },
+                { "bufferView": 2, "componentType": 5126, "count": 2, "type": "SCALAR", "min": [0], "max": [1] },
+                { "bufferView": 3, "componentType": 5126, "count": outputs, "type": "VEC3" },
+            ],
+            "animations": [{
+                "channels": [{ "sampler": 0, "target": { "node": 0, "path": "translation" } }],
+                "samplers": [{ "input": 2, "output": 3 }],
+            }],
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn imports_matching_accessors() {
+        let b3d = B3D::from_gltf(&document(3, 2)).unwrap();
+        assert_eq!(b3d.node.mesh.vertices.vertices.len(), 3);
+        assert_eq!(b3d.node.mesh.triangles[0].indices, [[0, 2, 1]]);
+        assert_eq!(b3d.node.keys[0].keys.len(), 2);
+    }
+
+    #[test]
+    fn rejects_short_vertex_attributes() {
+        let result = B3D::from_gltf(&document(2, 2));
+        assert!(matches!(result, Err(Error::UnsupportedGltf(_))), "{result:?}");
+    }
+
+    #[test]
+    fn rejects_short_sampler_outputs() {
+        let result = B3D::from_gltf(&document(3, 1));
+        assert!(matches!(result, Err(Error::UnsupportedGltf(_))), "{result:?}");
+    }
+}
@@ -0,0 +1,5 @@
+//! Conversions from other model formats into [`B3D`](crate::B3D).
+
+#[cfg(feature = "gltf")]
+pub mod gltf;
+pub mod obj;
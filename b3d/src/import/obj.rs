@@ -0,0 +1,331 @@
+//! Builds a [`B3D`] from a Wavefront OBJ file and its MTL material library.
+//!
+//! OBJ only describes static geometry, so the result is a single node holding one mesh:
+//!
+//! - Objects and groups are merged into the mesh; the first object name names the node.
+//! - Polygons are split into triangle fans, and lines, points and curves are skipped.
+//! - Each position, texture coordinate and normal combination becomes one vertex, with one
+//!   `TRIS` chunk per material. Vertex colors (`v x y z r g b`) are kept when present.
+//! - Materials become brushes keeping `Kd`, `d` (or `Tr`), `Ns` and fullbright `illum 0`, with
+//!   `map_Kd` as their only texture. Textures also used as `map_d` get the alpha flag. Materials
+//!   missing from the library become plain white brushes.
+//! - Positions and normals are converted from the right-handed space OBJ files are usually
+//!   written in into Blitz3D's left-handed space, and texture coordinates are flipped vertically.
+
+use std::collections::HashMap;
+
+use crate::utils::{Vec2, Vec3, Vec4};
+use crate::{coords, Brush, BrushId, Error, Mesh, Node, Texture, TextureId, Tris, Vertice, Verts, B3D};
+
+impl B3D {
+    /// Converts an OBJ file into a b3d model with a single static node.
+    ///
+    /// Materials named by `usemtl` become white brushes, see [`Self::from_obj_with_mtl`] to
+    /// read them from the material library.
+    pub fn from_obj(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_obj_with_mtl(bytes, &[])
+    }
+
+    /// Converts an OBJ file into a b3d model, taking brushes and textures from the MTL file
+    /// its `mtllib` statement refers to.
+    ///
+    /// See the [module documentation](crate::import::obj) for the lossy parts of the conversion.
+    pub fn from_obj_with_mtl(obj: &[u8], mtl: &[u8]) -> Result<Self, Error> {
+        let materials = read_mtl(&String::from_utf8_lossy(mtl))?;
+        let mut importer = Importer::default();
+        importer.read(&String::from_utf8_lossy(obj))?;
+        Ok(importer.finish(&materials))
+    }
+}
+
+/// A material read from an MTL file.
+#[derive(Debug, Clone)]
+struct Material {
+    color: Vec4,
+    shininess: f32,
+    fullbright: bool,
+    texture: Option<String>,
+    alpha_texture: Option<String>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            color: [1.0; 4],
+            shininess: 0.0,
+            fullbright: false,
+            texture: None,
+            alpha_texture: None,
+        }
+    }
+}
+
+fn invalid(line: usize, reason: &'static str) -> Error {
+    Error::InvalidObj { line, reason }
+}
+
+fn floats<const N: usize>(args: &[&str], line: usize) -> Result<[f32; N], Error> {
+    let mut values = [0.0; N];
+    for (value, arg) in values.iter_mut().zip(args) {
+        *value = arg.parse().map_err(|_| invalid(line, "malformed number"))?;
+    }
+    Ok(values)
+}
+
+fn read_mtl(mtl: &str) -> Result<HashMap<String, Material>, Error> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, Material)> = None;
+    for (index, line) in mtl.lines().enumerate() {
+        let line_number = index + 1;
+        let Some((keyword, rest)) = statement(line) else {
+            continue;
+        };
+        if keyword == "newmtl" {
+            materials.extend(current.take());
+            current = Some((rest.to_owned(), Material::default()));
+            continue;
+        }
+        let Some((_, material)) = current.as_mut() else {
+            continue;
+        };
+        let args: Vec<&str> = rest.split_whitespace().collect();
+        match keyword {
+            "Kd" => {
+                let [r, g, b] = floats(&args, line_number)?;
+                material.color = [r, g, b, material.color[3]];
+            }
+            "d" => material.color[3] = floats::<1>(&args, line_number)?[0],
+            "Tr" => material.color[3] = 1.0 - floats::<1>(&args, line_number)?[0],
+            // The inverse of what the OBJ export writes.
+            "Ns" => material.shininess = (floats::<1>(&args, line_number)?[0] / 128.0).clamp(0.0, 1.0),
+            "illum" => material.fullbright = args.first() == Some(&"0"),
+            "map_Kd" => material.texture = map_file(&args),
+            "map_d" => material.alpha_texture = map_file(&args),
+            _ => {}
+        }
+    }
+    materials.extend(current);
+    Ok(materials)
+}
+
+/// Splits a line into its keyword and arguments, skipping blank lines and comments.
+fn statement(line: &str) -> Option<(&str, &str)> {
+    let line = line.split('#').next()?.trim();
+    if line.is_empty() {
+        return None;
+    }
+    Some(line.split_once(char::is_whitespace).map_or((line, ""), |(keyword, rest)| (keyword, rest.trim())))
+}
+
+/// Takes the file name from the arguments of a `map_*` statement, skipping options such as
+/// `-s 1 1 1` or `-clamp on`.
+fn map_file(args: &[&str]) -> Option<String> {
+    let mut rest = args;
+    while let Some((_, tail)) = rest.split_first().filter(|(arg, _)| arg.starts_with('-')) {
+        let values = tail
+            .iter()
+            .take_while(|arg| arg.parse::<f32>().is_ok() || matches!(**arg, "on" | "off"))
+            .count();
+        rest = &tail[values..];
+    }
+    (!rest.is_empty()).then(|| rest.join(" "))
+}
+
+#[derive(Default)]
+struct Importer {
+    name: Option<String>,
+    positions: Vec<(Vec3, Option<Vec3>)>,
+    uvs: Vec<Vec2>,
+    normals: Vec<Vec3>,
+    /// The vertex each position, uv and normal combination became.
+    vertices: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+    verts: Vec<Vertice>,
+    has_uvs: bool,
+    has_normals: bool,
+    has_colors: bool,
+    /// Triangles by material name, in the order materials were first used.
+    groups: Vec<(Option<String>, Vec<[u32; 3]>)>,
+    material: Option<String>,
+}
+
+impl Importer {
+    fn read(&mut self, obj: &str) -> Result<(), Error> {
+        for (index, line) in obj.lines().enumerate() {
+            let line_number = index + 1;
+            let Some((keyword, rest)) = statement(line) else {
+                continue;
+            };
+            let args: Vec<&str> = rest.split_whitespace().collect();
+            match keyword {
+                "v" => {
+                    if args.len() < 3 {
+                        return Err(invalid(line_number, "vertex with fewer than 3 coordinates"));
+                    }
+                    let position = floats(&args, line_number)?;
+                    // Some tools append an RGB color to the position.
+                    let color = if args.len() >= 6 {
+                        self.has_colors = true;
+                        Some(floats(&args[3..], line_number)?)
+                    } else {
+                        None
+                    };
+                    self.positions.push((position, color));
+                }
+                "vt" => {
+                    let [u, v] = floats(&args, line_number)?;
+                    self.uvs.push([u, v]);
+                }
+                "vn" => self.normals.push(floats(&args, line_number)?),
+                "f" => self.face(&args, line_number)?,
+                "usemtl" => self.material = Some(rest.to_owned()),
+                "o" if self.name.is_none() && !rest.is_empty() => self.name = Some(rest.to_owned()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn face(&mut self, args: &[&str], line: usize) -> Result<(), Error> {
+        if args.len() < 3 {
+            return Err(invalid(line, "face with fewer than 3 vertices"));
+        }
+        let corners = args
+            .iter()
+            .map(|arg| self.vertex(arg, line))
+            .collect::<Result<Vec<u32>, Error>>()?;
+
+        let group = match self.groups.iter().position(|(material, _)| *material == self.material) {
+            Some(group) => group,
+            None => {
+                self.groups.push((self.material.clone(), vec![]));
+                self.groups.len() - 1
+            }
+        };
+        for pair in corners[1..].windows(2) {
+            // Reverse the winding along with the handedness.
+            self.groups[group].1.push([corners[0], pair[1], pair[0]]);
+        }
+        Ok(())
+    }
+
+    /// Returns the vertex for a `position/uv/normal` reference, adding it if it's new.
+    fn vertex(&mut self, reference: &str, line: usize) -> Result<u32, Error> {
+        let mut parts = reference.split('/');
+        let position = resolve(parts.next(), self.positions.len(), line)?
+            .ok_or_else(|| invalid(line, "face vertex without a position"))?;
+        let uv = resolve(parts.next(), self.uvs.len(), line)?;
+        let normal = resolve(parts.next(), self.normals.len(), line)?;
+
+        let key = (position, uv, normal);
+        if let Some(&vertex) = self.vertices.get(&key) {
+            return Ok(vertex);
+        }
+        let (position, color) = self.positions[position];
+        let color = color.map_or([1.0; 4], |[r, g, b]| [r, g, b, 1.0]);
+        let tex_coords = uv.map(|uv| {
+            self.has_uvs = true;
+            let [u, v] = self.uvs[uv];
+            vec![[u, 1.0 - v]]
+        });
+        let normal = normal.map_or([0.0; 3], |normal| {
+            self.has_normals = true;
+            coords::to_left_handed(self.normals[normal])
+        });
+
+        let vertex = self.verts.len() as u32;
+        self.verts.push(Vertice {
+            position: coords::to_left_handed(position),
+            normal,
+            color,
+            tex_coords: tex_coords.unwrap_or_default(),
+        });
+        self.vertices.insert(key, vertex);
+        Ok(vertex)
+    }
+
+    fn finish(mut self, materials: &HashMap<String, Material>) -> B3D {
+        let mut textures: Vec<Texture> = vec![];
+        let mut brushes: Vec<Brush> = vec![];
+        let mut texture_id = |file: &str, alpha: bool| {
+            let index = match textures.iter().position(|texture| texture.file == *file) {
+                Some(index) => index,
+                None => {
+                    textures.push(Texture::new(file));
+                    textures.len() - 1
+                }
+            };
+            if alpha {
+                textures[index].flags |= 2;
+            }
+            TextureId::new(index)
+        };
+
+        let mut triangles = vec![];
+        for (name, indices) in std::mem::take(&mut self.groups) {
+            let brush_id = match name {
+                Some(name) => {
+                    let material = materials.get(&name).cloned().unwrap_or_default();
+                    let texture = material.texture.as_deref().map(|file| {
+                        texture_id(file, material.alpha_texture.as_deref() == Some(file))
+                    });
+                    brushes.push(Brush {
+                        name: name.into(),
+                        color: material.color,
+                        shininess: material.shininess,
+                        blend: 1,
+                        fx: if material.fullbright { 1 } else { 0 },
+                        texture_id: texture.into_iter().collect(),
+                    });
+                    BrushId::new(brushes.len() - 1)
+                }
+                None => BrushId::NONE,
+            };
+            triangles.push(Tris { brush_id, indices });
+        }
+
+        // Vertices written without uvs still need a pair once any vertex has one.
+        if self.has_uvs {
+            for vertex in &mut self.verts {
+                if vertex.tex_coords.is_empty() {
+                    vertex.tex_coords.push([0.0, 0.0]);
+                }
+            }
+        }
+        let vertices = Verts {
+            flags: u32::from(self.has_normals) | (u32::from(self.has_colors) << 1),
+            tex_coord_sets: u32::from(self.has_uvs),
+            tex_coord_set_size: if self.has_uvs { 2 } else { 0 },
+            vertices: self.verts,
+        };
+
+        B3D {
+            version: 1,
+            textures,
+            brushes,
+            node: Node {
+                name: self.name.as_deref().unwrap_or("ROOT").into(),
+                scale: [1.0; 3],
+                rotation: [1.0, 0.0, 0.0, 0.0],
+                mesh: Mesh {
+                    brush_id: BrushId::NONE,
+                    vertices,
+                    triangles,
+                },
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Resolves a 1-based or negative, relative OBJ index into one of `count` elements.
+fn resolve(index: Option<&str>, count: usize, line: usize) -> Result<Option<usize>, Error> {
+    let Some(index) = index.filter(|index| !index.is_empty()) else {
+        return Ok(None);
+    };
+    let index: i64 = index.parse().map_err(|_| invalid(line, "malformed index"))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if !(0..count as i64).contains(&resolved) {
+        return Err(invalid(line, "index out of range"));
+    }
+    Ok(Some(resolved as usize))
+}
@@ -0,0 +1,89 @@
+//! Blitz3D's multitexturing rules, so renderers don't each have to interpret brush and texture
+//! flags themselves.
+
+use crate::{Brush, Texture, TextureId};
+
+/// How a texture layer combines with the layers beneath it, from `Texture::blend`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextureBlend {
+    /// Replace what's beneath, or blend by the texture's alpha if it has one (1).
+    Alpha,
+    /// Multiply with what's beneath (2), Blitz3D's default.
+    Multiply,
+    /// Add to what's beneath (3).
+    Add,
+    /// Treat the texture as a normal map lit by the vertex colors (4).
+    Dot3,
+    /// Multiply with what's beneath, then double (5).
+    Multiply2,
+}
+
+impl TextureBlend {
+    /// Decodes a blend mode, or returns `None` for 0, which disables the layer. Unknown modes
+    /// multiply.
+    pub fn from_raw(blend: u32) -> Option<Self> {
+        match blend {
+            0 => None,
+            1 => Some(Self::Alpha),
+            3 => Some(Self::Add),
+            4 => Some(Self::Dot3),
+            5 => Some(Self::Multiply2),
+            _ => Some(Self::Multiply),
+        }
+    }
+}
+
+/// How a texture layer is mapped when it is an environment map rather than uv mapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EnvMap {
+    /// Flag 64.
+    Spherical,
+    /// Flag 128.
+    Cubic,
+}
+
+/// A texture layer of a brush, see [`Brush::resolve_layers`].
+#[derive(Debug, Clone, Copy)]
+pub struct TextureLayer<'a> {
+    /// The texture stage the layer occupies on the brush.
+    pub stage: usize,
+    pub texture_id: TextureId,
+    pub texture: &'a Texture,
+    pub blend: TextureBlend,
+    /// Whether the layer is sampled with the second texture coordinate set.
+    pub uses_secondary_uv: bool,
+    pub env_map: Option<EnvMap>,
+}
+
+impl Brush {
+    /// Returns the texture layers the brush draws with, from the first stage to the last.
+    ///
+    /// Stages without a texture, with an id outside `textures` or disabled by a blend mode of 0
+    /// are left out, as Blitz3D skips them.
+    pub fn resolve_layers<'a>(&self, textures: &'a [Texture]) -> Vec<TextureLayer<'a>> {
+        self.texture_id
+            .iter()
+            .enumerate()
+            .filter_map(|(stage, &texture_id)| {
+                let texture = textures.get(texture_id.get()?)?;
+                let env_map = if texture.flags & 128 != 0 {
+                    Some(EnvMap::Cubic)
+                } else if texture.flags & 64 != 0 {
+                    Some(EnvMap::Spherical)
+                } else {
+                    None
+                };
+                Some(TextureLayer {
+                    stage,
+                    texture_id,
+                    texture,
+                    blend: TextureBlend::from_raw(texture.blend)?,
+                    uses_secondary_uv: texture.uses_secondary_uvs(),
+                    env_map,
+                })
+            })
+            .collect()
+    }
+}
@@ -1,9 +1,46 @@
 use std::io::Cursor;
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, SeekFrom};
 use byteorder::{ReadBytesExt, LittleEndian};
 
+pub mod coords;
+pub mod export;
+mod anim;
+mod extract;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+mod id;
+pub mod import;
+mod layers;
+mod math;
+mod merge;
+mod name;
+mod optimize;
+mod options;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "pyo3")]
+mod python;
+pub mod repair;
+pub mod retarget;
+mod scan;
+mod tree;
+mod usage;
 mod utils;
-
+pub mod validate;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod write;
+
+pub use anim::{Clip, PlaybackMode};
+pub use extract::{MaterialData, MeshData};
+pub use id::{BrushId, NodeId, TextureId};
+pub use layers::{EnvMap, TextureBlend, TextureLayer};
+pub use name::{Interner, Name};
+pub use options::{Limits, ParseContext, ParseOptions, ProgressCallback};
+pub use scan::Summary;
+pub use tree::ChunkTree;
+pub use usage::{BrushUsage, TrisUsage};
+pub use utils::{Chunk, Tag};
 use utils::*;
 
 #[derive(thiserror::Error, Debug)]
@@ -14,11 +51,26 @@ pub enum Error {
     Utf8(#[from] std::string::FromUtf8Error),
     #[error("Invalid Chunk: {0}")]
     InvalidChunk(Chunk),
+    #[error("Limit exceeded: {what} (limit: {limit})")]
+    LimitExceeded { what: &'static str, limit: usize },
+    #[error("Invalid chunk order: {0}")]
+    ChunkOrder(validate::OrderViolation),
+    #[error("Invalid OBJ at line {line}: {reason}")]
+    InvalidObj { line: usize, reason: &'static str },
+    #[cfg(feature = "gltf")]
+    #[error(transparent)]
+    Gltf(#[from] gltf::Error),
+    #[cfg(feature = "gltf")]
+    #[error("Unsupported glTF: {0}")]
+    UnsupportedGltf(&'static str),
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct Texture {
-    pub file: String,
+    pub file: Name,
     pub flags: u32,
     pub blend: u32,
     pub position: Vec2,
@@ -27,11 +79,11 @@ pub struct Texture {
 }
 
 impl Texture {
-    pub fn read<T>(data: &mut T) -> Result<Self, Error>
+    pub fn read<T>(data: &mut T, ctx: &mut ParseContext) -> Result<Self, Error>
     where
         T: Read + Seek
     {
-        let file = read_null_term_string(data);
+        let file = ctx.read_name(data)?;
         let flags = data.read_u32::<LittleEndian>()?;
         let blend = data.read_u32::<LittleEndian>()?;
         let mut position = [0.0; 2];
@@ -49,24 +101,51 @@ impl Texture {
             rotation,
         })
     }
+
+    /// Creates an untransformed texture loaded from `file` with the flags and blend Blitz3D's
+    /// `LoadTexture` defaults to: color, mipmapped and multiplied.
+    pub fn new(file: impl Into<Name>) -> Self {
+        Self {
+            file: file.into(),
+            flags: 1 | 8,
+            blend: 2,
+            position: [0.0, 0.0],
+            scale: [1.0, 1.0],
+            rotation: 0.0,
+        }
+    }
+
+    /// Returns whether flag `0x10000` is set, which samples the texture with the second
+    /// texture coordinate set, as lightmaps usually are.
+    pub fn uses_secondary_uvs(&self) -> bool {
+        self.flags & 0x10000 != 0
+    }
+
+    /// Returns the index of the texture coordinate set the texture is sampled with.
+    pub fn uv_set(&self) -> usize {
+        usize::from(self.uses_secondary_uvs())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct Brush {
-    pub name: String,
+    pub name: Name,
 	pub color: Vec4,
 	pub shininess: f32,
 	pub blend: u32,
 	pub fx: u32,
-	pub texture_id: Vec<u32>,
+	pub texture_id: Vec<TextureId>,
 }
 
 impl Brush {
-    pub fn read<T>(data: &mut T, n_texs: usize) -> Result<Self, Error>
+    pub fn read<T>(data: &mut T, n_texs: usize, ctx: &mut ParseContext) -> Result<Self, Error>
     where
         T: Read + Seek
     {
-        let name = read_null_term_string(data);
+        let name = ctx.read_name(data)?;
         let mut color = [0.0; 4];
         data.read_f32_into::<LittleEndian>(&mut color)?;
         let shininess = data.read_f32::<LittleEndian>()?;
@@ -76,7 +155,7 @@ impl Brush {
         let mut texture_id = vec![];
 
         for _ in 0..n_texs {
-            texture_id.push(data.read_u32::<LittleEndian>()?);
+            texture_id.push(TextureId::from_raw(data.read_u32::<LittleEndian>()?));
         }
 
         Ok(Self {
@@ -90,15 +169,22 @@ impl Brush {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct Vertice {
     pub position: Vec3,
     pub normal: Vec3,
     pub color: Vec4,
-    pub tex_coords: Vec2,
+    /// One uv pair per texture coordinate set. Components past the second are dropped.
+    pub tex_coords: Vec<Vec2>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct Verts {
     pub flags: u32,
     pub tex_coord_sets: u32,
@@ -107,17 +193,25 @@ pub struct Verts {
 }
 
 impl Verts {
-    pub fn read<T>(data: &mut T, next: u64) -> Result<Self, Error>
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "VRTS", level = "trace", skip_all))]
+    pub fn read<T>(data: &mut T, next: u64, ctx: &mut ParseContext) -> Result<Self, Error>
     where
         T: Read + Seek
     {
         let flags = data.read_u32::<LittleEndian>()?;
         let tex_coord_sets = data.read_u32::<LittleEndian>()?;
         let tex_coord_set_size = data.read_u32::<LittleEndian>()?;
+        if tex_coord_sets > 8 {
+            return Err(Error::LimitExceeded { what: "texture coordinate sets", limit: 8 });
+        }
+        if tex_coord_set_size > 4 {
+            return Err(Error::LimitExceeded { what: "texture coordinate set size", limit: 4 });
+        }
 
         let mut vertices: Vec<Vertice> = Vec::new();
 
         while eof(data, next)? {
+            ctx.add_vertex()?;
             let mut position = [0.0; 3];
             data.read_f32_into::<LittleEndian>(&mut position)?;
             let mut normal = [0.0; 3];
@@ -128,8 +222,12 @@ impl Verts {
             if flags & 2 != 0 {
                 data.read_f32_into::<LittleEndian>(&mut color)?;
             }
-            let mut tex_coords = [0.0; 2];
-            data.read_f32_into::<LittleEndian>(&mut tex_coords)?;
+            let mut tex_coords = Vec::with_capacity(tex_coord_sets as usize);
+            for _ in 0..tex_coord_sets {
+                let mut set = [0.0; 4];
+                data.read_f32_into::<LittleEndian>(&mut set[..tex_coord_set_size as usize])?;
+                tex_coords.push([set[0], set[1]]);
+            }
 
             vertices.push(Vertice {
                 position,
@@ -139,6 +237,8 @@ impl Verts {
             });
         }
 
+        trace_event!(flags, vertices = vertices.len(), "read vertices");
+
         Ok(Self {
             flags,
             tex_coord_sets,
@@ -146,28 +246,72 @@ impl Verts {
             vertices,
         })
     }
+
+    pub fn positions(&self) -> Vec<Vec3> {
+        self.vertices.iter().map(|v| v.position).collect()
+    }
+
+    /// Returns whether the vertices store normals. Without them every `normal` is zero, and
+    /// renderers are expected to generate their own.
+    pub fn has_normals(&self) -> bool {
+        self.flags & 1 != 0
+    }
+
+    /// Returns whether the vertices store colors. Without them every `color` is zero, and
+    /// Blitz3D draws the vertices white.
+    pub fn has_colors(&self) -> bool {
+        self.flags & 2 != 0
+    }
+
+    /// Returns the normals, or `None` if the vertices don't store any.
+    pub fn normals(&self) -> Option<Vec<Vec3>> {
+        self.has_normals().then(|| self.vertices.iter().map(|v| v.normal).collect())
+    }
+
+    /// Returns the colors, or `None` if the vertices don't store any.
+    pub fn colors(&self) -> Option<Vec<Vec4>> {
+        self.has_colors().then(|| self.vertices.iter().map(|v| v.color).collect())
+    }
+
+    /// Iterates over texture coordinate set `n`, or returns `None` if there are fewer sets.
+    pub fn iter_uv_set(&self, n: usize) -> Option<impl Iterator<Item = Vec2> + '_> {
+        (n < self.tex_coord_sets as usize)
+            .then(|| self.vertices.iter().map(move |v| v.tex_coords.get(n).copied().unwrap_or_default()))
+    }
+
+    /// Returns texture coordinate set `n`, or `None` if there are fewer sets.
+    pub fn uv_set(&self, n: usize) -> Option<Vec<Vec2>> {
+        self.iter_uv_set(n).map(Iterator::collect)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct Tris {
-    pub brush_id: u32,
+    pub brush_id: BrushId,
     pub indices: Vec<[u32; 3]>,
 }
 
 impl Tris {
-    pub fn read<T>(data: &mut T, next: u64) -> Result<Self, Error>
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "TRIS", level = "trace", skip_all))]
+    pub fn read<T>(data: &mut T, next: u64, ctx: &mut ParseContext) -> Result<Self, Error>
     where
         T: Read + Seek
     {
-        let brush_id = data.read_u32::<LittleEndian>()?;
+        let brush_id = BrushId::from_raw(data.read_u32::<LittleEndian>()?);
         let mut indices = Vec::new();
 
         while eof(data, next)? {
+            ctx.add_triangle()?;
             let mut face = [0; 3];
             data.read_u32_into::<LittleEndian>(&mut face)?;
             indices.push(face);
         }
 
+        trace_event!(brush_id = %brush_id, triangles = indices.len(), "read triangles");
+
         Ok(Self {
             brush_id,
             indices,
@@ -175,26 +319,30 @@ impl Tris {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct Mesh {
-    pub brush_id: u32,
+    pub brush_id: BrushId,
     pub vertices: Verts,
     pub triangles: Vec<Tris>,
 }
 
 impl Mesh {
-    pub fn read<T>(data: &mut T, next: u64) -> Result<Self, Error>
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "MESH", level = "trace", skip_all))]
+    pub fn read<T>(data: &mut T, next: u64, ctx: &mut ParseContext) -> Result<Self, Error>
     where
         T: Read + Seek
     {
-        let brush_id = data.read_u32::<LittleEndian>()?;
-        let vert_chunk = Chunk::read(data)?;
-        let vertices = Verts::read(data, vert_chunk.next)?;
+        let brush_id = BrushId::from_raw(data.read_u32::<LittleEndian>()?);
+        let vert_chunk = ctx.read_chunk(data)?;
+        let vertices = Verts::read(data, vert_chunk.next, ctx)?;
         let mut triangles = Vec::new();
 
         while eof(data, next)? {
-            let tri_chunk = Chunk::read(data)?;
-            triangles.push(Tris::read(data, tri_chunk.next)?);
+            let tri_chunk = ctx.read_chunk(data)?;
+            triangles.push(Tris::read(data, tri_chunk.next, ctx)?);
         }
 
         Ok(Self {
@@ -205,7 +353,10 @@ impl Mesh {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct Bone {
     pub vertex_id: u32,
     pub weight: f32,
@@ -223,7 +374,10 @@ impl Bone {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct Key {
     pub frame: u32,
     pub position: Vec3,
@@ -260,7 +414,41 @@ impl Key {
     }
 }
 
-#[derive(Debug, Default)]
+/// A `KEYS` chunk. Only the fields of each key selected by `flags` are meaningful: 1 for
+/// position, 2 for scale and 4 for rotation.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
+pub struct Keys {
+    pub flags: u32,
+    pub keys: Vec<Key>,
+}
+
+impl Keys {
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "KEYS", level = "trace", skip_all))]
+    pub fn read<T>(data: &mut T, next: u64, ctx: &mut ParseContext) -> Result<Self, Error>
+    where
+        T: Read + Seek
+    {
+        let flags = data.read_u32::<LittleEndian>()?;
+        let mut keys = vec![];
+        while eof(data, next)? {
+            ctx.add_key()?;
+            keys.push(Key::read(data, flags)?);
+        }
+        trace_event!(flags, keys = keys.len(), "read keys");
+        Ok(Self {
+            flags,
+            keys,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct Animation {
     pub flags: u32,
     pub frames: u32,
@@ -268,6 +456,7 @@ pub struct Animation {
 }
 
 impl Animation {
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "ANIM", level = "trace", skip_all))]
     pub fn read<T>(data: &mut T, _next: u64) -> Result<Self, Error>
     where
         T: Read + Seek
@@ -280,7 +469,10 @@ impl Animation {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct Sequence {
     pub name: String,
     pub first_frame: u32,
@@ -289,12 +481,13 @@ pub struct Sequence {
 }
 
 impl Sequence {
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "SEQS", level = "trace", skip_all))]
     pub fn read<T>(data: &mut T, _next: u64) -> Result<Self, Error>
     where
         T: Read + Seek
     {
         Ok(Self {
-            name: read_null_term_string(data),
+            name: read_null_term_string(data)?,
             first_frame: data.read_u32::<LittleEndian>()?,
             last_frame: data.read_u32::<LittleEndian>()?,
             unused: data.read_u32::<LittleEndian>()?,
@@ -302,27 +495,46 @@ impl Sequence {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct Node {
-    pub name: String,
+    pub name: Name,
     pub position: Vec3,
     pub scale: Vec3,
     pub rotation: Vec4,
     pub mesh: Mesh,
     pub bones: Vec<Bone>,
-    pub key_flags: u32,
-    pub keys: Vec<Key>,
+    /// Every `KEYS` chunk of the node, as exporters may write one per animated channel.
+    pub keys: Vec<Keys>,
     pub children: Vec<Node>,
     pub animation: Animation,
     pub sequences: Vec<Sequence>,
 }
 
 impl Node {
-    pub fn read<T>(data: &mut T, next: u64) -> Result<Self, Error>
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "NODE", level = "debug", skip_all))]
+    pub fn read<T>(data: &mut T, next: u64, ctx: &mut ParseContext) -> Result<Self, Error>
     where
         T: Read + Seek
     {
-        let name = read_null_term_string(data);
+        ctx.enter_node()?;
+        let mut node = Self::read_fields(data, ctx)?;
+        while eof(data, next)? {
+            let chunk = ctx.read_chunk(data)?;
+            node.read_chunk(data, chunk, ctx)?;
+        }
+        ctx.leave_node();
+        Ok(node)
+    }
+
+    /// Reads the name and transform that start a `NODE` chunk.
+    pub(crate) fn read_fields<T>(data: &mut T, ctx: &mut ParseContext) -> Result<Self, Error>
+    where
+        T: Read + Seek
+    {
+        let name = ctx.read_name(data)?;
         let mut position = [0.0; 3];
         data.read_f32_into::<LittleEndian>(&mut position)?;
         let mut scale = [0.0; 3];
@@ -330,49 +542,38 @@ impl Node {
         let mut rotation = [0.0; 4];
         data.read_f32_into::<LittleEndian>(&mut rotation)?;
 
-        let mut mesh = Mesh::default();
-        let mut children = Vec::new();
-        let mut bones = Vec::new();
-        let mut animation = Animation::default();
-        let mut sequences = Vec::new();
-        let mut key_flags = 0;
-        let mut keys = Vec::new();
-
-        while eof(data, next)? {
-            let chunk = Chunk::read(data)?;
-            match chunk.tag.as_str() {
-                "MESH" => mesh = Mesh::read(data, chunk.next)?,
-                "BONE" => bones = Self::read_bones(data, chunk.next)?,
-                "KEYS" => {
-                    key_flags = data.read_u32::<LittleEndian>()?;
-                    keys = Self::read_keys(data, chunk.next, key_flags)?;
-                },
-                "NODE" => children.push(Node::read(data, chunk.next)?),
-                "ANIM" => animation = Animation::read(data, chunk.next)?,
-                "SEQS" => sequences.push(Sequence::read(data, chunk.next)?),
-                "PIVO" => {
-                    let mut buf = vec![0; chunk.size as usize];
-                    data.read_exact(&mut buf)?;
-                }
-                _ => return Err(Error::InvalidChunk(chunk).into()),
-            }
-        }
+        trace_event!(name = %name, "read node");
 
         Ok(Self {
             name,
             position,
             scale,
             rotation,
-            mesh,
-            bones,
-            key_flags,
-            keys,
-            children,
-            animation,
-            sequences,
+            ..Default::default()
         })
     }
 
+    /// Reads a chunk nested in the node, whose header has just been read.
+    pub(crate) fn read_chunk<T>(&mut self, data: &mut T, chunk: Chunk, ctx: &mut ParseContext) -> Result<(), Error>
+    where
+        T: Read + Seek
+    {
+        match chunk.tag {
+            Tag::MESH => self.mesh = Mesh::read(data, chunk.next, ctx)?,
+            Tag::BONE => self.bones = Self::read_bones(data, chunk.next)?,
+            Tag::KEYS => self.keys.push(Keys::read(data, chunk.next, ctx)?),
+            Tag::NODE => self.children.push(Node::read(data, chunk.next, ctx)?),
+            Tag::ANIM => self.animation = Animation::read(data, chunk.next)?,
+            Tag::SEQS => self.sequences.push(Sequence::read(data, chunk.next)?),
+            Tag::PIVO => {
+                data.seek(SeekFrom::Start(chunk.next))?;
+            }
+            _ => return Err(Error::InvalidChunk(chunk)),
+        }
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "BONE", level = "trace", skip_all))]
     pub fn read_bones<T>(data: &mut T, next: u64) -> Result<Vec<Bone>, Error>
     where
         T: Read + Seek
@@ -381,22 +582,64 @@ impl Node {
         while eof(data, next)? {
             bones.push(Bone::read(data)?);
         }
+        trace_event!(weights = bones.len(), "read bones");
         Ok(bones)
     }
 
-    pub fn read_keys<T>(data: &mut T, next: u64, flags: u32) -> Result<Vec<Key>, Error>
-    where
-        T: Read + Seek
-    {
-        let mut keys = vec![];
-        while eof(data, next)? {
-            keys.push(Key::read(data, flags)?);
+    /// Iterates over this node and all of its descendants in depth-first order.
+    pub fn iter(&self) -> Nodes<'_> {
+        Nodes { stack: vec![self] }
+    }
+
+    /// Classifies the node by what it carries. Skin weights live on the bones themselves in
+    /// b3d, so a node is a bone exactly when it has a `BONE` chunk.
+    pub fn kind(&self) -> NodeKind {
+        if !self.bones.is_empty() {
+            NodeKind::Bone
+        } else if !self.mesh.vertices.vertices.is_empty() {
+            NodeKind::Mesh
+        } else {
+            NodeKind::Pivot
         }
-        Ok(keys)
+    }
+
+    /// Returns whether any `KEYS` chunk of the node has keys.
+    pub fn is_animated(&self) -> bool {
+        self.keys.iter().any(|keys| !keys.keys.is_empty())
+    }
+}
+
+/// What a [`Node`] represents, see [`Node::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NodeKind {
+    /// A node with vertices.
+    Mesh,
+    /// A node deforming vertices through skin weights.
+    Bone,
+    /// A node with neither, used for grouping or as an attachment point.
+    Pivot,
+}
+
+/// A depth-first iterator over a node hierarchy, created by [`Node::iter`].
+#[derive(Debug, Clone)]
+pub struct Nodes<'a> {
+    stack: Vec<&'a Node>,
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.children.iter().rev());
+        Some(node)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(get_all, skip_from_py_object, module = "b3d"))]
 pub struct B3D {
     pub version: u32,
     pub textures: Vec<Texture>,
@@ -406,11 +649,76 @@ pub struct B3D {
 
 impl B3D {
     pub fn read(data: &[u8]) -> Result<Self, Error> {
+        Self::read_with_options(data, &ParseOptions::default())
+    }
+
+    /// Returns the brush `id` refers to, if any.
+    pub fn brush(&self, id: BrushId) -> Option<&Brush> {
+        self.brushes.get(id.get()?)
+    }
+
+    /// Returns the texture `id` refers to, if any.
+    pub fn texture(&self, id: TextureId) -> Option<&Texture> {
+        self.textures.get(id.get()?)
+    }
+
+    /// Iterates over every node in depth-first order, starting with the root.
+    pub fn nodes(&self) -> Nodes<'_> {
+        self.node.iter()
+    }
+
+    /// Returns the node `id` refers to, counting nodes in depth-first order.
+    pub fn node_at(&self, id: NodeId) -> Option<&Node> {
+        self.nodes().nth(id.get()?)
+    }
+
+    /// Returns the node at `path`, the names of the nodes from the root down joined by `/`,
+    /// such as `ROOT/Bip01/Bip01 Spine`.
+    pub fn node_by_path(&self, path: &str) -> Option<&Node> {
+        self.node_chain(path)?.pop()
+    }
+
+    /// Returns the nodes along `path`, from the root down.
+    pub(crate) fn node_chain(&self, path: &str) -> Option<Vec<&Node>> {
+        let mut segments = path.split('/');
+        if segments.next()? != self.node.name {
+            return None;
+        }
+        let mut chain = vec![&self.node];
+        for segment in segments {
+            let parent = chain[chain.len() - 1];
+            chain.push(parent.children.iter().find(|child| child.name == segment)?);
+        }
+        Some(chain)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "BB3D", level = "debug", skip_all, fields(len = data.len()), err))]
+    pub fn read_with_options(data: &[u8], options: &ParseOptions) -> Result<Self, Error> {
+        Self::read_root(data, options, |data, chunk, ctx| Node::read(data, chunk.next, ctx))
+    }
+
+    /// Parses everything but the root node, which is left to `read_node`.
+    pub(crate) fn read_root<F>(data: &[u8], options: &ParseOptions, mut read_node: F) -> Result<Self, Error>
+    where
+        F: FnMut(&mut Cursor<&[u8]>, Chunk, &mut ParseContext) -> Result<Node, Error>
+    {
+        if options.strict_order {
+            let tree_options = ParseOptions {
+                limits: options.limits.clone(),
+                ..Default::default()
+            };
+            let tree = ChunkTree::read_with_options(data, &tree_options)?;
+            if let Some(violation) = validate::chunk_order(&tree).into_iter().next() {
+                return Err(Error::ChunkOrder(violation));
+            }
+        }
+
         let mut cursor = Cursor::new(data);
+        let mut ctx = ParseContext::new(options, data.len() as u64);
 
-        let main_chunk = Chunk::read(&mut cursor)?;
-        if main_chunk.tag != "BB3D" {
-            return Err(Error::InvalidChunk(main_chunk).into());
+        let main_chunk = ctx.read_chunk(&mut cursor)?;
+        if main_chunk.tag != Tag::BB3D {
+            return Err(Error::InvalidChunk(main_chunk));
         }
         let version = cursor.read_u32::<LittleEndian>()?;
         let mut textures = Vec::new();
@@ -418,19 +726,20 @@ impl B3D {
         let mut node = Node::default();
 
         while eof(&mut cursor, main_chunk.next)? {
-            let chunk = Chunk::read(&mut cursor)?;
-            match chunk.tag.as_str() {
-                "TEXS" => textures = Self::read_textures(&mut cursor, chunk.next)?,
-                "BRUS" => brushes = Self::read_brushes(&mut cursor, chunk.next)?,
-                "NODE" => node = Node::read(&mut cursor, chunk.next)?,
-                "PIVO" => {
-                    let mut buf = vec![0; chunk.size as usize];
-                    cursor.read_exact(&mut buf)?;
+            let chunk = ctx.read_chunk(&mut cursor)?;
+            match chunk.tag {
+                Tag::TEXS => textures = Self::read_textures(&mut cursor, chunk.next, &mut ctx)?,
+                Tag::BRUS => brushes = Self::read_brushes(&mut cursor, chunk.next, &mut ctx)?,
+                Tag::NODE => node = read_node(&mut cursor, chunk, &mut ctx)?,
+                Tag::PIVO => {
+                    cursor.seek(SeekFrom::Start(chunk.next))?;
                 }
-                _ => return Err(Error::InvalidChunk(chunk).into()),
+                _ => return Err(Error::InvalidChunk(chunk)),
             }
         }
 
+        ctx.progress(main_chunk.next, &main_chunk);
+
         Ok(Self {
             version,
             textures,
@@ -439,26 +748,31 @@ impl B3D {
         })
     }
 
-    pub fn read_textures<T>(data: &mut T, next: u64) -> Result<Vec<Texture>, Error>
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "TEXS", level = "trace", skip_all))]
+    pub fn read_textures<T>(data: &mut T, next: u64, ctx: &mut ParseContext) -> Result<Vec<Texture>, Error>
     where
         T: Read + Seek
     {
         let mut textures = vec![];
         while eof(data, next)? {
-            textures.push(Texture::read(data)?);
+            ctx.add_texture()?;
+            textures.push(Texture::read(data, ctx)?);
         }
+        trace_event!(textures = textures.len(), "read textures");
         Ok(textures)
     }
 
-    pub fn read_brushes<T>(data: &mut T, next: u64) -> Result<Vec<Brush>, Error>
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "BRUS", level = "trace", skip_all))]
+    pub fn read_brushes<T>(data: &mut T, next: u64, ctx: &mut ParseContext) -> Result<Vec<Brush>, Error>
     where
         T: Read + Seek
     {
         let mut brushes = vec![];
         let n_texs = data.read_u32::<LittleEndian>()?;
         while eof(data, next)? {
-            brushes.push(Brush::read(data, n_texs as usize)?);
+            brushes.push(Brush::read(data, n_texs as usize, ctx)?);
         }
+        trace_event!(brushes = brushes.len(), "read brushes");
         Ok(brushes)
     }
 }
@@ -0,0 +1,165 @@
+//! Small column-major matrix and `[x, y, z, w]` quaternion helpers.
+
+use crate::utils::{Vec3, Vec4};
+
+pub type Mat4 = [f32; 16];
+
+pub const IDENTITY: Mat4 = [
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 1.0, 0.0,
+    0.0, 0.0, 0.0, 1.0,
+];
+
+/// Builds the matrix scaling by `scale`, then rotating by `rotation`, then translating.
+pub fn compose(translation: Vec3, rotation: Vec4, scale: Vec3) -> Mat4 {
+    let [x, y, z, w] = rotation;
+    let (xx, yy, zz) = (x * x, y * y, z * z);
+    let (xy, xz, yz) = (x * y, x * z, y * z);
+    let (wx, wy, wz) = (w * x, w * y, w * z);
+
+    [
+        (1.0 - 2.0 * (yy + zz)) * scale[0],
+        2.0 * (xy + wz) * scale[0],
+        2.0 * (xz - wy) * scale[0],
+        0.0,
+        2.0 * (xy - wz) * scale[1],
+        (1.0 - 2.0 * (xx + zz)) * scale[1],
+        2.0 * (yz + wx) * scale[1],
+        0.0,
+        2.0 * (xz + wy) * scale[2],
+        2.0 * (yz - wx) * scale[2],
+        (1.0 - 2.0 * (xx + yy)) * scale[2],
+        0.0,
+        translation[0],
+        translation[1],
+        translation[2],
+        1.0,
+    ]
+}
+
+pub fn mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut out = [0.0; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            out[col * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[col * 4 + k]).sum();
+        }
+    }
+    out
+}
+
+/// Inverts a general 4x4 matrix, returning the identity for singular matrices.
+pub fn inverse(m: &Mat4) -> Mat4 {
+    let mut inv = [0.0; 16];
+
+    inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+        + m[9] * m[7] * m[14] + m[13] * m[6] * m[11] - m[13] * m[7] * m[10];
+    inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+        - m[8] * m[7] * m[14] - m[12] * m[6] * m[11] + m[12] * m[7] * m[10];
+    inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+        + m[8] * m[7] * m[13] + m[12] * m[5] * m[11] - m[12] * m[7] * m[9];
+    inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+        - m[8] * m[6] * m[13] - m[12] * m[5] * m[10] + m[12] * m[6] * m[9];
+    inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+        - m[9] * m[3] * m[14] - m[13] * m[2] * m[11] + m[13] * m[3] * m[10];
+    inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+        + m[8] * m[3] * m[14] + m[12] * m[2] * m[11] - m[12] * m[3] * m[10];
+    inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+        - m[8] * m[3] * m[13] - m[12] * m[1] * m[11] + m[12] * m[3] * m[9];
+    inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+        + m[8] * m[2] * m[13] + m[12] * m[1] * m[10] - m[12] * m[2] * m[9];
+    inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+        + m[5] * m[3] * m[14] + m[13] * m[2] * m[7] - m[13] * m[3] * m[6];
+    inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+        - m[4] * m[3] * m[14] - m[12] * m[2] * m[7] + m[12] * m[3] * m[6];
+    inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+        + m[4] * m[3] * m[13] + m[12] * m[1] * m[7] - m[12] * m[3] * m[5];
+    inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+        - m[4] * m[2] * m[13] - m[12] * m[1] * m[6] + m[12] * m[2] * m[5];
+    inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+        - m[5] * m[3] * m[10] - m[9] * m[2] * m[7] + m[9] * m[3] * m[6];
+    inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+        + m[4] * m[3] * m[10] + m[8] * m[2] * m[7] - m[8] * m[3] * m[6];
+    inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+        - m[4] * m[3] * m[9] - m[8] * m[1] * m[7] + m[8] * m[3] * m[5];
+    inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+        + m[4] * m[2] * m[9] + m[8] * m[1] * m[6] - m[8] * m[2] * m[5];
+
+    let det = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+    if det == 0.0 {
+        return IDENTITY;
+    }
+    inv.map(|v| v / det)
+}
+
+/// Transforms a point by `m`, including its translation.
+pub fn transform_point(m: &Mat4, p: Vec3) -> Vec3 {
+    [
+        m[0] * p[0] + m[4] * p[1] + m[8] * p[2] + m[12],
+        m[1] * p[0] + m[5] * p[1] + m[9] * p[2] + m[13],
+        m[2] * p[0] + m[6] * p[1] + m[10] * p[2] + m[14],
+    ]
+}
+
+/// Transforms a direction by `m`, ignoring its translation.
+pub fn transform_vector(m: &Mat4, v: Vec3) -> Vec3 {
+    [
+        m[0] * v[0] + m[4] * v[1] + m[8] * v[2],
+        m[1] * v[0] + m[5] * v[1] + m[9] * v[2],
+        m[2] * v[0] + m[6] * v[1] + m[10] * v[2],
+    ]
+}
+
+pub fn transpose(m: &Mat4) -> Mat4 {
+    std::array::from_fn(|i| m[(i % 4) * 4 + i / 4])
+}
+
+pub fn lerp<const N: usize>(a: [f32; N], b: [f32; N], t: f32) -> [f32; N] {
+    std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+/// Multiplies two `[x, y, z, w]` quaternions, so that the result rotates by `b` then `a`.
+pub fn quat_mul(a: Vec4, b: Vec4) -> Vec4 {
+    let [ax, ay, az, aw] = a;
+    let [bx, by, bz, bw] = b;
+    [
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+        aw * bw - ax * bx - ay * by - az * bz,
+    ]
+}
+
+/// Returns the inverse of a unit `[x, y, z, w]` quaternion.
+pub fn quat_conjugate(q: Vec4) -> Vec4 {
+    [-q[0], -q[1], -q[2], q[3]]
+}
+
+/// Spherically interpolates between two `[x, y, z, w]` quaternions along the shortest path.
+pub fn slerp(a: Vec4, b: Vec4, t: f32) -> Vec4 {
+    let mut dot: f32 = (0..4).map(|i| a[i] * b[i]).sum();
+    let b = if dot < 0.0 {
+        dot = -dot;
+        b.map(|v| -v)
+    } else {
+        b
+    };
+
+    if dot > 0.9995 {
+        return normalize(lerp(a, b, t));
+    }
+
+    let theta = dot.acos();
+    let sin = theta.sin();
+    let wa = ((1.0 - t) * theta).sin() / sin;
+    let wb = (t * theta).sin() / sin;
+    std::array::from_fn(|i| a[i] * wa + b[i] * wb)
+}
+
+pub fn normalize<const N: usize>(v: [f32; N]) -> [f32; N] {
+    let len = v.iter().map(|c| c * c).sum::<f32>().sqrt();
+    if len == 0.0 {
+        return v;
+    }
+    v.map(|c| c / len)
+}
@@ -0,0 +1,82 @@
+//! Combining several models into one.
+
+use crate::optimize::remap_brushes;
+use crate::{Brush, BrushId, Node, Texture, TextureId, B3D};
+
+impl B3D {
+    /// Combines `models` under a new root node named `name`, whose children are the models'
+    /// root nodes in order.
+    ///
+    /// Textures and brushes are appended and the ids referring to them remapped, with
+    /// identical ones shared between models. Each model's animation stays on its root node.
+    pub fn merge<I>(name: &str, models: I) -> Self
+    where
+        I: IntoIterator<Item = B3D>
+    {
+        let mut merged = B3D {
+            version: 1,
+            textures: vec![],
+            brushes: vec![],
+            node: Node {
+                name: name.into(),
+                scale: [1.0; 3],
+                rotation: [1.0, 0.0, 0.0, 0.0],
+                ..Default::default()
+            },
+        };
+
+        for mut model in models {
+            merged.version = merged.version.max(model.version);
+
+            let texture_ids: Vec<TextureId> = model
+                .textures
+                .into_iter()
+                .map(|texture| TextureId::new(add(&mut merged.textures, texture, same_texture)))
+                .collect();
+            let brush_ids: Vec<BrushId> = model
+                .brushes
+                .into_iter()
+                .map(|mut brush| {
+                    for id in &mut brush.texture_id {
+                        *id = id.get().and_then(|index| texture_ids.get(index).copied()).unwrap_or(TextureId::NONE);
+                    }
+                    BrushId::new(add(&mut merged.brushes, brush, same_brush))
+                })
+                .collect();
+
+            let remap = |id: BrushId| id.get().and_then(|index| brush_ids.get(index).copied()).unwrap_or(BrushId::NONE);
+            remap_brushes(&mut model.node, &remap);
+            merged.node.children.push(model.node);
+        }
+        merged
+    }
+}
+
+/// Returns the index of `item` in `items`, appending it unless an equal one is already there.
+fn add<T>(items: &mut Vec<T>, item: T, same: fn(&T, &T) -> bool) -> usize {
+    match items.iter().position(|other| same(other, &item)) {
+        Some(index) => index,
+        None => {
+            items.push(item);
+            items.len() - 1
+        }
+    }
+}
+
+fn same_texture(a: &Texture, b: &Texture) -> bool {
+    a.file == b.file
+        && a.flags == b.flags
+        && a.blend == b.blend
+        && a.position == b.position
+        && a.scale == b.scale
+        && a.rotation == b.rotation
+}
+
+fn same_brush(a: &Brush, b: &Brush) -> bool {
+    a.name == b.name
+        && a.color == b.color
+        && a.shininess == b.shininess
+        && a.blend == b.blend
+        && a.fx == b.fx
+        && a.texture_id == b.texture_id
+}
@@ -0,0 +1,326 @@
+//! Passes that shrink models and speed up drawing them without changing how they look.
+
+use std::collections::HashMap;
+
+use crate::{Bone, BrushId, Node, TextureId, Vertice, B3D};
+
+/// Vertices the post-transform cache is assumed to hold, see [`B3D::optimize_vertex_cache`].
+const CACHE_SIZE: usize = 32;
+
+impl B3D {
+    /// Merges the vertices of each mesh that share their normal, color, texture coordinates
+    /// and bone weights and lie within `tolerance` of each other, returning how many were
+    /// removed. Positions are snapped to a grid of `tolerance`, so a tolerance of zero only
+    /// merges exact duplicates. Triangles using a vertex the mesh doesn't have are removed.
+    pub fn weld_vertices(&mut self, tolerance: f32) -> usize {
+        let mut removed = 0;
+        for_each_mesh(&mut self.node, &mut |node| {
+            let weights = vertex_weights(node);
+            let mut cells: HashMap<WeldKey, u32> = HashMap::new();
+            let mut remap = Vec::with_capacity(weights.len());
+            for (vertex, weights) in node.mesh.vertices.vertices.iter().zip(weights) {
+                let key = WeldKey::new(vertex, weights, tolerance);
+                let next = cells.len() as u32;
+                remap.push(*cells.entry(key).or_insert(next));
+            }
+            removed += remap.len() - cells.len();
+            remap_vertices(node, &remap);
+        });
+        removed
+    }
+
+    /// Removes triangles that repeat a vertex or whose corners coincide, and `TRIS` chunks
+    /// left empty, returning how many triangles were removed.
+    pub fn remove_degenerate_triangles(&mut self) -> usize {
+        let mut removed = 0;
+        for_each_mesh(&mut self.node, &mut |node| {
+            let vertices = &node.mesh.vertices.vertices;
+            let position = |index: u32| vertices.get(index as usize).map(|v| v.position);
+            for tris in &mut node.mesh.triangles {
+                let before = tris.indices.len();
+                tris.indices.retain(|&[a, b, c]| {
+                    let (pa, pb, pc) = (position(a), position(b), position(c));
+                    a != b && b != c && a != c && pa != pb && pb != pc && pa != pc
+                });
+                removed += before - tris.indices.len();
+            }
+            node.mesh.triangles.retain(|tris| !tris.indices.is_empty());
+        });
+        removed
+    }
+
+    /// Removes brushes that no mesh or `TRIS` chunk uses, then textures that no remaining
+    /// brush uses, returning how many brushes and textures were removed. Ids that pointed
+    /// past the end of their list become [`BrushId::NONE`] or [`TextureId::NONE`].
+    pub fn prune_unused(&mut self) -> (usize, usize) {
+        let mut used = vec![false; self.brushes.len()];
+        for node in self.nodes() {
+            let ids = node.mesh.triangles.iter().map(|tris| tris.brush_id);
+            for id in ids.chain([node.mesh.brush_id]) {
+                if let Some(used) = id.get().and_then(|index| used.get_mut(index)) {
+                    *used = true;
+                }
+            }
+        }
+        let brush_remap = keep(&mut self.brushes, &used);
+        let remap_brush = |id: BrushId| BrushId::from(id.get().and_then(|index| brush_remap.get(index).copied().flatten()));
+        remap_brushes(&mut self.node, &remap_brush);
+
+        let mut used = vec![false; self.textures.len()];
+        for id in self.brushes.iter().flat_map(|brush| &brush.texture_id) {
+            if let Some(used) = id.get().and_then(|index| used.get_mut(index)) {
+                *used = true;
+            }
+        }
+        let texture_remap = keep(&mut self.textures, &used);
+        for id in self.brushes.iter_mut().flat_map(|brush| &mut brush.texture_id) {
+            *id = TextureId::from(id.get().and_then(|index| texture_remap.get(index).copied().flatten()));
+        }
+
+        (removed(&brush_remap), removed(&texture_remap))
+    }
+
+    /// Reorders the triangles of every `TRIS` chunk so consecutive triangles share vertices,
+    /// using Tom Forsyth's linear-speed vertex cache optimization, then renumbers vertices
+    /// in the order the triangles first use them. Triangles using a vertex the mesh doesn't
+    /// have are removed.
+    pub fn optimize_vertex_cache(&mut self) {
+        for_each_mesh(&mut self.node, &mut |node| {
+            let count = node.mesh.vertices.vertices.len();
+            for tris in &mut node.mesh.triangles {
+                tris.indices = reorder_triangles(&tris.indices, count);
+            }
+
+            // Vertices no triangle uses keep their order after the used ones.
+            let mut remap = vec![u32::MAX; count];
+            let mut next = 0;
+            let used = node.mesh.triangles.iter().flat_map(|tris| tris.indices.iter().flatten().copied());
+            for index in used.chain(0..count as u32) {
+                if let Some(slot) = remap.get_mut(index as usize).filter(|slot| **slot == u32::MAX) {
+                    *slot = next;
+                    next += 1;
+                }
+            }
+            remap_vertices(node, &remap);
+        });
+    }
+}
+
+/// Returns how many entries [`keep`] removed.
+fn removed(remap: &[Option<usize>]) -> usize {
+    remap.iter().filter(|index| index.is_none()).count()
+}
+
+/// Keeps the items whose `used` flag is set, returning the new index of every old one.
+fn keep<T>(items: &mut Vec<T>, used: &[bool]) -> Vec<Option<usize>> {
+    let mut next = 0;
+    let remap = used
+        .iter()
+        .map(|&used| {
+            used.then(|| {
+                next += 1;
+                next - 1
+            })
+        })
+        .collect();
+    let mut flags = used.iter();
+    items.retain(|_| flags.next().copied().unwrap_or(false));
+    remap
+}
+
+/// Replaces every brush id in `node` and its descendants with `remap(id)`.
+pub(crate) fn remap_brushes(node: &mut Node, remap: &impl Fn(BrushId) -> BrushId) {
+    node.mesh.brush_id = remap(node.mesh.brush_id);
+    for tris in &mut node.mesh.triangles {
+        tris.brush_id = remap(tris.brush_id);
+    }
+    for child in &mut node.children {
+        remap_brushes(child, remap);
+    }
+}
+
+/// Calls `f` on every node with vertices.
+fn for_each_mesh(node: &mut Node, f: &mut impl FnMut(&mut Node)) {
+    if !node.mesh.vertices.vertices.is_empty() {
+        f(node);
+    }
+    for child in &mut node.children {
+        for_each_mesh(child, f);
+    }
+}
+
+/// Collects the weights of the bones deforming the mesh of `node`: its own and those of its
+/// descendants, stopping at nested meshes which own the bones below them.
+fn skin_bones(node: &mut Node) -> Vec<&mut Vec<Bone>> {
+    fn walk<'a>(children: &'a mut [Node], bones: &mut Vec<&'a mut Vec<Bone>>) {
+        for child in children {
+            if child.mesh.vertices.vertices.is_empty() {
+                bones.push(&mut child.bones);
+                walk(&mut child.children, bones);
+            }
+        }
+    }
+    let mut bones = vec![&mut node.bones];
+    walk(&mut node.children, &mut bones);
+    bones
+}
+
+/// Lists, for every vertex of the mesh of `node`, the bones deforming it and their weights.
+fn vertex_weights(node: &mut Node) -> Vec<Vec<(usize, u32)>> {
+    let mut weights = vec![vec![]; node.mesh.vertices.vertices.len()];
+    for (bone, bones) in skin_bones(node).into_iter().enumerate() {
+        for weight in bones.iter() {
+            if let Some(weights) = weights.get_mut(weight.vertex_id as usize) {
+                weights.push((bone, weight.weight.to_bits()));
+            }
+        }
+    }
+    weights
+}
+
+/// Moves vertex `i` of the mesh of `node` to `remap[i]`, updating triangles and bone
+/// weights. Where several vertices move to the same place, the first one is kept. Triangles
+/// with a corner past the last vertex are dropped, as there's nowhere to move it.
+fn remap_vertices(node: &mut Node, remap: &[u32]) {
+    let len = remap.iter().map(|&index| index as usize + 1).max().unwrap_or(0);
+    let mut vertices: Vec<Option<Vertice>> = vec![None; len];
+    for (vertex, &index) in std::mem::take(&mut node.mesh.vertices.vertices).into_iter().zip(remap) {
+        vertices[index as usize].get_or_insert(vertex);
+    }
+    node.mesh.vertices.vertices = vertices.into_iter().flatten().collect();
+
+    let map = |index: u32| remap.get(index as usize).copied().unwrap_or(index);
+    for tris in &mut node.mesh.triangles {
+        tris.indices.retain(|face| face.iter().all(|&index| (index as usize) < remap.len()));
+        for face in &mut tris.indices {
+            *face = face.map(map);
+        }
+    }
+    for bones in skin_bones(node) {
+        let mut seen = vec![false; len];
+        bones.retain_mut(|bone| {
+            bone.vertex_id = map(bone.vertex_id);
+            match seen.get_mut(bone.vertex_id as usize) {
+                Some(seen) => !std::mem::replace(seen, true),
+                None => true,
+            }
+        });
+    }
+}
+
+/// What makes two vertices interchangeable, see [`B3D::weld_vertices`].
+#[derive(PartialEq, Eq, Hash)]
+struct WeldKey {
+    cell: [i64; 3],
+    attributes: Vec<u32>,
+    weights: Vec<(usize, u32)>,
+}
+
+impl WeldKey {
+    fn new(vertex: &Vertice, mut weights: Vec<(usize, u32)>, tolerance: f32) -> Self {
+        let cell = vertex.position.map(|p| {
+            if tolerance > 0.0 {
+                (p / tolerance).round() as i64
+            } else {
+                i64::from(bits(p))
+            }
+        });
+        let attributes = vertex
+            .normal
+            .iter()
+            .chain(&vertex.color)
+            .chain(vertex.tex_coords.iter().flatten())
+            .map(|&v| bits(v))
+            .collect();
+        weights.sort_unstable();
+        Self { cell, attributes, weights }
+    }
+}
+
+/// Returns the bits of `v`, treating both zeroes as one.
+fn bits(v: f32) -> u32 {
+    if v == 0.0 {
+        0
+    } else {
+        v.to_bits()
+    }
+}
+
+fn vertex_score(cache_position: Option<usize>, remaining: usize) -> f32 {
+    if remaining == 0 {
+        return -1.0;
+    }
+    let cache = match cache_position {
+        // The last triangle's vertices score the same so it can't be favored by winding.
+        Some(position) if position < 3 => 0.75,
+        Some(position) => (1.0 - (position - 3) as f32 / (CACHE_SIZE - 3) as f32).powf(1.5),
+        None => 0.0,
+    };
+    // Vertices with few triangles left are finished off first.
+    cache + 2.0 / (remaining as f32).sqrt()
+}
+
+fn reorder_triangles(indices: &[[u32; 3]], vertex_count: usize) -> Vec<[u32; 3]> {
+    if indices.iter().flatten().any(|&index| index as usize >= vertex_count) {
+        return indices.to_vec();
+    }
+
+    let mut adjacency: Vec<Vec<usize>> = vec![vec![]; vertex_count];
+    for (triangle, face) in indices.iter().enumerate() {
+        for &vertex in face {
+            adjacency[vertex as usize].push(triangle);
+        }
+    }
+    let mut cache_position: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut score: Vec<f32> = adjacency.iter().map(|triangles| vertex_score(None, triangles.len())).collect();
+    let triangle_score = |face: &[u32; 3], score: &[f32]| face.iter().map(|&v| score[v as usize]).sum::<f32>();
+
+    let mut emitted = vec![false; indices.len()];
+    let mut cache: Vec<u32> = vec![];
+    let mut output = Vec::with_capacity(indices.len());
+    let mut cursor = 0;
+    let mut best = (0..indices.len()).max_by(|&a, &b| {
+        triangle_score(&indices[a], &score).total_cmp(&triangle_score(&indices[b], &score))
+    });
+
+    while let Some(triangle) = best {
+        let face = indices[triangle];
+        emitted[triangle] = true;
+        output.push(face);
+        for &vertex in &face {
+            adjacency[vertex as usize].retain(|&other| other != triangle);
+        }
+
+        let mut touched: Vec<u32> = vec![];
+        for vertex in face.iter().chain(&cache) {
+            if !touched.contains(vertex) {
+                touched.push(*vertex);
+            }
+        }
+        for (position, &vertex) in touched.iter().enumerate() {
+            let vertex = vertex as usize;
+            cache_position[vertex] = (position < CACHE_SIZE).then_some(position);
+            score[vertex] = vertex_score(cache_position[vertex], adjacency[vertex].len());
+        }
+        cache = touched.iter().copied().take(CACHE_SIZE).collect();
+
+        best = None;
+        let mut best_score = f32::NEG_INFINITY;
+        for &vertex in &touched {
+            for &other in &adjacency[vertex as usize] {
+                let other_score = triangle_score(&indices[other], &score);
+                if other_score > best_score {
+                    best = Some(other);
+                    best_score = other_score;
+                }
+            }
+        }
+        if best.is_none() {
+            while cursor < indices.len() && emitted[cursor] {
+                cursor += 1;
+            }
+            best = (cursor < indices.len()).then_some(cursor);
+        }
+    }
+    output
+}
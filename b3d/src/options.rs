@@ -0,0 +1,210 @@
+use std::fmt;
+use std::io::{Read, Seek};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::utils::read_null_term_string;
+use crate::{Chunk, Error, Interner, Name};
+
+/// Called with `(bytes_done, bytes_total, current_chunk)` whenever a chunk header is read.
+pub type ProgressCallback = Arc<dyn Fn(u64, u64, &Chunk) + Send + Sync>;
+
+/// Options controlling how a b3d file is parsed.
+#[derive(Clone, Default)]
+pub struct ParseOptions {
+    pub limits: Limits,
+    pub on_progress: Option<ProgressCallback>,
+    /// Reject files whose chunks aren't in the canonical order, see
+    /// [`validate::chunk_order`](crate::validate::chunk_order).
+    pub strict_order: bool,
+    /// Interner shared between parses, so that models loaded with the same options share the
+    /// names they have in common. Each parse interns into a new interner when this is `None`.
+    pub interner: Option<Arc<Mutex<Interner>>>,
+}
+
+impl ParseOptions {
+    /// Sets the callback used to report parsing progress.
+    pub fn with_progress<F>(mut self, on_progress: F) -> Self
+    where
+        F: Fn(u64, u64, &Chunk) + Send + Sync + 'static
+    {
+        self.on_progress = Some(Arc::new(on_progress));
+        self
+    }
+}
+
+impl fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseOptions")
+            .field("limits", &self.limits)
+            .field("on_progress", &self.on_progress.is_some())
+            .field("strict_order", &self.strict_order)
+            .field("interner", &self.interner.is_some())
+            .finish()
+    }
+}
+
+/// Upper bounds enforced while parsing, so that untrusted files can't claim absurd sizes.
+///
+/// The defaults don't limit anything; use [`Limits::untrusted`] for user-uploaded models.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Maximum number of vertices summed over every mesh in the file.
+    pub max_vertices: usize,
+    /// Maximum number of triangles summed over every mesh in the file.
+    pub max_triangles: usize,
+    /// Maximum number of animation keys summed over every node in the file.
+    pub max_keys: usize,
+    /// Maximum number of textures in the `TEXS` chunk.
+    pub max_textures: usize,
+    /// Maximum size any chunk may claim in its header.
+    pub max_chunk_size: u32,
+    /// Maximum nesting depth of nodes.
+    pub max_depth: usize,
+}
+
+impl Limits {
+    /// Limits suited for parsing models from untrusted sources.
+    pub fn untrusted() -> Self {
+        Self {
+            max_vertices: 1 << 20,
+            max_triangles: 1 << 20,
+            max_keys: 1 << 20,
+            max_textures: 1 << 10,
+            max_chunk_size: 1 << 26,
+            max_depth: 128,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_vertices: usize::MAX,
+            max_triangles: usize::MAX,
+            max_keys: usize::MAX,
+            max_textures: usize::MAX,
+            max_chunk_size: u32::MAX,
+            max_depth: usize::MAX,
+        }
+    }
+}
+
+/// The state of a single parse, tracking the totals checked against [`Limits`].
+#[derive(Debug)]
+pub struct ParseContext<'a> {
+    options: &'a ParseOptions,
+    len: u64,
+    vertices: usize,
+    triangles: usize,
+    keys: usize,
+    textures: usize,
+    depth: usize,
+    interner: Arc<Mutex<Interner>>,
+}
+
+impl<'a> ParseContext<'a> {
+    /// Creates the context for parsing `len` bytes.
+    pub fn new(options: &'a ParseOptions, len: u64) -> Self {
+        Self {
+            options,
+            len,
+            vertices: 0,
+            triangles: 0,
+            keys: 0,
+            textures: 0,
+            depth: 0,
+            interner: options.interner.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Reads a chunk header, rejecting it if it claims more than [`Limits::max_chunk_size`].
+    pub fn read_chunk<T>(&mut self, data: &mut T) -> Result<Chunk, Error>
+    where
+        T: Read + Seek
+    {
+        let chunk = Chunk::read(data)?;
+        check("chunk size", chunk.size as usize, self.options.limits.max_chunk_size as usize)?;
+        self.progress(chunk.position, &chunk);
+        Ok(chunk)
+    }
+
+    /// Reports `done` bytes of progress through [`ParseOptions::on_progress`].
+    pub(crate) fn progress(&self, done: u64, chunk: &Chunk) {
+        if let Some(on_progress) = &self.options.on_progress {
+            on_progress(done.min(self.len), self.len, chunk);
+        }
+    }
+
+    pub(crate) fn add_vertex(&mut self) -> Result<(), Error> {
+        self.vertices += 1;
+        check("vertices", self.vertices, self.options.limits.max_vertices)
+    }
+
+    pub(crate) fn add_triangle(&mut self) -> Result<(), Error> {
+        self.triangles += 1;
+        check("triangles", self.triangles, self.options.limits.max_triangles)
+    }
+
+    pub(crate) fn add_key(&mut self) -> Result<(), Error> {
+        self.keys += 1;
+        check("keys", self.keys, self.options.limits.max_keys)
+    }
+
+    pub(crate) fn add_texture(&mut self) -> Result<(), Error> {
+        self.textures += 1;
+        check("textures", self.textures, self.options.limits.max_textures)
+    }
+
+    /// Starts a context for parsing another part of the same file, sharing the interner and
+    /// starting from the current node depth. Its totals are added back with [`Self::join`].
+    #[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+    pub(crate) fn fork(&self) -> Self {
+        Self {
+            vertices: 0,
+            triangles: 0,
+            keys: 0,
+            textures: 0,
+            interner: self.interner.clone(),
+            ..*self
+        }
+    }
+
+    /// Adds the totals of a forked context, checking them against the limits.
+    #[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+    pub(crate) fn join(&mut self, other: &Self) -> Result<(), Error> {
+        self.vertices += other.vertices;
+        self.triangles += other.triangles;
+        self.keys += other.keys;
+        self.textures += other.textures;
+        let limits = &self.options.limits;
+        check("vertices", self.vertices, limits.max_vertices)?;
+        check("triangles", self.triangles, limits.max_triangles)?;
+        check("keys", self.keys, limits.max_keys)?;
+        check("textures", self.textures, limits.max_textures)
+    }
+
+    /// Reads a null-terminated name, interning it.
+    pub(crate) fn read_name<T>(&mut self, data: &mut T) -> Result<Name, Error>
+    where
+        T: Read + Seek
+    {
+        let name = read_null_term_string(data)?;
+        Ok(self.interner.lock().unwrap_or_else(PoisonError::into_inner).intern(&name))
+    }
+
+    pub(crate) fn enter_node(&mut self) -> Result<(), Error> {
+        self.depth += 1;
+        check("node depth", self.depth, self.options.limits.max_depth)
+    }
+
+    pub(crate) fn leave_node(&mut self) {
+        self.depth -= 1;
+    }
+}
+
+fn check(what: &'static str, value: usize, limit: usize) -> Result<(), Error> {
+    if value > limit {
+        return Err(Error::LimitExceeded { what, limit });
+    }
+    Ok(())
+}
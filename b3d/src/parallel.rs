@@ -0,0 +1,56 @@
+use std::io::{Cursor, Seek, SeekFrom};
+
+use rayon::prelude::*;
+
+use crate::utils::eof;
+use crate::{Chunk, Error, Node, ParseContext, ParseOptions, Tag, B3D};
+
+impl B3D {
+    /// Parses like [`B3D::read_with_options`], but parses the subtrees under the root node's
+    /// children concurrently. Level exports often put every object directly under the root, so
+    /// this splits them evenly.
+    ///
+    /// Limits apply to the whole file as usual, though a subtree may finish parsing before
+    /// another pushes the totals over a limit. Progress is reported from several threads, so
+    /// the bytes done may go backwards.
+    pub fn read_parallel(data: &[u8], options: &ParseOptions) -> Result<Self, Error> {
+        Self::read_root(data, options, |cursor, chunk, ctx| read_root_node(data, cursor, chunk, ctx))
+    }
+}
+
+fn read_root_node(data: &[u8], cursor: &mut Cursor<&[u8]>, chunk: Chunk, ctx: &mut ParseContext) -> Result<Node, Error> {
+    ctx.enter_node()?;
+    let mut node = Node::read_fields(cursor, ctx)?;
+
+    // Children are skipped here and parsed once the rest of the node is known.
+    let mut pending = vec![];
+    while eof(cursor, chunk.next)? {
+        let child = ctx.read_chunk(cursor)?;
+        if child.tag == Tag::NODE {
+            cursor.seek(SeekFrom::Start(child.next))?;
+            pending.push((node.children.len(), child));
+            node.children.push(Node::default());
+        } else {
+            node.read_chunk(cursor, child, ctx)?;
+        }
+    }
+
+    let base = &*ctx;
+    let parsed: Vec<(usize, Node, ParseContext)> = pending
+        .into_par_iter()
+        .map(|(index, child)| {
+            let mut ctx = base.fork();
+            let mut cursor = Cursor::new(data);
+            cursor.seek(SeekFrom::Start(child.next - child.size as u64))?;
+            let node = Node::read(&mut cursor, child.next, &mut ctx)?;
+            Ok((index, node, ctx))
+        })
+        .collect::<Result<_, Error>>()?;
+
+    for (index, child, child_ctx) in parsed {
+        ctx.join(&child_ctx)?;
+        node.children[index] = child;
+    }
+    ctx.leave_node();
+    Ok(node)
+}
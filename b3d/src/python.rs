@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+
+use numpy::{PyArray1, PyArray2, PyArrayMethods};
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+
+use crate::{BrushId, Error, Mesh, Name, NodeId, TextureId, Tris, Verts, B3D};
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::IO(err) => PyIOError::new_err(err.to_string()),
+            err => PyValueError::new_err(err.to_string()),
+        }
+    }
+}
+
+/// Ids convert to an `int`, or `None` for ids stored as -1.
+macro_rules! id_into_py {
+    ($($name:ident),*) => {
+        $(
+            impl<'py> IntoPyObject<'py> for $name {
+                type Target = PyAny;
+                type Output = Bound<'py, PyAny>;
+                type Error = std::convert::Infallible;
+
+                fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+                    self.get().into_pyobject(py)
+                }
+            }
+        )*
+    };
+}
+
+id_into_py!(BrushId, TextureId, NodeId);
+
+impl<'py> IntoPyObject<'py> for &Name {
+    type Target = PyString;
+    type Output = Bound<'py, PyString>;
+    type Error = std::convert::Infallible;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        Ok(PyString::new(py, self))
+    }
+}
+
+/// Flattens `N` component rows into a `(len, N)` numpy array.
+fn to_array2<'py, T, const N: usize>(
+    py: Python<'py>,
+    rows: impl IntoIterator<Item = [T; N]>,
+) -> PyResult<Bound<'py, PyArray2<T>>>
+where
+    T: numpy::Element + Copy,
+{
+    let flat: Vec<T> = rows.into_iter().flatten().collect();
+    let len = flat.len() / N;
+    PyArray1::from_vec(py, flat).reshape([len, N])
+}
+
+#[pymethods]
+impl B3D {
+    /// Parses a b3d file from its raw bytes.
+    #[staticmethod]
+    #[pyo3(name = "read")]
+    fn py_read(data: &[u8]) -> PyResult<Self> {
+        Ok(Self::read(data)?)
+    }
+
+    /// Reads and parses the b3d file at `path`.
+    #[staticmethod]
+    fn open(path: PathBuf) -> PyResult<Self> {
+        let bytes = std::fs::read(path).map_err(Error::from)?;
+        Ok(Self::read(&bytes)?)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "B3D(version={}, textures={}, brushes={}, node={:?})",
+            self.version,
+            self.textures.len(),
+            self.brushes.len(),
+            self.node.name
+        )
+    }
+}
+
+#[pymethods]
+impl Verts {
+    /// Vertex positions as a `(n, 3)` float32 array.
+    #[pyo3(name = "positions")]
+    fn py_positions<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        to_array2(py, self.positions())
+    }
+
+    /// Vertex normals as a `(n, 3)` float32 array, or `None` if the file has none.
+    #[pyo3(name = "normals")]
+    fn py_normals<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyArray2<f32>>>> {
+        self.normals().map(|normals| to_array2(py, normals)).transpose()
+    }
+
+    /// Vertex colors as a `(n, 4)` float32 array, or `None` if the file has none.
+    #[pyo3(name = "colors")]
+    fn py_colors<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyArray2<f32>>>> {
+        self.colors().map(|colors| to_array2(py, colors)).transpose()
+    }
+
+    /// Texture coordinates of the first set as a `(n, 2)` float32 array.
+    fn tex_coords<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        self.py_uv_set(0, py)
+    }
+
+    /// Texture coordinates of set `n` as a `(n, 2)` float32 array, empty if there is no such set.
+    #[pyo3(name = "uv_set")]
+    fn py_uv_set<'py>(&self, n: usize, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        to_array2(py, self.uv_set(n).unwrap_or_default())
+    }
+
+    fn __len__(&self) -> usize {
+        self.vertices.len()
+    }
+}
+
+#[pymethods]
+impl Tris {
+    /// Triangle indices as a `(n, 3)` uint32 array.
+    fn index_array<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<u32>>> {
+        to_array2(py, self.indices.iter().copied())
+    }
+
+    fn __len__(&self) -> usize {
+        self.indices.len()
+    }
+}
+
+#[pymethods]
+impl Mesh {
+    /// Triangle indices of every `Tris` chunk as a single `(n, 3)` uint32 array.
+    fn index_array<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<u32>>> {
+        to_array2(
+            py,
+            self.triangles.iter().flat_map(|tris| tris.indices.iter().copied()),
+        )
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "b3d")]
+fn b3d_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<B3D>()?;
+    m.add_class::<crate::Texture>()?;
+    m.add_class::<crate::Brush>()?;
+    m.add_class::<crate::Node>()?;
+    m.add_class::<Mesh>()?;
+    m.add_class::<Verts>()?;
+    m.add_class::<crate::Vertice>()?;
+    m.add_class::<Tris>()?;
+    m.add_class::<crate::Bone>()?;
+    m.add_class::<crate::Key>()?;
+    m.add_class::<crate::Keys>()?;
+    m.add_class::<crate::Animation>()?;
+    m.add_class::<crate::Sequence>()?;
+    Ok(())
+}
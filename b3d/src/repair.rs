@@ -0,0 +1,216 @@
+//! Repairs for the problems [`validate`](crate::validate) finds that can be fixed without
+//! guessing what the exporter meant.
+
+use std::fmt;
+
+use crate::validate::ROTATION_TOLERANCE;
+use crate::utils::Vec4;
+use crate::{BrushId, Node, Tag, TextureId, B3D};
+
+/// Truncated chunks needing more zeros than this are dropped rather than padded, as their
+/// size is more likely corrupt than cut short.
+const MAX_PADDING: usize = 1 << 24;
+
+/// A change made by one of the repairs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    /// `file`, `brush <index>`, or the path of the node that was changed.
+    pub location: String,
+    pub kind: RepairKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairKind {
+    /// Zeros appended to a chunk cut off by the end of the file.
+    PaddedChunk { tag: Tag, bytes: usize },
+    /// A chunk cut off by the end of the file that was removed, either because its header
+    /// was incomplete or it claimed more than [`truncation`] is willing to pad.
+    DroppedChunk { tag: Option<Tag> },
+    /// Triangles referring to vertices the mesh doesn't have, removed from `TRIS` chunk
+    /// `tris`.
+    DroppedTriangles { tris: usize, count: usize },
+    /// A brush id that pointed past `BRUS`, replaced with [`BrushId::NONE`].
+    ClearedBrush(BrushId),
+    /// A texture id that pointed past `TEXS`, replaced with [`TextureId::NONE`].
+    ClearedTexture(TextureId),
+    /// Bone weights for vertices the deformed mesh doesn't have, removed.
+    DroppedBoneWeights { count: usize },
+    /// Rotations scaled back to unit length, with zero rotations reset to the identity.
+    NormalizedRotations { count: usize },
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.location)?;
+        match self.kind {
+            RepairKind::PaddedChunk { tag, bytes } => write!(f, "padded truncated {tag} chunk with {bytes} zero bytes"),
+            RepairKind::DroppedChunk { tag: Some(tag) } => write!(f, "dropped truncated {tag} chunk"),
+            RepairKind::DroppedChunk { tag: None } => write!(f, "dropped truncated chunk header"),
+            RepairKind::DroppedTriangles { tris, count } => {
+                write!(f, "dropped {count} triangles with missing vertices from TRIS {tris}")
+            }
+            RepairKind::ClearedBrush(id) => write!(f, "cleared missing brush {id}"),
+            RepairKind::ClearedTexture(id) => write!(f, "cleared missing texture {id}"),
+            RepairKind::DroppedBoneWeights { count } => write!(f, "dropped {count} weights for missing vertices"),
+            RepairKind::NormalizedRotations { count } => write!(f, "normalized {count} rotations"),
+        }
+    }
+}
+
+/// Completes a file cut off by the end of the data: the last chunk is padded with zeros to
+/// the size it claims, and the chunks around it are shrunk to end with it. Returns `None`
+/// if the file isn't truncated or isn't a b3d file at all.
+pub fn truncation(data: &[u8]) -> Option<(Vec<u8>, Repair)> {
+    let header = |at: usize| -> Option<(Tag, usize)> {
+        let bytes = data.get(at..at + 8)?;
+        let tag = Tag([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        Some((tag, u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize))
+    };
+    let (tag, size) = header(0)?;
+    if tag != Tag::BB3D || 8 + size <= data.len() {
+        return None;
+    }
+
+    // Offsets of the containers running past the end, from the outermost in.
+    let mut containers = vec![0];
+    let mut at = 12;
+    let mut fixed = data.to_vec();
+    let kind = loop {
+        let Some((tag, size)) = header(at) else {
+            fixed.truncate(at);
+            break RepairKind::DroppedChunk { tag: None };
+        };
+        let end = at + 8 + size;
+        if end <= data.len() {
+            at = end;
+            continue;
+        }
+        let children = match tag {
+            Tag::MESH => Some(at + 12),
+            // The name and the 40 bytes of position, scale and rotation.
+            Tag::NODE => data[at + 8..].iter().position(|&byte| byte == 0).map(|name| at + 8 + name + 1 + 40),
+            _ => None,
+        };
+        match children {
+            Some(children) if children <= data.len() => {
+                containers.push(at);
+                at = children;
+            }
+            _ if end - data.len() > MAX_PADDING => {
+                fixed.truncate(at);
+                break RepairKind::DroppedChunk { tag: Some(tag) };
+            }
+            _ => {
+                fixed.resize(end, 0);
+                break RepairKind::PaddedChunk { tag, bytes: end - data.len() };
+            }
+        }
+    };
+
+    for &container in &containers {
+        let size = (fixed.len() - container - 8) as u32;
+        fixed[container + 4..container + 8].copy_from_slice(&size.to_le_bytes());
+    }
+    Some((fixed, Repair { location: "file".to_owned(), kind }))
+}
+
+/// Removes or clears the references [`validate::references`](crate::validate::references)
+/// reports: triangles and bone weights for missing vertices are dropped, and missing brush
+/// and texture ids are replaced with none.
+pub fn references(b3d: &mut B3D) -> Vec<Repair> {
+    let mut repairs = vec![];
+    let textures = b3d.textures.len();
+    for (index, brush) in b3d.brushes.iter_mut().enumerate() {
+        for id in &mut brush.texture_id {
+            if id.get().is_some_and(|texture| texture >= textures) {
+                repairs.push(Repair { location: format!("brush {index}"), kind: RepairKind::ClearedTexture(*id) });
+                *id = TextureId::NONE;
+            }
+        }
+    }
+    let brushes = b3d.brushes.len();
+    repair_node(&mut b3d.node, "", None, brushes, &mut repairs);
+    repairs
+}
+
+fn repair_node(node: &mut Node, parent: &str, vertices: Option<usize>, brushes: usize, repairs: &mut Vec<Repair>) {
+    let path = child_path(parent, node);
+    let mut report = |kind| repairs.push(Repair { location: path.clone(), kind });
+    let mut clear_brush = |id: &mut BrushId| {
+        if id.get().is_some_and(|brush| brush >= brushes) {
+            report(RepairKind::ClearedBrush(*id));
+            *id = BrushId::NONE;
+        }
+    };
+
+    let mesh = &mut node.mesh;
+    clear_brush(&mut mesh.brush_id);
+    for tris in &mut mesh.triangles {
+        clear_brush(&mut tris.brush_id);
+    }
+    let count = mesh.vertices.vertices.len();
+    for (index, tris) in mesh.triangles.iter_mut().enumerate() {
+        let before = tris.indices.len();
+        tris.indices.retain(|face| face.iter().all(|&i| (i as usize) < count));
+        if tris.indices.len() < before {
+            report(RepairKind::DroppedTriangles { tris: index, count: before - tris.indices.len() });
+        }
+    }
+
+    let vertices = if count > 0 { Some(count) } else { vertices };
+    let before = node.bones.len();
+    node.bones.retain(|bone| vertices.is_some_and(|n| (bone.vertex_id as usize) < n));
+    if node.bones.len() < before {
+        report(RepairKind::DroppedBoneWeights { count: before - node.bones.len() });
+    }
+
+    for child in &mut node.children {
+        repair_node(child, &path, vertices, brushes, repairs);
+    }
+}
+
+/// Normalizes the node and key rotations [`validate::sanity`](crate::validate::sanity)
+/// reports as not unit length. Zero and non-finite rotations become the identity.
+pub fn rotations(b3d: &mut B3D) -> Vec<Repair> {
+    let mut repairs = vec![];
+    normalize_node(&mut b3d.node, "", &mut repairs);
+    repairs
+}
+
+fn normalize_node(node: &mut Node, parent: &str, repairs: &mut Vec<Repair>) {
+    let path = child_path(parent, node);
+    let mut count = usize::from(normalize(&mut node.rotation));
+    for keys in node.keys.iter_mut().filter(|keys| keys.flags & 4 != 0) {
+        for key in &mut keys.keys {
+            count += usize::from(normalize(&mut key.rotation));
+        }
+    }
+    if count > 0 {
+        repairs.push(Repair { location: path.clone(), kind: RepairKind::NormalizedRotations { count } });
+    }
+    for child in &mut node.children {
+        normalize_node(child, &path, repairs);
+    }
+}
+
+/// Returns whether `rotation`, stored as w, x, y, z, had to be changed.
+fn normalize(rotation: &mut Vec4) -> bool {
+    let length = rotation.iter().map(|c| c * c).sum::<f32>().sqrt();
+    if (length - 1.0).abs() <= ROTATION_TOLERANCE {
+        return false;
+    }
+    *rotation = if length.is_finite() && length > f32::EPSILON {
+        rotation.map(|c| c / length)
+    } else {
+        [1.0, 0.0, 0.0, 0.0]
+    };
+    true
+}
+
+fn child_path(parent: &str, node: &Node) -> String {
+    if parent.is_empty() {
+        node.name.to_string()
+    } else {
+        format!("{parent}/{}", node.name)
+    }
+}
@@ -0,0 +1,130 @@
+//! Moves animation between skeletons that share bone names, such as re-rigged characters
+//! reusing the stock animations.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::math;
+use crate::utils::{Vec3, Vec4};
+use crate::{coords, Key, Keys, Name, Node, B3D};
+
+/// Options for [`retarget`].
+#[derive(Debug, Clone, Default)]
+pub struct RetargetOptions {
+    /// Maps source node names to target node names for bones that were renamed.
+    pub aliases: HashMap<String, String>,
+    /// Applies keys relative to each bone's rest pose, so that the target moves away from its own
+    /// rest pose as the source moves away from its one. Without this the keys are copied as they
+    /// are, which only suits skeletons with identical rest poses.
+    pub compensate_rest_pose: bool,
+}
+
+/// The outcome of [`retarget`].
+#[derive(Debug, Clone, Default)]
+pub struct RetargetReport {
+    /// Names of the target nodes that received keys.
+    pub matched: Vec<Name>,
+    /// Names of the animated source nodes with no counterpart in the target.
+    pub unmatched: Vec<Name>,
+}
+
+/// Replaces the keys of every node in `target` with those of the same-named node in `source`,
+/// also copying the source's `ANIM` chunk and sequences onto the target's root node.
+///
+/// Names are compared ignoring ASCII case, as Blitz3D's `FindChild` does. Target nodes with no
+/// animated counterpart keep their keys.
+pub fn retarget(source: &B3D, target: &mut B3D, options: &RetargetOptions) -> RetargetReport {
+    let mut sources: HashMap<String, &Node> = HashMap::new();
+    for node in source.nodes().filter(|node| !node.keys.is_empty()) {
+        let name = options.aliases.get(node.name.as_str()).map_or(node.name.as_str(), String::as_str);
+        sources.entry(name.to_ascii_lowercase()).or_insert(node);
+    }
+
+    let mut report = RetargetReport::default();
+    let mut used = HashSet::new();
+    retarget_node(&mut target.node, &sources, options, &mut used, &mut report);
+
+    report.unmatched = source
+        .nodes()
+        .filter(|node| !node.keys.is_empty())
+        .filter(|node| {
+            let name = options.aliases.get(node.name.as_str()).map_or(node.name.as_str(), String::as_str);
+            !used.contains(&name.to_ascii_lowercase())
+        })
+        .map(|node| node.name.clone())
+        .collect();
+
+    if let Some(animation) = source.animation() {
+        target.node.animation = animation.clone();
+    }
+    let sequences: Vec<_> = source.nodes().flat_map(|node| &node.sequences).cloned().collect();
+    if !sequences.is_empty() {
+        target.node.sequences = sequences;
+    }
+
+    report
+}
+
+fn retarget_node(
+    node: &mut Node,
+    sources: &HashMap<String, &Node>,
+    options: &RetargetOptions,
+    used: &mut HashSet<String>,
+    report: &mut RetargetReport,
+) {
+    let name = node.name.to_ascii_lowercase();
+    if let Some(source) = sources.get(&name) {
+        node.keys = if options.compensate_rest_pose {
+            source.keys.iter().map(|keys| compensate(keys, source, node)).collect()
+        } else {
+            source.keys.clone()
+        };
+        used.insert(name);
+        report.matched.push(node.name.clone());
+    }
+
+    for child in &mut node.children {
+        retarget_node(child, sources, options, used, report);
+    }
+}
+
+/// Moves keys from `source`'s rest pose onto `target`'s.
+fn compensate(keys: &Keys, source: &Node, target: &Node) -> Keys {
+    let source_rotation = math::quat_conjugate(coords::rotation(source.rotation));
+    let target_rotation = coords::rotation(target.rotation);
+
+    let map = |key: &Key| {
+        let mut key = key.clone();
+        if keys.flags & 1 != 0 {
+            key.position = std::array::from_fn(|i| target.position[i] + key.position[i] - source.position[i]);
+        }
+        if keys.flags & 2 != 0 {
+            key.scale = std::array::from_fn(|i| target.scale[i] * ratio(key.scale, source.scale, i));
+        }
+        if keys.flags & 4 != 0 {
+            let delta = math::quat_mul(source_rotation, coords::rotation(key.rotation));
+            key.rotation = from_quaternion(math::quat_mul(target_rotation, delta));
+        }
+        key
+    };
+
+    Keys {
+        flags: keys.flags,
+        keys: keys.keys.iter().map(map).collect(),
+    }
+}
+
+/// Returns `a[i] / b[i]`, or 1 if `b[i]` is zero.
+fn ratio(a: Vec3, b: Vec3, i: usize) -> f32 {
+    if b[i] == 0.0 {
+        1.0
+    } else {
+        a[i] / b[i]
+    }
+}
+
+/// Converts a standard `[x, y, z, w]` quaternion back to a b3d rotation, undoing
+/// [`coords::rotation`].
+fn from_quaternion(q: Vec4) -> Vec4 {
+    let [x, y, z, w] = q;
+    [w, -x, -y, -z]
+}
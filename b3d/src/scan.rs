@@ -0,0 +1,127 @@
+//! Counting what a file holds from its chunk sizes, without decoding vertices or keys.
+
+use crate::{Chunk, ChunkTree, Error, ParseOptions, Tag, B3D};
+
+/// Totals of a file, see [`B3D::scan`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Summary {
+    pub version: u32,
+    /// Size of the file in bytes.
+    pub bytes: usize,
+    pub textures: usize,
+    pub brushes: usize,
+    pub nodes: usize,
+    /// Nodes with a `MESH` chunk.
+    pub meshes: usize,
+    /// Nodes with a `BONE` chunk.
+    pub bones: usize,
+    pub vertices: usize,
+    pub triangles: usize,
+    pub keys: usize,
+    /// Frame count of the root `ANIM` chunk, if there is one.
+    pub frames: Option<u32>,
+}
+
+impl B3D {
+    /// Counts textures, brushes, nodes, vertices, triangles and keys from chunk headers and
+    /// sizes alone, which is much faster than [`B3D::read`] for indexing large asset trees.
+    pub fn scan(data: &[u8]) -> Result<Summary, Error> {
+        Self::scan_with_options(data, &ParseOptions::default())
+    }
+
+    /// Scans the file, enforcing `options.limits` on chunk sizes and nesting depth.
+    pub fn scan_with_options(data: &[u8], options: &ParseOptions) -> Result<Summary, Error> {
+        let tree = ChunkTree::read_with_options(data, options)?;
+        let mut summary = Summary {
+            version: field(data, &tree.chunk, 0)?,
+            bytes: data.len(),
+            ..Default::default()
+        };
+        count(&tree, data, true, &mut summary)?;
+        Ok(summary)
+    }
+}
+
+/// `root_node` is set for the first `NODE` in the file, the only one whose `ANIM` counts.
+fn count(tree: &ChunkTree, data: &[u8], root_node: bool, summary: &mut Summary) -> Result<(), Error> {
+    let chunk = &tree.chunk;
+    let size = chunk.size as usize;
+    match chunk.tag {
+        Tag::TEXS => summary.textures += records(data, chunk, 0, 28)?,
+        Tag::BRUS => {
+            let n_texs = field(data, chunk, 0)? as usize;
+            let fixed = n_texs.checked_mul(4).and_then(|texs| texs.checked_add(28)).ok_or_else(|| invalid(chunk))?;
+            summary.brushes += records(data, chunk, 4, fixed)?;
+        }
+        Tag::NODE => summary.nodes += 1,
+        Tag::MESH => summary.meshes += 1,
+        Tag::VRTS => {
+            let flags = field(data, chunk, 0)?;
+            let sets = field(data, chunk, 4)? as usize;
+            let set_size = field(data, chunk, 8)? as usize;
+            // The same limits as `Verts::read`, which also keep the stride from overflowing.
+            if sets > 8 || set_size > 4 {
+                return Err(invalid(chunk));
+            }
+            let stride = 12
+                + usize::from(flags & 1 != 0) * 12
+                + usize::from(flags & 2 != 0) * 16
+                + sets * set_size * 4;
+            summary.vertices += size.saturating_sub(12) / stride;
+        }
+        Tag::TRIS => summary.triangles += size.saturating_sub(4) / 12,
+        Tag::BONE => summary.bones += 1,
+        Tag::KEYS => {
+            let flags = field(data, chunk, 0)?;
+            let stride = 4
+                + usize::from(flags & 1 != 0) * 12
+                + usize::from(flags & 2 != 0) * 12
+                + usize::from(flags & 4 != 0) * 16;
+            summary.keys += size.saturating_sub(4) / stride;
+        }
+        _ => {}
+    }
+
+    for child in &tree.children {
+        if root_node && child.chunk.tag == Tag::ANIM {
+            summary.frames = Some(field(data, &child.chunk, 4)?);
+        }
+        let child_is_root = chunk.tag == Tag::BB3D && child.chunk.tag == Tag::NODE;
+        count(child, data, child_is_root, summary)?;
+    }
+    Ok(())
+}
+
+/// Counts the records of a chunk that each hold a name followed by `fixed` bytes, starting
+/// `start` bytes into the chunk.
+fn records(data: &[u8], chunk: &Chunk, start: usize, fixed: usize) -> Result<usize, Error> {
+    let contents = contents(data, chunk)?;
+    let mut at = start;
+    let mut count = 0;
+    while at < contents.len() {
+        let name = contents[at..].iter().position(|&byte| byte == 0).ok_or_else(|| invalid(chunk))?;
+        at = at.saturating_add(name + 1).saturating_add(fixed);
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Reads the `u32` at `offset` into the chunk's contents.
+fn field(data: &[u8], chunk: &Chunk, offset: usize) -> Result<u32, Error> {
+    let bytes = contents(data, chunk)?.get(offset..offset + 4).ok_or_else(|| invalid(chunk))?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn contents<'a>(data: &'a [u8], chunk: &Chunk) -> Result<&'a [u8], Error> {
+    data.get(chunk.position as usize + 8..chunk.next as usize).ok_or_else(|| invalid(chunk))
+}
+
+fn invalid(chunk: &Chunk) -> Error {
+    Error::InvalidChunk(Chunk {
+        tag: chunk.tag,
+        size: chunk.size,
+        position: chunk.position,
+        next: chunk.next,
+    })
+}
@@ -0,0 +1,92 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use crate::utils::{eof, read_null_term_string};
+use crate::{Chunk, Error, ParseContext, ParseOptions, Tag};
+
+/// The raw nesting of chunks in a file, without decoding their contents.
+///
+/// Only `BB3D`, `NODE` and `MESH` chunks are descended into; every other chunk, including
+/// unknown ones, is kept as a leaf.
+#[derive(Debug)]
+pub struct ChunkTree {
+    pub chunk: Chunk,
+    /// Where the nested chunks start, after the fields of this chunk. Equal to `chunk.next`
+    /// for leaves.
+    pub children_start: u64,
+    pub children: Vec<ChunkTree>,
+}
+
+impl ChunkTree {
+    pub fn read(data: &[u8]) -> Result<Self, Error> {
+        Self::read_with_options(data, &ParseOptions::default())
+    }
+
+    /// Reads the chunk structure, enforcing `options.limits` on chunk sizes and nesting depth.
+    pub fn read_with_options(data: &[u8], options: &ParseOptions) -> Result<Self, Error> {
+        let mut cursor = Cursor::new(data);
+        let mut ctx = ParseContext::new(options, data.len() as u64);
+
+        let chunk = ctx.read_chunk(&mut cursor)?;
+        if chunk.tag != Tag::BB3D {
+            return Err(Error::InvalidChunk(chunk));
+        }
+        Self::read_chunk(&mut cursor, chunk, data.len() as u64, &mut ctx)
+    }
+
+    fn read_chunk<T>(data: &mut T, chunk: Chunk, end: u64, ctx: &mut ParseContext) -> Result<Self, Error>
+    where
+        T: Read + Seek
+    {
+        if chunk.next > end {
+            return Err(Error::InvalidChunk(chunk));
+        }
+
+        let mut children = vec![];
+        let children_start = match chunk.tag {
+            Tag::BB3D => {
+                // The version.
+                data.seek(SeekFrom::Current(4))?;
+                data.stream_position()?
+            }
+            Tag::MESH => {
+                // The brush id.
+                data.seek(SeekFrom::Current(4))?;
+                data.stream_position()?
+            }
+            Tag::NODE => {
+                ctx.enter_node()?;
+                read_null_term_string(data)?;
+                // The position, scale and rotation.
+                data.seek(SeekFrom::Current(40))?;
+                data.stream_position()?
+            }
+            _ => {
+                data.seek(SeekFrom::Start(chunk.next))?;
+                return Ok(Self {
+                    children_start: chunk.next,
+                    chunk,
+                    children,
+                });
+            }
+        };
+
+        while eof(data, chunk.next)? {
+            let child = ctx.read_chunk(data)?;
+            children.push(Self::read_chunk(data, child, chunk.next, ctx)?);
+        }
+        if chunk.tag == Tag::NODE {
+            ctx.leave_node();
+        }
+
+        Ok(Self {
+            chunk,
+            children_start,
+            children,
+        })
+    }
+
+    /// Returns the direct children with the given tag.
+    pub fn children_with(&self, tag: Tag) -> impl Iterator<Item = &ChunkTree> {
+        self.children.iter().filter(move |child| child.chunk.tag == tag)
+    }
+}
@@ -0,0 +1,63 @@
+//! Reports on which parts of a model use which brushes.
+
+use crate::{BrushId, NodeId, TextureId, B3D};
+
+/// Where a brush is used, see [`B3D::brush_usage`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BrushUsage {
+    pub brush: BrushId,
+    /// Every `TRIS` chunk drawn with the brush.
+    pub users: Vec<TrisUsage>,
+    /// Triangles summed over `users`.
+    pub triangles: usize,
+    /// The textures the brush references, excluding ids that point nowhere.
+    pub textures: Vec<TextureId>,
+}
+
+/// A `TRIS` chunk drawn with a brush.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrisUsage {
+    /// The node whose mesh holds the chunk.
+    pub node: NodeId,
+    /// Index of the chunk in the mesh's `triangles`.
+    pub tris: usize,
+    pub triangles: usize,
+}
+
+impl B3D {
+    /// Reports, for every brush in order, the `TRIS` chunks drawn with it and the textures it
+    /// pulls in. Unused brushes have no users.
+    ///
+    /// A `TRIS` chunk without a brush uses its mesh's brush, as in Blitz3D.
+    pub fn brush_usage(&self) -> Vec<BrushUsage> {
+        let mut usage: Vec<BrushUsage> = self
+            .brushes
+            .iter()
+            .enumerate()
+            .map(|(index, brush)| BrushUsage {
+                brush: BrushId::new(index),
+                textures: brush.texture_id.iter().copied().filter(|&id| self.texture(id).is_some()).collect(),
+                ..Default::default()
+            })
+            .collect();
+
+        for (index, node) in self.nodes().enumerate() {
+            for (tris_index, tris) in node.mesh.triangles.iter().enumerate() {
+                let brush = tris.brush_id.or(node.mesh.brush_id);
+                let Some(usage) = brush.get().and_then(|brush| usage.get_mut(brush)) else {
+                    continue;
+                };
+                usage.users.push(TrisUsage {
+                    node: NodeId::new(index),
+                    tris: tris_index,
+                    triangles: tris.indices.len(),
+                });
+                usage.triangles += tris.indices.len();
+            }
+        }
+
+        usage
+    }
+}
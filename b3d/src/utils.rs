@@ -13,19 +13,29 @@ pub type Vec3 = [f32; 3];
 /// The `Vec4` is used by Quats and Colors.
 pub type Vec4 = [f32; 4];
 
-pub fn read_null_term_string<T>(data: &mut T) -> String
+/// Emits a `tracing` event when the `tracing` feature is enabled.
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)*);
+    };
+}
+
+pub(crate) use trace_event;
+
+pub fn read_null_term_string<T>(data: &mut T) -> Result<String, Error>
 where
     T: Read + Seek
 {
     let mut string = vec![];
     loop {
-        let byte = data.read_u8().unwrap();
+        let byte = data.read_u8()?;
         if byte == 0 {
             break;
         }
         string.push(byte);
     }
-    String::from_utf8(string).unwrap()
+    Ok(String::from_utf8(string)?)
 }
 
 pub fn eof<T>(data: &mut T, next: u64) -> Result<bool, Error>
@@ -35,9 +45,58 @@ where
     Ok(data.stream_position()? < next)
 }
 
+/// The four byte identifier at the start of every chunk, such as `NODE`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tag(pub [u8; 4]);
+
+impl Tag {
+    pub const BB3D: Self = Self(*b"BB3D");
+    pub const TEXS: Self = Self(*b"TEXS");
+    pub const BRUS: Self = Self(*b"BRUS");
+    pub const NODE: Self = Self(*b"NODE");
+    pub const MESH: Self = Self(*b"MESH");
+    pub const VRTS: Self = Self(*b"VRTS");
+    pub const TRIS: Self = Self(*b"TRIS");
+    pub const BONE: Self = Self(*b"BONE");
+    pub const KEYS: Self = Self(*b"KEYS");
+    pub const ANIM: Self = Self(*b"ANIM");
+    pub const SEQS: Self = Self(*b"SEQS");
+    pub const PIVO: Self = Self(*b"PIVO");
+
+    pub fn as_bytes(&self) -> &[u8; 4] {
+        &self.0
+    }
+}
+
+impl From<[u8; 4]> for Tag {
+    fn from(bytes: [u8; 4]) -> Self {
+        Self(bytes)
+    }
+}
+
+/// Prints the tag as text, escaping bytes that aren't printable ASCII.
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &byte in &self.0 {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                write!(f, "{}", byte as char)?;
+            } else {
+                write!(f, "\\x{byte:02x}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Tag(\"{self}\")")
+    }
+}
+
 #[derive(Debug)]
 pub struct Chunk {
-    pub tag: String,
+    pub tag: Tag,
     pub size: u32,
     
     pub position: u64,
@@ -50,13 +109,14 @@ impl Chunk {
         T: Read + Seek
     {
         let position = data.stream_position()?;
-        let mut tag_buf = vec![0; 4];
-        data.read_exact(&mut tag_buf)?;
-        let tag = String::from_utf8(tag_buf)?;
+        let mut tag = Tag([0; 4]);
+        data.read_exact(&mut tag.0)?;
 
         let size = data.read_u32::<LittleEndian>()?;
         let next = position + (size as u64) + 8;
 
+        trace_event!(tag = %tag, offset = position, size, "read chunk");
+
         Ok(Self {
             tag,
             size,
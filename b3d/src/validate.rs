@@ -0,0 +1,325 @@
+//! Conformance checks for exporters, reporting where a file deviates from the b3d spec or
+//! holds values that won't render.
+
+use std::fmt;
+
+use crate::utils::{Vec3, Vec4};
+use crate::{BrushId, ChunkTree, Node, NodeKind, Tag, TextureId, B3D};
+
+/// A chunk that isn't where the spec puts it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderViolation {
+    pub tag: Tag,
+    /// Offset of the chunk header in the file.
+    pub position: u64,
+    pub kind: OrderViolationKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderViolationKind {
+    /// The chunk appears after a sibling that should follow it.
+    After(Tag),
+    /// The chunk appears more than once in its parent.
+    Repeated,
+    /// The chunk lacks a child it requires.
+    Missing(Tag),
+}
+
+impl fmt::Display for OrderViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {} ", self.tag, self.position)?;
+        match self.kind {
+            OrderViolationKind::After(other) => write!(f, "appears after {other}"),
+            OrderViolationKind::Repeated => write!(f, "appears more than once"),
+            OrderViolationKind::Missing(other) => write!(f, "has no {other} chunk"),
+        }
+    }
+}
+
+/// Checks that chunks follow the canonical order: `TEXS` before `BRUS` before `NODE` in the
+/// file, `VRTS` before `TRIS` in meshes, and `ANIM` before `KEYS` in nodes.
+pub fn chunk_order(tree: &ChunkTree) -> Vec<OrderViolation> {
+    let mut violations = vec![];
+    check(tree, &mut violations);
+    violations
+}
+
+fn check(tree: &ChunkTree, violations: &mut Vec<OrderViolation>) {
+    match tree.chunk.tag {
+        Tag::BB3D => {
+            check_sequence(tree, &[Tag::TEXS, Tag::BRUS, Tag::NODE], &[Tag::TEXS, Tag::BRUS, Tag::NODE], violations);
+        }
+        Tag::MESH => {
+            if tree.children_with(Tag::VRTS).next().is_none() {
+                violations.push(violation(tree, OrderViolationKind::Missing(Tag::VRTS)));
+            }
+            check_sequence(tree, &[Tag::VRTS, Tag::TRIS], &[Tag::VRTS], violations);
+        }
+        Tag::NODE => {
+            check_sequence(tree, &[Tag::ANIM, Tag::KEYS], &[Tag::ANIM], violations);
+        }
+        _ => {}
+    }
+
+    for child in &tree.children {
+        check(child, violations);
+    }
+}
+
+/// Flags children of `tree` that appear after a sibling later in `order`, and repeats of the
+/// tags in `unique`.
+fn check_sequence(tree: &ChunkTree, order: &[Tag], unique: &[Tag], violations: &mut Vec<OrderViolation>) {
+    let mut seen: Vec<Tag> = vec![];
+
+    for child in &tree.children {
+        let tag = child.chunk.tag;
+        if unique.contains(&tag) && seen.contains(&tag) {
+            violations.push(violation(child, OrderViolationKind::Repeated));
+        }
+        if let Some(rank) = rank_of(order, tag) {
+            let later = seen.iter().find(|&&other| rank_of(order, other).is_some_and(|r| r > rank));
+            if let Some(&later) = later {
+                violations.push(violation(child, OrderViolationKind::After(later)));
+            }
+        }
+        if !seen.contains(&tag) {
+            seen.push(tag);
+        }
+    }
+}
+
+fn rank_of(order: &[Tag], tag: Tag) -> Option<usize> {
+    order.iter().position(|&t| t == tag)
+}
+
+fn violation(tree: &ChunkTree, kind: OrderViolationKind) -> OrderViolation {
+    OrderViolation {
+        tag: tree.chunk.tag,
+        position: tree.chunk.position,
+        kind,
+    }
+}
+
+/// A suspicious value in a parsed model, the usual cause of meshes that render exploded or not
+/// at all.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Names of the nodes from the root down to the offending one, joined by `/`.
+    pub path: String,
+    pub kind: DiagnosticKind,
+}
+
+/// What [`sanity`] found. `frame` is the frame of the offending key, or `None` for the node's
+/// rest pose.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiagnosticKind {
+    /// Vertices with NaN or infinite positions, counting from the first.
+    NonFiniteVertices { first: usize, count: usize },
+    /// A NaN or infinite position, scale or rotation.
+    NonFiniteTransform { frame: Option<u32> },
+    /// A rotation that isn't a unit quaternion, including the zero quaternion.
+    NonUnitRotation { frame: Option<u32>, length: f32 },
+    /// A scale with a zero component, which collapses the node and its children.
+    ZeroScale { frame: Option<u32> },
+    /// A bone scaled differently along each axis, which shears its children.
+    NonUniformBoneScale { frame: Option<u32>, scale: Vec3 },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        let frame = |frame: Option<u32>| match frame {
+            Some(frame) => format!(" at frame {frame}"),
+            None => String::new(),
+        };
+        match self.kind {
+            DiagnosticKind::NonFiniteVertices { first, count } => {
+                write!(f, "{count} vertices with non-finite positions, the first at index {first}")
+            }
+            DiagnosticKind::NonFiniteTransform { frame: at } => write!(f, "non-finite transform{}", frame(at)),
+            DiagnosticKind::NonUnitRotation { frame: at, length } => {
+                write!(f, "rotation of length {length}{}", frame(at))
+            }
+            DiagnosticKind::ZeroScale { frame: at } => write!(f, "zero scale{}", frame(at)),
+            DiagnosticKind::NonUniformBoneScale { frame: at, scale } => {
+                write!(f, "non-uniform bone scale {scale:?}{}", frame(at))
+            }
+        }
+    }
+}
+
+/// How far a rotation's length may stray from 1 before it is reported.
+pub(crate) const ROTATION_TOLERANCE: f32 = 1e-3;
+
+/// Checks vertex positions, and node and key transforms, for values that break rendering.
+pub fn sanity(b3d: &B3D) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    check_node(&b3d.node, "", &mut diagnostics);
+    diagnostics
+}
+
+fn check_node(node: &Node, parent: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let path = child_path(parent, node);
+    let mut report = |kind| diagnostics.push(Diagnostic { path: path.clone(), kind });
+
+    let mut non_finite = node.mesh.vertices.vertices.iter().enumerate().filter(|(_, v)| !finite(&v.position));
+    if let Some((first, _)) = non_finite.next() {
+        report(DiagnosticKind::NonFiniteVertices { first, count: non_finite.count() + 1 });
+    }
+
+    let bone = node.kind() == NodeKind::Bone;
+    check_transform(Some(node.position), Some(node.scale), Some(node.rotation), None, bone, &mut report);
+    for keys in &node.keys {
+        for key in &keys.keys {
+            check_transform(
+                (keys.flags & 1 != 0).then_some(key.position),
+                (keys.flags & 2 != 0).then_some(key.scale),
+                (keys.flags & 4 != 0).then_some(key.rotation),
+                Some(key.frame),
+                bone,
+                &mut report,
+            );
+        }
+    }
+
+    for child in &node.children {
+        check_node(child, &path, diagnostics);
+    }
+}
+
+fn check_transform(
+    position: Option<Vec3>,
+    scale: Option<Vec3>,
+    rotation: Option<Vec4>,
+    frame: Option<u32>,
+    bone: bool,
+    report: &mut impl FnMut(DiagnosticKind),
+) {
+    let finite_parts = position.is_none_or(|p| finite(&p))
+        && scale.is_none_or(|s| finite(&s))
+        && rotation.is_none_or(|r| finite(&r));
+    if !finite_parts {
+        report(DiagnosticKind::NonFiniteTransform { frame });
+        return;
+    }
+
+    if let Some(rotation) = rotation {
+        let length = rotation.iter().map(|c| c * c).sum::<f32>().sqrt();
+        if (length - 1.0).abs() > ROTATION_TOLERANCE {
+            report(DiagnosticKind::NonUnitRotation { frame, length });
+        }
+    }
+    if let Some(scale) = scale {
+        if scale.contains(&0.0) {
+            report(DiagnosticKind::ZeroScale { frame });
+        } else if bone && (scale[0] != scale[1] || scale[1] != scale[2]) {
+            report(DiagnosticKind::NonUniformBoneScale { frame, scale });
+        }
+    }
+}
+
+fn finite(values: &[f32]) -> bool {
+    values.iter().all(|v| v.is_finite())
+}
+
+fn child_path(parent: &str, node: &Node) -> String {
+    if parent.is_empty() {
+        node.name.to_string()
+    } else {
+        format!("{parent}/{}", node.name)
+    }
+}
+
+/// An id or index pointing past the end of the list it refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenReference {
+    /// `brush <index>` for brushes, otherwise the node path as in [`Diagnostic::path`].
+    pub location: String,
+    pub kind: BrokenReferenceKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrokenReferenceKind {
+    /// A brush layer naming a texture that `TEXS` doesn't have.
+    Texture(TextureId),
+    /// A mesh or `TRIS` chunk naming a brush that `BRUS` doesn't have.
+    Brush(BrushId),
+    /// Triangle indices past the end of the mesh's vertices, counting from the first, in the
+    /// `TRIS` chunk at index `tris`.
+    Vertex { tris: usize, first: u32, count: usize },
+    /// Bone weights for vertices that the mesh the bone deforms doesn't have, counting from the
+    /// first.
+    BoneVertex { first: u32, count: usize },
+}
+
+impl fmt::Display for BrokenReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.location)?;
+        match self.kind {
+            BrokenReferenceKind::Texture(id) => write!(f, "texture {id} does not exist"),
+            BrokenReferenceKind::Brush(id) => write!(f, "brush {id} does not exist"),
+            BrokenReferenceKind::Vertex { tris, first, count } => {
+                write!(f, "{count} indices past the last vertex in TRIS {tris}, the first is {first}")
+            }
+            BrokenReferenceKind::BoneVertex { first, count } => {
+                write!(f, "{count} weights for missing vertices, the first for vertex {first}")
+            }
+        }
+    }
+}
+
+/// Checks that brushes, meshes, triangles and bones only refer to textures, brushes and
+/// vertices the file has. The parser accepts such files, but they crash Blitz3D.
+pub fn references(b3d: &B3D) -> Vec<BrokenReference> {
+    let mut broken = vec![];
+    for (index, brush) in b3d.brushes.iter().enumerate() {
+        for &id in &brush.texture_id {
+            if id.get().is_some_and(|texture| texture >= b3d.textures.len()) {
+                broken.push(BrokenReference {
+                    location: format!("brush {index}"),
+                    kind: BrokenReferenceKind::Texture(id),
+                });
+            }
+        }
+    }
+    check_references(b3d, &b3d.node, "", None, &mut broken);
+    broken
+}
+
+/// `vertices` is the vertex count of the nearest mesh above `node`, which its bones deform.
+fn check_references(
+    b3d: &B3D,
+    node: &Node,
+    parent: &str,
+    vertices: Option<usize>,
+    broken: &mut Vec<BrokenReference>,
+) {
+    let path = child_path(parent, node);
+    let mut report = |kind| broken.push(BrokenReference { location: path.clone(), kind });
+    let brush_exists = |id: BrushId| id.get().is_none_or(|brush| brush < b3d.brushes.len());
+
+    let mesh = &node.mesh;
+    if !brush_exists(mesh.brush_id) {
+        report(BrokenReferenceKind::Brush(mesh.brush_id));
+    }
+    let count = mesh.vertices.vertices.len();
+    for (index, tris) in mesh.triangles.iter().enumerate() {
+        if !brush_exists(tris.brush_id) {
+            report(BrokenReferenceKind::Brush(tris.brush_id));
+        }
+        let mut outside = tris.indices.iter().flatten().filter(|&&i| i as usize >= count);
+        if let Some(&first) = outside.next() {
+            report(BrokenReferenceKind::Vertex { tris: index, first, count: outside.count() + 1 });
+        }
+    }
+
+    let vertices = if count > 0 { Some(count) } else { vertices };
+    let mut outside = node.bones.iter().filter(|bone| vertices.is_none_or(|n| bone.vertex_id as usize >= n));
+    if let Some(first) = outside.next() {
+        report(BrokenReferenceKind::BoneVertex { first: first.vertex_id, count: outside.count() + 1 });
+    }
+
+    for child in &node.children {
+        check_references(b3d, child, &path, vertices, broken);
+    }
+}
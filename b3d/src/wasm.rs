@@ -0,0 +1,127 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Node, NodeId, B3D};
+
+#[derive(Serialize)]
+struct Summary<'a> {
+    version: u32,
+    textures: Vec<&'a str>,
+    brushes: Vec<&'a str>,
+    nodes: Vec<NodeSummary<'a>>,
+}
+
+#[derive(Serialize)]
+struct NodeSummary<'a> {
+    name: &'a str,
+    children: usize,
+    vertices: usize,
+    triangles: usize,
+    bones: usize,
+    keys: usize,
+}
+
+/// A parsed b3d file, exported to JavaScript as `B3D`.
+#[wasm_bindgen(js_name = B3D)]
+pub struct WasmB3D {
+    b3d: B3D,
+}
+
+#[wasm_bindgen(js_class = B3D)]
+impl WasmB3D {
+    /// Parses a b3d file from the bytes of a `Uint8Array`.
+    pub fn parse(bytes: &[u8]) -> Result<WasmB3D, JsError> {
+        Ok(Self {
+            b3d: B3D::read(bytes)?,
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn version(&self) -> u32 {
+        self.b3d.version
+    }
+
+    /// Returns a JSON summary of the textures, brushes and node tree.
+    pub fn summary(&self) -> Result<String, JsError> {
+        let summary = Summary {
+            version: self.b3d.version,
+            textures: self.b3d.textures.iter().map(|t| t.file.as_str()).collect(),
+            brushes: self.b3d.brushes.iter().map(|b| b.name.as_str()).collect(),
+            nodes: self
+                .b3d
+                .nodes()
+                .map(|node| NodeSummary {
+                    name: &node.name,
+                    children: node.children.len(),
+                    vertices: node.mesh.vertices.vertices.len(),
+                    triangles: node.mesh.triangles.iter().map(|t| t.indices.len()).sum(),
+                    bones: node.bones.len(),
+                    keys: node.keys.iter().map(|keys| keys.keys.len()).sum(),
+                })
+                .collect(),
+        };
+        Ok(serde_json::to_string(&summary)?)
+    }
+
+    /// Returns the whole parsed model as JSON.
+    pub fn json(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&self.b3d)?)
+    }
+
+    /// The number of nodes, in the depth-first order used by the geometry accessors.
+    #[wasm_bindgen(js_name = nodeCount)]
+    pub fn node_count(&self) -> usize {
+        self.b3d.nodes().count()
+    }
+
+    #[wasm_bindgen(js_name = nodeName)]
+    pub fn node_name(&self, node: usize) -> Option<String> {
+        self.with_node(node, |node| node.name.to_string())
+    }
+
+    /// Vertex positions of a node as a flat `Float32Array` of xyz triples.
+    pub fn positions(&self, node: usize) -> Option<Vec<f32>> {
+        self.with_node(node, |node| {
+            node.mesh.vertices.vertices.iter().flat_map(|v| v.position).collect()
+        })
+    }
+
+    /// Vertex normals of a node as a flat `Float32Array` of xyz triples, or `undefined` if the
+    /// node has none.
+    pub fn normals(&self, node: usize) -> Option<Vec<f32>> {
+        self.with_node(node, |node| node.mesh.vertices.normals().map(|normals| normals.concat()))
+            .flatten()
+    }
+
+    /// Vertex colors of a node as a flat `Float32Array` of rgba quadruples, or `undefined` if
+    /// the node has none.
+    pub fn colors(&self, node: usize) -> Option<Vec<f32>> {
+        self.with_node(node, |node| node.mesh.vertices.colors().map(|colors| colors.concat()))
+            .flatten()
+    }
+
+    /// Texture coordinates of a node as a flat `Float32Array` of uv pairs.
+    #[wasm_bindgen(js_name = texCoords)]
+    pub fn tex_coords(&self, node: usize) -> Option<Vec<f32>> {
+        self.with_node(node, |node| {
+            node.mesh.vertices.uv_set(0).unwrap_or_default().concat()
+        })
+    }
+
+    /// Triangle indices of all of a node's `TRIS` chunks as a flat `Uint32Array`.
+    pub fn indices(&self, node: usize) -> Option<Vec<u32>> {
+        self.with_node(node, |node| {
+            node.mesh
+                .triangles
+                .iter()
+                .flat_map(|tris| tris.indices.iter().flatten().copied())
+                .collect()
+        })
+    }
+}
+
+impl WasmB3D {
+    fn with_node<R>(&self, index: usize, f: impl FnOnce(&Node) -> R) -> Option<R> {
+        self.b3d.node_at(NodeId::new(index)).map(f)
+    }
+}
@@ -0,0 +1,318 @@
+//! Serialization back into the b3d format.
+
+use std::io::Write;
+use byteorder::{WriteBytesExt, LittleEndian};
+
+use crate::{Animation, Brush, Error, Keys, Mesh, Node, Sequence, Tag, Texture, TextureId, Verts, B3D};
+
+/// Builds a file in memory, patching chunk sizes once their contents are known.
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    /// Writes a chunk header with a placeholder size, returning its offset for [`Self::end`].
+    fn begin(&mut self, tag: Tag) -> usize {
+        let start = self.bytes.len();
+        self.bytes.extend_from_slice(tag.as_bytes());
+        self.u32(0);
+        start
+    }
+
+    fn end(&mut self, start: usize) {
+        let size = (self.bytes.len() - start - 8) as u32;
+        self.bytes[start + 4..start + 8].copy_from_slice(&size.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        // Writing into a `Vec` can't fail.
+        let _ = self.bytes.write_u32::<LittleEndian>(value);
+    }
+
+    fn f32s(&mut self, values: &[f32]) {
+        for &value in values {
+            let _ = self.bytes.write_f32::<LittleEndian>(value);
+        }
+    }
+
+    fn string(&mut self, string: &str) {
+        self.bytes.extend_from_slice(string.as_bytes());
+        self.bytes.push(0);
+    }
+
+    fn textures(&mut self, textures: &[Texture]) {
+        let start = self.begin(Tag::TEXS);
+        for texture in textures {
+            self.string(&texture.file);
+            self.u32(texture.flags);
+            self.u32(texture.blend);
+            self.f32s(&texture.position);
+            self.f32s(&texture.scale);
+            self.f32s(&[texture.rotation]);
+        }
+        self.end(start);
+    }
+
+    /// Writes every brush with as many texture layers as the brush with the most, filling the
+    /// rest with [`TextureId::NONE`].
+    fn brushes(&mut self, brushes: &[Brush]) {
+        let start = self.begin(Tag::BRUS);
+        let n_texs = brushes.iter().map(|brush| brush.texture_id.len()).max().unwrap_or(0);
+        self.u32(n_texs as u32);
+        for brush in brushes {
+            self.string(&brush.name);
+            self.f32s(&brush.color);
+            self.f32s(&[brush.shininess]);
+            self.u32(brush.blend);
+            self.u32(brush.fx);
+            for layer in 0..n_texs {
+                self.u32(brush.texture_id.get(layer).copied().unwrap_or(TextureId::NONE).raw());
+            }
+        }
+        self.end(start);
+    }
+
+    fn node(&mut self, node: &Node) {
+        let start = self.begin(Tag::NODE);
+        self.string(&node.name);
+        self.f32s(&node.position);
+        self.f32s(&node.scale);
+        self.f32s(&node.rotation);
+
+        if !node.mesh.vertices.vertices.is_empty() || !node.mesh.triangles.is_empty() {
+            self.mesh(&node.mesh);
+        }
+        if !node.bones.is_empty() {
+            let bones = self.begin(Tag::BONE);
+            for bone in &node.bones {
+                self.u32(bone.vertex_id);
+                self.f32s(&[bone.weight]);
+            }
+            self.end(bones);
+        }
+        if node.animation.frames != 0 || node.animation.fps != 0.0 {
+            self.animation(&node.animation);
+        }
+        for sequence in &node.sequences {
+            self.sequence(sequence);
+        }
+        for keys in &node.keys {
+            self.keys(keys);
+        }
+        for child in &node.children {
+            self.node(child);
+        }
+        self.end(start);
+    }
+
+    fn mesh(&mut self, mesh: &Mesh) {
+        let start = self.begin(Tag::MESH);
+        self.u32(mesh.brush_id.raw());
+        self.verts(&mesh.vertices);
+        for tris in &mesh.triangles {
+            let chunk = self.begin(Tag::TRIS);
+            self.u32(tris.brush_id.raw());
+            for face in &tris.indices {
+                face.iter().for_each(|&index| self.u32(index));
+            }
+            self.end(chunk);
+        }
+        self.end(start);
+    }
+
+    /// Writes the first two components of every set from `tex_coords` and zeroes the rest, as
+    /// the parser doesn't keep them.
+    fn verts(&mut self, verts: &Verts) {
+        let start = self.begin(Tag::VRTS);
+        self.u32(verts.flags);
+        self.u32(verts.tex_coord_sets);
+        self.u32(verts.tex_coord_set_size);
+        let size = verts.tex_coord_set_size as usize;
+        for vertex in &verts.vertices {
+            self.f32s(&vertex.position);
+            if verts.flags & 1 != 0 {
+                self.f32s(&vertex.normal);
+            }
+            if verts.flags & 2 != 0 {
+                self.f32s(&vertex.color);
+            }
+            for set in 0..verts.tex_coord_sets as usize {
+                let uv = vertex.tex_coords.get(set).copied().unwrap_or_default();
+                let mut components = [0.0; 4];
+                components[..2].copy_from_slice(&uv);
+                self.f32s(&components[..size.min(4)]);
+            }
+        }
+        self.end(start);
+    }
+
+    fn keys(&mut self, keys: &Keys) {
+        let start = self.begin(Tag::KEYS);
+        self.u32(keys.flags);
+        for key in &keys.keys {
+            self.u32(key.frame);
+            if keys.flags & 1 != 0 {
+                self.f32s(&key.position);
+            }
+            if keys.flags & 2 != 0 {
+                self.f32s(&key.scale);
+            }
+            if keys.flags & 4 != 0 {
+                self.f32s(&key.rotation);
+            }
+        }
+        self.end(start);
+    }
+
+    fn animation(&mut self, animation: &Animation) {
+        let start = self.begin(Tag::ANIM);
+        self.u32(animation.flags);
+        self.u32(animation.frames);
+        self.f32s(&[animation.fps]);
+        self.end(start);
+    }
+
+    fn sequence(&mut self, sequence: &Sequence) {
+        let start = self.begin(Tag::SEQS);
+        self.string(&sequence.name);
+        self.u32(sequence.first_frame);
+        self.u32(sequence.last_frame);
+        self.u32(sequence.unused);
+        self.end(start);
+    }
+}
+
+impl B3D {
+    /// Serializes the model in canonical chunk order.
+    ///
+    /// Only what the parser keeps is written: `PIVO` chunks and empty `BONE` chunks are
+    /// dropped, and texture coordinates past the second component are zeroed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer { bytes: vec![] };
+        let start = writer.begin(Tag::BB3D);
+        writer.u32(self.version);
+        if !self.textures.is_empty() {
+            writer.textures(&self.textures);
+        }
+        if !self.brushes.is_empty() {
+            writer.brushes(&self.brushes);
+        }
+        writer.node(&self.node);
+        writer.end(start);
+        writer.bytes
+    }
+
+    /// Serializes the model into `out`, see [`Self::to_bytes`].
+    pub fn write<W>(&self, out: &mut W) -> Result<(), Error>
+    where
+        W: Write
+    {
+        out.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Animation, Bone, Brush, BrushId, Key, Keys, Mesh, Node, Sequence, Texture, TextureId, Tris, Vertice, Verts,
+        B3D,
+    };
+
+    fn model() -> B3D {
+        let vertex = |position, tex_coord| Vertice {
+            position,
+            normal: [0.0, 0.0, -1.0],
+            tex_coords: vec![tex_coord],
+            ..Default::default()
+        };
+        B3D {
+            version: 1,
+            textures: vec![Texture {
+                file: "wall.png".into(),
+                flags: 1 | 8,
+                blend: 2,
+                position: [0.0, 0.5],
+                scale: [1.0, 2.0],
+                rotation: 0.25,
+            }],
+            brushes: vec![Brush {
+                name: "wall".into(),
+                color: [1.0, 0.5, 0.25, 1.0],
+                shininess: 0.5,
+                blend: 1,
+                fx: 16,
+                texture_id: vec![TextureId::new(0)],
+            }],
+            node: Node {
+                name: "root".into(),
+                scale: [1.0; 3],
+                rotation: [1.0, 0.0, 0.0, 0.0],
+                mesh: Mesh {
+                    brush_id: BrushId::new(0),
+                    vertices: Verts {
+                        flags: 1,
+                        tex_coord_sets: 1,
+                        tex_coord_set_size: 2,
+                        vertices: vec![
+                            vertex([0.0, 0.0, 0.0], [0.0, 1.0]),
+                            vertex([1.0, 0.0, 0.0], [1.0, 1.0]),
+                            vertex([0.0, 1.0, 0.0], [0.0, 0.0]),
+                        ],
+                    },
+                    triangles: vec![Tris { brush_id: BrushId::NONE, indices: vec![[0, 1, 2]] }],
+                },
+                animation: Animation { flags: 0, frames: 10, fps: 30.0 },
+                sequences: vec![Sequence { name: "idle".into(), first_frame: 0, last_frame: 9, unused: 0 }],
+                children: vec![Node {
+                    name: "bone".into(),
+                    position: [0.0, 1.0, 0.0],
+                    scale: [1.0; 3],
+                    rotation: [1.0, 0.0, 0.0, 0.0],
+                    bones: vec![Bone { vertex_id: 2, weight: 1.0 }],
+                    keys: vec![Keys {
+                        flags: 1 | 4,
+                        keys: vec![Key {
+                            frame: 1,
+                            position: [0.0, 2.0, 0.0],
+                            rotation: [1.0, 0.0, 0.0, 0.0],
+                            ..Default::default()
+                        }],
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn to_bytes_round_trips() {
+        let bytes = model().to_bytes();
+        let read = B3D::read(&bytes).unwrap();
+
+        assert_eq!(read.textures[0].file.as_str(), "wall.png");
+        assert_eq!(read.textures[0].scale, [1.0, 2.0]);
+        assert_eq!(read.brushes[0].texture_id, [TextureId::new(0)]);
+        assert_eq!(read.node.mesh.brush_id, BrushId::new(0));
+        assert_eq!(read.node.mesh.vertices.vertices[1].tex_coords, [[1.0, 1.0]]);
+        assert_eq!(read.node.mesh.triangles[0].indices, [[0, 1, 2]]);
+        assert_eq!(read.node.animation.frames, 10);
+        assert_eq!(read.node.sequences[0].name, "idle");
+
+        let bone = &read.node.children[0];
+        assert_eq!(bone.name.as_str(), "bone");
+        assert_eq!(bone.bones[0].vertex_id, 2);
+        assert_eq!(bone.keys[0].keys[0].position, [0.0, 2.0, 0.0]);
+
+        assert_eq!(read.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn brushes_are_padded_to_the_same_layer_count() {
+        let mut b3d = model();
+        b3d.brushes.push(Brush { name: "plain".into(), texture_id: vec![], ..b3d.brushes[0].clone() });
+        let read = B3D::read(&b3d.to_bytes()).unwrap();
+        assert_eq!(read.brushes[1].texture_id, [TextureId::NONE]);
+    }
+}
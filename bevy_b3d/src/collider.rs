@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::loader::{position, B3DLoaderSettings};
+
+/// The collision shape of a node with geometry, in the node's space with the loader's
+/// coordinate conversion and scale applied. Physics engines build their colliders from it, for
+/// example with avian's `Collider::trimesh`, `Collider::convex_hull` and `Collider::cuboid`
+/// or rapier's functions of the same names.
+#[derive(Asset, Debug, Clone, Reflect)]
+pub enum B3DCollider {
+    /// Every triangle of the mesh, except those with a corner past the last vertex.
+    TriMesh {
+        vertices: Vec<Vec3>,
+        indices: Vec<[u32; 3]>,
+    },
+    /// The points to build a convex hull around, the vertices of the mesh.
+    ConvexHull { points: Vec<Vec3> },
+    /// The box bounding the mesh.
+    Aabb { center: Vec3, half_extents: Vec3 },
+}
+
+/// Which [`B3DCollider`] to generate for a node.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum B3DColliderShape {
+    TriMesh,
+    ConvexHull,
+    Aabb,
+}
+
+/// Builds the collider of `mesh`.
+pub(crate) fn load_collider(
+    mesh: &b3d::Mesh,
+    shape: B3DColliderShape,
+    settings: &B3DLoaderSettings,
+) -> B3DCollider {
+    let vertices: Vec<Vec3> = mesh
+        .vertices
+        .positions()
+        .into_iter()
+        .map(|vertex| position(vertex, settings))
+        .collect();
+    match shape {
+        B3DColliderShape::TriMesh => {
+            let indices = mesh
+                .triangles
+                .iter()
+                .flat_map(|tris| &tris.indices)
+                .filter(|triangle| triangle.iter().all(|&i| (i as usize) < vertices.len()))
+                .map(|&triangle| {
+                    if settings.convert_coordinates {
+                        b3d::coords::triangle_to_right_handed(triangle)
+                    } else {
+                        triangle
+                    }
+                })
+                .collect();
+            B3DCollider::TriMesh { vertices, indices }
+        }
+        B3DColliderShape::ConvexHull => B3DCollider::ConvexHull { points: vertices },
+        B3DColliderShape::Aabb => {
+            let min = vertices
+                .iter()
+                .copied()
+                .reduce(Vec3::min)
+                .unwrap_or_default();
+            let max = vertices
+                .iter()
+                .copied()
+                .reduce(Vec3::max)
+                .unwrap_or_default();
+            B3DCollider::Aabb {
+                center: (min + max) / 2.0,
+                half_extents: (max - min) / 2.0,
+            }
+        }
+    }
+}
+
+/// Returns whether `name` matches `pattern`, where `*` stands for any run of characters,
+/// ignoring case.
+pub(crate) fn matches_pattern(name: &str, pattern: &str) -> bool {
+    let name = name.to_lowercase();
+    let pattern = pattern.to_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No wildcard, so the whole name has to match.
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
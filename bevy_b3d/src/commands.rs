@@ -0,0 +1,116 @@
+use bevy::{
+    animation::AnimationTarget,
+    ecs::system::EntityCommands,
+    prelude::*,
+    scene::{SceneInstance, SceneSpawner},
+};
+
+use crate::{B3DMaterial, B3D};
+
+/// Spawns the scenes of loaded b3d files without building a [`SceneBundle`] by hand, such as
+/// `commands.spawn_b3d(asset_server.load("model.b3d"))`.
+pub trait B3DCommandsExt {
+    /// Spawns an entity that gets the scene of `b3d` as soon as it's loaded, tagged with
+    /// [`B3DSceneRoot`]. Insert a [`B3DAutoplay`] on it to play one of its clips.
+    fn spawn_b3d<M: B3DMaterial>(&mut self, b3d: Handle<B3D<M>>) -> EntityCommands<'_>;
+}
+
+impl B3DCommandsExt for Commands<'_, '_> {
+    fn spawn_b3d<M: B3DMaterial>(&mut self, b3d: Handle<B3D<M>>) -> EntityCommands<'_> {
+        let mut entity = self.spawn_empty();
+        entity.insert_b3d(b3d);
+        entity
+    }
+}
+
+/// Turns existing entities into the roots of b3d scenes, see [`B3DCommandsExt`].
+pub trait B3DEntityCommandsExt {
+    /// Gives the entity the scene of `b3d` as soon as it's loaded, tagged with
+    /// [`B3DSceneRoot`].
+    fn insert_b3d<M: B3DMaterial>(&mut self, b3d: Handle<B3D<M>>) -> &mut Self;
+}
+
+impl B3DEntityCommandsExt for EntityCommands<'_> {
+    fn insert_b3d<M: B3DMaterial>(&mut self, b3d: Handle<B3D<M>>) -> &mut Self {
+        self.insert((SpatialBundle::default(), B3DSceneRoot(b3d)))
+    }
+}
+
+/// The root of a b3d scene spawned with [`B3DCommandsExt::spawn_b3d`], holding its file.
+#[derive(Component, Debug, Clone)]
+pub struct B3DSceneRoot<M: B3DMaterial = StandardMaterial>(pub Handle<B3D<M>>);
+
+/// Inserted on a [`B3DSceneRoot`] once its scene has been spawned beneath it.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct B3DSceneReady;
+
+/// Plays the clip of this name on repeat once the scene of its [`B3DSceneRoot`] is spawned,
+/// adding an [`AnimationPlayer`] if the file was loaded without one.
+#[derive(Component, Debug, Clone)]
+pub struct B3DAutoplay(pub String);
+
+/// Gives scene roots the scene of their file once it's loaded.
+pub(crate) fn attach_b3d_scenes<M: B3DMaterial>(
+    mut commands: Commands,
+    roots: Query<(Entity, &B3DSceneRoot<M>), Without<Handle<Scene>>>,
+    files: Res<Assets<B3D<M>>>,
+) {
+    for (entity, root) in &roots {
+        if let Some(b3d) = files.get(&root.0) {
+            commands.entity(entity).insert(b3d.scene.clone());
+        }
+    }
+}
+
+/// Marks scene roots whose scene is spawned and starts their [`B3DAutoplay`] clip.
+#[allow(clippy::type_complexity)]
+pub(crate) fn finish_b3d_scenes<M: B3DMaterial>(
+    mut commands: Commands,
+    roots: Query<
+        (
+            Entity,
+            &B3DSceneRoot<M>,
+            &SceneInstance,
+            Option<&B3DAutoplay>,
+        ),
+        Without<B3DSceneReady>,
+    >,
+    targets: Query<&AnimationTarget>,
+    mut players: Query<&mut AnimationPlayer>,
+    files: Res<Assets<B3D<M>>>,
+    scene_spawner: Res<SceneSpawner>,
+) {
+    for (entity, root, instance, autoplay) in &roots {
+        if !scene_spawner.instance_is_ready(**instance) {
+            continue;
+        }
+        commands.entity(entity).insert(B3DSceneReady);
+        let (Some(autoplay), Some(b3d)) = (autoplay, files.get(&root.0)) else {
+            continue;
+        };
+        let (Some(graph), Some(&node)) = (
+            &b3d.animation_graph,
+            b3d.named_animation_nodes.get(&autoplay.0),
+        ) else {
+            warn!("No clip named {:?} to play", autoplay.0);
+            continue;
+        };
+        // Clips are played by the entity every animated node names as its player.
+        let Some(player) = scene_spawner
+            .iter_instance_entities(**instance)
+            .find_map(|entity| targets.get(entity).ok())
+            .map(|target| target.player)
+        else {
+            continue;
+        };
+        if let Ok(mut animation_player) = players.get_mut(player) {
+            animation_player.play(node).repeat();
+        } else {
+            let mut animation_player = AnimationPlayer::default();
+            animation_player.play(node).repeat();
+            commands
+                .entity(player)
+                .insert((animation_player, graph.clone()));
+        }
+    }
+}
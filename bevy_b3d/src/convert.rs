@@ -0,0 +1,93 @@
+//! Builds bevy assets straight out of parsed b3d data, for files that don't come through the
+//! [`AssetServer`], such as downloaded mods or models read out of archives. They convert the
+//! same way [`B3DLoader`](crate::B3DLoader) does, following the [`B3DLoaderSettings`] that
+//! apply to them.
+
+use bevy::{
+    prelude::*,
+    render::texture::{CompressedImageFormats, Image},
+};
+
+use crate::loader::{
+    animation_fps, brush_groups, decode_texture, load_animation, load_material, load_primitive,
+    node_transform, texture_sampler, B3DError, B3DLoaderSettings,
+};
+
+/// Converts every triangle of `mesh` into a single [`Mesh`], whatever its brushes.
+pub fn b3d_to_mesh(mesh: &b3d::Mesh, settings: &B3DLoaderSettings) -> Mesh {
+    let indices = mesh
+        .triangles
+        .iter()
+        .flat_map(|tris| tris.indices.iter().flatten().copied())
+        .collect();
+    load_primitive(mesh, indices, None, settings)
+}
+
+/// Converts `mesh` into a [`Mesh`] per brush its triangles use, in the order the brushes are
+/// first used.
+pub fn b3d_to_primitives(
+    mesh: &b3d::Mesh,
+    settings: &B3DLoaderSettings,
+) -> Vec<(b3d::BrushId, Mesh)> {
+    brush_groups(mesh)
+        .into_iter()
+        .map(|(brush_id, indices)| (brush_id, load_primitive(mesh, indices, None, settings)))
+        .collect()
+}
+
+/// Builds the [`StandardMaterial`] of `brush`. `textures` is the file's `TEXS` chunk and
+/// `images` holds the image of each of its entries, if there is one.
+pub fn brush_to_material(
+    brush: &b3d::Brush,
+    textures: &[b3d::Texture],
+    images: &[Option<Handle<Image>>],
+    settings: &B3DLoaderSettings,
+) -> StandardMaterial {
+    load_material(brush, &brush.resolve_layers(textures), images, settings)
+}
+
+/// Decodes the image file of `texture` out of `bytes`, with the sampler and mask its flags ask
+/// for.
+pub fn texture_to_image(
+    texture: &b3d::Texture,
+    bytes: &[u8],
+    settings: &B3DLoaderSettings,
+) -> Result<Image, B3DError> {
+    decode_texture(
+        bytes,
+        texture.file.as_str().into(),
+        CompressedImageFormats::all(),
+        texture_sampler(texture, settings),
+        settings.asset_usages,
+        texture.flags & 4 != 0,
+    )
+}
+
+/// Returns the local [`Transform`] of `node`.
+pub fn node_to_transform(node: &b3d::Node, settings: &B3DLoaderSettings) -> Transform {
+    node_transform(node, settings)
+}
+
+/// Builds a clip per sequence of `b3d`, or a single one named `Animation0` if it has none,
+/// targeting nodes the way the loaded scene names them. Returns nothing for files without an
+/// `ANIM` chunk.
+pub fn b3d_to_animation_clips(
+    b3d: &b3d::B3D,
+    settings: &B3DLoaderSettings,
+) -> Vec<(String, AnimationClip)> {
+    let Some(animation) = b3d.animation() else {
+        return vec![];
+    };
+    b3d.clips()
+        .into_iter()
+        .map(|clip| {
+            let animation_clip = load_animation(
+                &b3d.node,
+                clip.frames,
+                animation_fps(animation, settings),
+                settings,
+            );
+            (clip.name, animation_clip)
+        })
+        .collect()
+}
@@ -0,0 +1,244 @@
+use bevy::{
+    prelude::*,
+    render::mesh::{skinning::SkinnedMesh, VertexAttributeValues},
+    utils::HashSet,
+};
+
+/// Draws the skeletons of skinned meshes with gizmos, labels their joints and can tint them by
+/// their weights, to track down rigging and skinning issues in imported models. Configured
+/// through the [`B3DDebugSettings`] resource.
+#[derive(Default)]
+pub struct B3DDebugPlugin;
+
+impl Plugin for B3DDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<B3DDebugSettings>()
+            .add_systems(Update, (draw_skeletons, label_joints, show_weight_heatmaps));
+    }
+}
+
+/// What [`B3DDebugPlugin`] shows.
+#[derive(Resource, Debug, Clone)]
+pub struct B3DDebugSettings {
+    /// Draws a line from every joint to its parent joint.
+    pub bones: bool,
+    pub bone_color: Color,
+    /// Draws the axes of every joint this long, so joints can be told apart by their
+    /// orientation.
+    pub joint_axes: Option<f32>,
+    /// Shows the [`Name`] of every joint next to it in this font size, as UI text placed over
+    /// the view of the first active camera.
+    pub joint_names: Option<f32>,
+    /// Tints skinned meshes by their weights for this joint entity, blue where it has no
+    /// influence and red where it has all of it. Only meshes drawn with a
+    /// [`StandardMaterial`] whose geometry is kept in the main world are tinted.
+    pub weight_heatmap: Option<Entity>,
+}
+
+impl Default for B3DDebugSettings {
+    fn default() -> Self {
+        Self {
+            bones: true,
+            bone_color: Color::srgb(1.0, 0.8, 0.0),
+            joint_axes: None,
+            joint_names: None,
+            weight_heatmap: None,
+        }
+    }
+}
+
+/// The UI text showing the name of this joint entity.
+#[derive(Component)]
+struct JointLabel(Entity);
+
+/// The mesh and material a skinned mesh had before its weights were shown.
+#[derive(Component)]
+struct WeightHeatmap {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+fn draw_skeletons(
+    settings: Res<B3DDebugSettings>,
+    skinned_meshes: Query<&SkinnedMesh>,
+    joints: Query<(&GlobalTransform, Option<&Parent>)>,
+    mut gizmos: Gizmos,
+) {
+    if !settings.bones && settings.joint_axes.is_none() {
+        return;
+    }
+    // Primitives of the same mesh share their joints, so each is only drawn once.
+    let skeleton: HashSet<Entity> = skinned_meshes
+        .iter()
+        .flat_map(|skin| skin.joints.iter().copied())
+        .collect();
+    for &joint in &skeleton {
+        let Ok((transform, parent)) = joints.get(joint) else {
+            continue;
+        };
+        if let Some(length) = settings.joint_axes {
+            gizmos.axes(*transform, length);
+        }
+        let parent = parent
+            .map(Parent::get)
+            .filter(|parent| skeleton.contains(parent));
+        if let Some((parent_transform, _)) = parent.and_then(|parent| joints.get(parent).ok()) {
+            if settings.bones {
+                gizmos.line(
+                    parent_transform.translation(),
+                    transform.translation(),
+                    settings.bone_color,
+                );
+            }
+        }
+    }
+}
+
+fn label_joints(
+    mut commands: Commands,
+    settings: Res<B3DDebugSettings>,
+    skinned_meshes: Query<&SkinnedMesh>,
+    joints: Query<(&GlobalTransform, &Name)>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut labels: Query<(Entity, &JointLabel, &mut Style, &mut Visibility)>,
+) {
+    let skeleton: HashSet<Entity> = match settings.joint_names {
+        Some(_) => skinned_meshes
+            .iter()
+            .flat_map(|skin| skin.joints.iter().copied())
+            .collect(),
+        None => HashSet::new(),
+    };
+    let camera = cameras.iter().find(|(camera, _)| camera.is_active);
+
+    let mut labeled = HashSet::new();
+    for (label, &JointLabel(joint), mut style, mut visibility) in &mut labels {
+        let Some((transform, _)) = joints.get(joint).ok().filter(|_| skeleton.contains(&joint))
+        else {
+            commands.entity(label).despawn();
+            continue;
+        };
+        labeled.insert(joint);
+        let position = camera.and_then(|(camera, camera_transform)| {
+            camera.world_to_viewport(camera_transform, transform.translation())
+        });
+        if let Some(position) = position {
+            style.left = Val::Px(position.x);
+            style.top = Val::Px(position.y);
+            *visibility = Visibility::Inherited;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+
+    let Some(font_size) = settings.joint_names else {
+        return;
+    };
+    for &joint in skeleton.difference(&labeled) {
+        let Ok((_, name)) = joints.get(joint) else {
+            continue;
+        };
+        let text = TextStyle {
+            font_size,
+            color: settings.bone_color,
+            ..default()
+        };
+        let label = TextBundle::from_section(name.as_str(), text).with_style(Style {
+            position_type: PositionType::Absolute,
+            ..default()
+        });
+        commands.spawn((
+            TextBundle {
+                // Placed over its joint from the next frame on.
+                visibility: Visibility::Hidden,
+                ..label
+            },
+            JointLabel(joint),
+        ));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn show_weight_heatmaps(
+    mut commands: Commands,
+    settings: Res<B3DDebugSettings>,
+    mut skinned_meshes: Query<(
+        Entity,
+        Ref<SkinnedMesh>,
+        &mut Handle<Mesh>,
+        &mut Handle<StandardMaterial>,
+        Option<&WeightHeatmap>,
+    )>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut heatmap_material: Local<Option<Handle<StandardMaterial>>>,
+) {
+    for (entity, skin, mut mesh, mut material, heatmap) in &mut skinned_meshes {
+        if !settings.is_changed() && !skin.is_added() {
+            continue;
+        }
+        let Some(joint) = settings.weight_heatmap else {
+            if let Some(heatmap) = heatmap {
+                *mesh = heatmap.mesh.clone();
+                *material = heatmap.material.clone();
+                commands.entity(entity).remove::<WeightHeatmap>();
+            }
+            continue;
+        };
+        let original = heatmap.map_or_else(|| mesh.clone(), |heatmap| heatmap.mesh.clone());
+        let joint_index = skin.joints.iter().position(|&other| other == joint);
+        let Some(tinted) = meshes
+            .get(&original)
+            .and_then(|original| weight_heatmap(original, joint_index))
+        else {
+            continue;
+        };
+        if heatmap.is_none() {
+            commands.entity(entity).insert(WeightHeatmap {
+                mesh: original,
+                material: material.clone(),
+            });
+        }
+        *mesh = meshes.add(tinted);
+        // Unlit, so the vertex colors show as they are.
+        *material = heatmap_material
+            .get_or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    unlit: true,
+                    ..default()
+                })
+            })
+            .clone();
+    }
+}
+
+/// Returns a copy of `mesh` colored by the weights of the joint at `joint_index` of its skin,
+/// or `None` if it has no weights.
+fn weight_heatmap(mesh: &Mesh, joint_index: Option<usize>) -> Option<Mesh> {
+    let Some(VertexAttributeValues::Float32x4(weights)) =
+        mesh.attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT)
+    else {
+        return None;
+    };
+    let Some(VertexAttributeValues::Uint16x4(indices)) =
+        mesh.attribute(Mesh::ATTRIBUTE_JOINT_INDEX)
+    else {
+        return None;
+    };
+    let colors: Vec<[f32; 4]> = indices
+        .iter()
+        .zip(weights)
+        .map(|(indices, weights)| {
+            let weight: f32 = indices
+                .iter()
+                .zip(weights)
+                .filter(|(&index, _)| Some(index as usize) == joint_index)
+                .map(|(_, weight)| weight)
+                .sum();
+            LinearRgba::rgb(weight, 0.0, 1.0 - weight).to_f32_array()
+        })
+        .collect();
+    let mut mesh = mesh.clone();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    Some(mesh)
+}
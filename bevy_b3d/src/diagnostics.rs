@@ -0,0 +1,79 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    prelude::*,
+};
+
+/// Reports how long each b3d file took to load and how much it held as diagnostics, so they
+/// show up next to bevy's own, such as in the log of `LogDiagnosticsPlugin`.
+#[derive(Default)]
+pub struct B3DDiagnosticsPlugin;
+
+impl B3DDiagnosticsPlugin {
+    /// Milliseconds spent parsing the file.
+    pub const PARSE_TIME: DiagnosticPath = DiagnosticPath::const_new("b3d/parse_time");
+    /// Milliseconds spent reading and decoding textures.
+    pub const TEXTURE_TIME: DiagnosticPath = DiagnosticPath::const_new("b3d/texture_time");
+    /// Milliseconds spent building meshes.
+    pub const MESH_TIME: DiagnosticPath = DiagnosticPath::const_new("b3d/mesh_time");
+    /// Meshes in the file, one per node with geometry.
+    pub const MESHES: DiagnosticPath = DiagnosticPath::const_new("b3d/meshes");
+    /// Materials in the file, one per brush.
+    pub const MATERIALS: DiagnosticPath = DiagnosticPath::const_new("b3d/materials");
+    /// Animation clips in the file.
+    pub const ANIMATIONS: DiagnosticPath = DiagnosticPath::const_new("b3d/animations");
+}
+
+impl Plugin for B3DDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        for path in [Self::PARSE_TIME, Self::TEXTURE_TIME, Self::MESH_TIME] {
+            app.register_diagnostic(Diagnostic::new(path).with_suffix("ms"));
+        }
+        for path in [Self::MESHES, Self::MATERIALS, Self::ANIMATIONS] {
+            app.register_diagnostic(Diagnostic::new(path));
+        }
+        app.init_resource::<LoadStatsQueue>()
+            .add_systems(Update, record_load_stats);
+    }
+}
+
+/// What a single load measured.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct LoadStats {
+    pub(crate) parse_time: Duration,
+    pub(crate) texture_time: Duration,
+    pub(crate) mesh_time: Duration,
+    pub(crate) meshes: usize,
+    pub(crate) materials: usize,
+    pub(crate) animations: usize,
+}
+
+/// Loads finished but not yet recorded, shared with the loader. Only present with
+/// [`B3DDiagnosticsPlugin`], so nothing piles up without it.
+#[derive(Resource, Default, Clone)]
+pub(crate) struct LoadStatsQueue(pub(crate) Arc<Mutex<VecDeque<LoadStats>>>);
+
+/// Records one load per frame, since a diagnostic only keeps one measurement a frame.
+fn record_load_stats(queue: Res<LoadStatsQueue>, mut diagnostics: Diagnostics) {
+    let Some(stats) = queue.0.lock().unwrap().pop_front() else {
+        return;
+    };
+    let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    diagnostics.add_measurement(&B3DDiagnosticsPlugin::PARSE_TIME, || {
+        millis(stats.parse_time)
+    });
+    diagnostics.add_measurement(&B3DDiagnosticsPlugin::TEXTURE_TIME, || {
+        millis(stats.texture_time)
+    });
+    diagnostics.add_measurement(&B3DDiagnosticsPlugin::MESH_TIME, || millis(stats.mesh_time));
+    diagnostics.add_measurement(&B3DDiagnosticsPlugin::MESHES, || stats.meshes as f64);
+    diagnostics.add_measurement(&B3DDiagnosticsPlugin::MATERIALS, || stats.materials as f64);
+    diagnostics.add_measurement(&B3DDiagnosticsPlugin::ANIMATIONS, || {
+        stats.animations as f64
+    });
+}
@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+
+use bevy::{
+    pbr::{environment_map::ReflectionProbeBundle, LightProbe},
+    prelude::*,
+    render::primitives::Aabb,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::loader::texture_handle;
+
+/// How the environment map of a brush is projected, after the flag of its texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum B3DEnvMapping {
+    /// Flag 64, a sphere map looked up by the view space normal.
+    #[default]
+    Spherical,
+    /// Flag 128, a cube map looked up by the reflected view direction.
+    Cubic,
+}
+
+/// The environment map a mesh entity of a b3d scene is drawn with, for projects that want to
+/// reproduce Blitz3D's reflections exactly. Its material only approximates them by being more
+/// metallic, see [`B3DLoaderSettings::env_map_metallic`](crate::B3DLoaderSettings).
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct B3DEnvironmentMap {
+    pub mapping: B3DEnvMapping,
+    /// The texture of the layer, if it loaded.
+    pub texture: Option<Handle<Image>>,
+    /// The texture's blend mode, as in `Texture::blend`.
+    pub blend: u32,
+    /// The texture stage the layer occupies on the brush.
+    pub stage: usize,
+    /// The reflection probe spawned around the entity, from
+    /// [`B3DLoaderSettings::reflection_probe`](crate::B3DLoaderSettings).
+    pub probe: Option<B3DProbeMaps>,
+}
+
+/// The loaded maps of a [`B3DReflectionProbe`].
+#[derive(Debug, Clone, Default, Reflect)]
+pub struct B3DProbeMaps {
+    pub diffuse_map: Handle<Image>,
+    pub specular_map: Handle<Image>,
+    pub intensity: f32,
+}
+
+/// A prefiltered environment map reflected by the meshes drawn with an environment mapped
+/// texture, through a reflection probe fitted to their bounds. Both maps are cube maps, as
+/// for [`EnvironmentMapLight`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct B3DReflectionProbe {
+    /// The path of the diffuse map, relative to the asset root.
+    pub diffuse_map: PathBuf,
+    /// The path of the specular map, relative to the asset root.
+    pub specular_map: PathBuf,
+    pub intensity: f32,
+}
+
+/// Returns the first environment mapped layer of `layers`, described as a component.
+pub(crate) fn environment_map(
+    layers: &[b3d::TextureLayer],
+    textures: &[Option<Handle<Image>>],
+    probe: Option<&B3DProbeMaps>,
+) -> Option<B3DEnvironmentMap> {
+    layers.iter().find_map(|layer| {
+        let mapping = match layer.env_map? {
+            b3d::EnvMap::Spherical => B3DEnvMapping::Spherical,
+            b3d::EnvMap::Cubic => B3DEnvMapping::Cubic,
+        };
+        Some(B3DEnvironmentMap {
+            mapping,
+            texture: texture_handle(layer, textures),
+            blend: layer.texture.blend,
+            stage: layer.stage,
+            probe: probe.cloned(),
+        })
+    })
+}
+
+/// Spawns a reflection probe around every mesh entity drawn with `M` that has the maps of one,
+/// once its bounds are known. The probe is a child of the entity, so it follows it.
+#[allow(clippy::type_complexity)]
+pub(crate) fn spawn_reflection_probes<M: Material>(
+    mut commands: Commands,
+    env_maps: Query<
+        (Entity, &B3DEnvironmentMap, &Aabb),
+        (With<Handle<M>>, Or<(Added<B3DEnvironmentMap>, Added<Aabb>)>),
+    >,
+) {
+    for (entity, env_map, aabb) in &env_maps {
+        let Some(probe) = &env_map.probe else {
+            continue;
+        };
+        // A little larger than the mesh, so flat meshes still sit inside it.
+        let size = Vec3::from(aabb.half_extents) * 2.2 + Vec3::splat(0.01);
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                ReflectionProbeBundle {
+                    spatial: SpatialBundle::from_transform(
+                        Transform::from_translation(aabb.center.into()).with_scale(size),
+                    ),
+                    light_probe: LightProbe,
+                    environment_map: EnvironmentMapLight {
+                        diffuse_map: probe.diffuse_map.clone(),
+                        specular_map: probe.specular_map.clone(),
+                        intensity: probe.intensity,
+                    },
+                },
+                Name::new("ReflectionProbe"),
+            ));
+        });
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::{
+    asset::load_internal_asset,
+    pbr::{ExtendedMaterial, MaterialExtension},
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef, ShaderType},
+};
+
+use crate::loader::{is_lightmap, texture_handle, uv_transform};
+use crate::{B3DLoaderSettings, B3DMaterial, B3DMaterialPlugin};
+
+const LAYERS_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x6a1c_3f2e_9b84_4d07_a5e2_71c0_d9b3_58f4);
+
+/// A [`StandardMaterial`] that also draws the texture layers of a brush past the first, such as
+/// detail and glow maps.
+pub type B3DLayeredMaterial = ExtendedMaterial<StandardMaterial, B3DLayers>;
+
+/// Loads b3d files as `B3D<B3DLayeredMaterial>` and renders their [`B3DLayeredMaterial`]s.
+#[derive(Default)]
+pub struct B3DLayeredMaterialPlugin;
+
+impl Plugin for B3DLayeredMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, LAYERS_SHADER_HANDLE, "layers.wgsl", Shader::from_wgsl);
+        app.add_plugins((
+            MaterialPlugin::<B3DLayeredMaterial>::default(),
+            B3DMaterialPlugin::<B3DLayeredMaterial>::default(),
+        ));
+    }
+}
+
+/// Up to three texture layers blended over the base color of a [`StandardMaterial`] in order,
+/// with Blitz3D's blend modes. Lightmaps and environment maps aren't drawn as layers.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct B3DLayers {
+    #[uniform(100)]
+    pub uniform: B3DLayerUniform,
+    #[texture(101)]
+    #[sampler(102)]
+    pub layer_1: Option<Handle<Image>>,
+    #[texture(103)]
+    #[sampler(104)]
+    pub layer_2: Option<Handle<Image>>,
+    #[texture(105)]
+    #[sampler(106)]
+    pub layer_3: Option<Handle<Image>>,
+}
+
+/// How each layer of [`B3DLayers`] is drawn.
+#[derive(ShaderType, Reflect, Debug, Clone, Copy, Default)]
+pub struct B3DLayerUniform {
+    /// The blend mode of each layer, as in `Texture::blend`, or 0 for layers without a texture.
+    pub blend: UVec4,
+    /// The uv set each layer is sampled with, 0 or 1.
+    pub uv_set: UVec4,
+    pub uv_transform: [Mat3; 3],
+}
+
+impl MaterialExtension for B3DLayers {
+    fn fragment_shader() -> ShaderRef {
+        LAYERS_SHADER_HANDLE.into()
+    }
+
+    fn deferred_fragment_shader() -> ShaderRef {
+        LAYERS_SHADER_HANDLE.into()
+    }
+}
+
+impl B3DMaterial for B3DLayeredMaterial {
+    fn from_brush(
+        brush: &b3d::Brush,
+        layers: &[b3d::TextureLayer],
+        textures: &[Option<Handle<Image>>],
+        settings: &B3DLoaderSettings,
+    ) -> Self {
+        let base = StandardMaterial::from_brush(brush, layers, textures, settings);
+        let mut extension = B3DLayers::default();
+        // The first layer that isn't a lightmap is the base material's texture.
+        let base_layer = layers
+            .iter()
+            .position(|layer| !is_lightmap(&layer) && layer.env_map.is_none());
+        let extra_layers = layers
+            .iter()
+            .enumerate()
+            .filter(|&(index, layer)| {
+                Some(index) != base_layer && !is_lightmap(&layer) && layer.env_map.is_none()
+            })
+            .map(|(_, layer)| layer)
+            .take(3);
+        for (index, layer) in extra_layers.enumerate() {
+            let Some(texture) = texture_handle(layer, textures) else {
+                continue;
+            };
+            match index {
+                0 => extension.layer_1 = Some(texture),
+                1 => extension.layer_2 = Some(texture),
+                _ => extension.layer_3 = Some(texture),
+            }
+            extension.uniform.blend[index] = layer.texture.blend;
+            extension.uniform.uv_set[index] = layer.uses_secondary_uv as u32;
+            extension.uniform.uv_transform[index] = Mat3::from(uv_transform(layer.texture));
+        }
+        ExtendedMaterial { base, extension }
+    }
+}
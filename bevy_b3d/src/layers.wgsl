@@ -0,0 +1,105 @@
+// Draws the texture layers of a brush past the first on top of a `StandardMaterial`, see
+// `B3DLayers`.
+
+#import bevy_pbr::{
+    pbr_functions::alpha_discard,
+    pbr_fragment::pbr_input_from_standard_material,
+}
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::{
+    prepass_io::{VertexOutput, FragmentOutput},
+    pbr_deferred_functions::deferred_output,
+}
+#else
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+    pbr_types::STANDARD_MATERIAL_FLAGS_UNLIT_BIT,
+}
+#endif
+
+struct B3DLayerUniform {
+    blend: vec4<u32>,
+    uv_set: vec4<u32>,
+    uv_transform: array<mat3x3<f32>, 3>,
+}
+
+@group(2) @binding(100) var<uniform> layers: B3DLayerUniform;
+@group(2) @binding(101) var layer_1_texture: texture_2d<f32>;
+@group(2) @binding(102) var layer_1_sampler: sampler;
+@group(2) @binding(103) var layer_2_texture: texture_2d<f32>;
+@group(2) @binding(104) var layer_2_sampler: sampler;
+@group(2) @binding(105) var layer_3_texture: texture_2d<f32>;
+@group(2) @binding(106) var layer_3_sampler: sampler;
+
+fn layer_uv(in: VertexOutput, layer: u32) -> vec2<f32> {
+    var uv = vec2<f32>(0.0);
+#ifdef VERTEX_UVS_A
+    uv = in.uv;
+#endif
+#ifdef VERTEX_UVS_B
+    if layers.uv_set[layer] == 1u {
+        uv = in.uv_b;
+    }
+#endif
+    return (layers.uv_transform[layer] * vec3(uv, 1.0)).xy;
+}
+
+// Combines a layer with the ones beneath it the way Blitz3D's blend modes do.
+fn blend_layer(color: vec4<f32>, layer: vec4<f32>, blend: u32) -> vec4<f32> {
+    switch blend {
+        // Unused.
+        case 0u: {
+            return color;
+        }
+        // Alpha.
+        case 1u: {
+            return vec4(mix(color.rgb, layer.rgb, layer.a), color.a);
+        }
+        // Add.
+        case 3u: {
+            return vec4(color.rgb + layer.rgb, color.a);
+        }
+        // Multiply, then double.
+        case 5u: {
+            return vec4(color.rgb * layer.rgb * 2.0, color.a * layer.a);
+        }
+        // Multiply, which dot3 layers fall back to as well.
+        default: {
+            return color * layer;
+        }
+    }
+}
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+
+    // Sampled up front, since sampling has to happen in uniform control flow.
+    let layer_1 = textureSample(layer_1_texture, layer_1_sampler, layer_uv(in, 0u));
+    let layer_2 = textureSample(layer_2_texture, layer_2_sampler, layer_uv(in, 1u));
+    let layer_3 = textureSample(layer_3_texture, layer_3_sampler, layer_uv(in, 2u));
+    var color = pbr_input.material.base_color;
+    color = blend_layer(color, layer_1, layers.blend.x);
+    color = blend_layer(color, layer_2, layers.blend.y);
+    color = blend_layer(color, layer_3, layers.blend.z);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, color);
+
+#ifdef PREPASS_PIPELINE
+    let out = deferred_output(in, pbr_input);
+#else
+    var out: FragmentOutput;
+    if (pbr_input.material.flags & STANDARD_MATERIAL_FLAGS_UNLIT_BIT) == 0u {
+        out.color = apply_pbr_lighting(pbr_input);
+    } else {
+        out.color = pbr_input.material.base_color;
+    }
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+#endif
+
+    return out;
+}
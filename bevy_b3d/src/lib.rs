@@ -1,24 +1,85 @@
 pub use b3d;
+#[cfg(feature = "colliders")]
+pub use collider::*;
+pub use commands::*;
+pub use convert::*;
+#[cfg(feature = "debug")]
+pub use debug::*;
+pub use diagnostics::B3DDiagnosticsPlugin;
+pub use env_map::*;
+pub use layers::*;
 pub use loader::*;
+pub use processor::*;
 
+#[cfg(feature = "colliders")]
+mod collider;
+mod commands;
+mod convert;
+#[cfg(feature = "debug")]
+mod debug;
+mod diagnostics;
+mod env_map;
+// The size checks `ShaderType` derives are never called.
+#[allow(dead_code)]
+mod layers;
 mod loader;
+mod processor;
+
+use std::marker::PhantomData;
 
 use bevy::{
+    asset::AssetPath,
     prelude::*,
     reflect::TypePath,
-    render::{renderer::RenderDevice, texture::CompressedImageFormats},
+    render::{renderer::RenderDevice, texture::CompressedImageFormats, view::VisibilitySystems},
+    utils::HashMap,
 };
 
+use crate::commands::{attach_b3d_scenes, finish_b3d_scenes};
+use crate::diagnostics::LoadStatsQueue;
+use crate::env_map::spawn_reflection_probes;
+
 /// Adss support for b3d file loading to the app.
 #[derive(Default)]
 pub struct B3DPlugin;
 
 impl Plugin for B3DPlugin {
     fn build(&self, app: &mut App) {
-        app.init_asset::<B3D>()
-            .init_asset::<B3DNode>()
-            .init_asset::<B3DMesh>()
-            .preregister_asset_loader::<B3DLoader>(&["b3d"]);
+        app.add_plugins(B3DMaterialPlugin::<StandardMaterial>::default());
+    }
+}
+
+/// Adds support for loading b3d files as `B3D<M>`, with materials built by
+/// [`B3DMaterial::from_brush`] instead of [`StandardMaterial`]s. [`B3DPlugin`] adds the one for
+/// [`StandardMaterial`].
+pub struct B3DMaterialPlugin<M>(PhantomData<fn() -> M>);
+
+impl<M> Default for B3DMaterialPlugin<M> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<M: B3DMaterial> Plugin for B3DMaterialPlugin<M> {
+    fn build(&self, app: &mut App) {
+        app.register_type::<B3DMaterialInfo>()
+            .register_type::<B3DEnvironmentMap>()
+            .init_asset::<B3D<M>>()
+            .init_asset::<B3DNode<M>>()
+            .init_asset::<B3DMesh<M>>()
+            .preregister_asset_loader::<B3DLoader<M>>(&["b3d"])
+            .register_asset_processor(B3DGeometryCleanup::<M>::default())
+            .add_systems(Update, (attach_b3d_scenes::<M>, finish_b3d_scenes::<M>))
+            .add_systems(
+                PostUpdate,
+                spawn_reflection_probes::<M>.after(VisibilitySystems::CalculateBounds),
+            );
+        // Colliders don't depend on the material, so they're shared by every material's plugin.
+        #[cfg(feature = "colliders")]
+        if !app.world().contains_resource::<Assets<B3DCollider>>() {
+            app.init_asset::<B3DCollider>()
+                .register_asset_reflect::<B3DCollider>();
+        }
     }
 
     fn finish(&self, app: &mut App) {
@@ -27,32 +88,169 @@ impl Plugin for B3DPlugin {
 
             None => CompressedImageFormats::NONE,
         };
-        app.register_asset_loader(B3DLoader {
+        let asset_server = app.world().resource::<AssetServer>().clone();
+        let diagnostics = app.world().get_resource::<LoadStatsQueue>().cloned();
+        app.register_asset_loader(B3DLoader::<M> {
             supported_compressed_formats,
+            asset_server,
+            diagnostics,
+            marker: PhantomData,
         });
     }
 }
 
+/// A material that can be built from a b3d brush, to load b3d files with custom shaders through
+/// [`B3DMaterialPlugin`].
+pub trait B3DMaterial: Material + Default {
+    /// Builds the material of `brush`, given its resolved texture `layers`, the loaded
+    /// texture of every entry of the file's `TEXS` chunk, if it loaded, and the settings the
+    /// file is loaded with.
+    fn from_brush(
+        brush: &b3d::Brush,
+        layers: &[b3d::TextureLayer],
+        textures: &[Option<Handle<Image>>],
+        settings: &B3DLoaderSettings,
+    ) -> Self;
+}
+
 /// Representation of a loaded b3d file.
 #[derive(Asset, Debug, TypePath)]
-pub struct B3D {
+pub struct B3D<M: B3DMaterial = StandardMaterial> {
     pub scene: Handle<Scene>,
-    pub meshes: Vec<Handle<B3DMesh>>,
-    pub materials: Vec<Handle<StandardMaterial>>,
-    pub nodes: Vec<Handle<B3DNode>>,
+    /// A scene per child of the root node, so the props of a file can be spawned one by one.
+    pub scenes: Vec<Handle<Scene>>,
+    /// [`Self::scenes`] by the name of the child, holding the first of children sharing a name.
+    pub named_scenes: HashMap<String, Handle<Scene>>,
+    /// The clips cut from the file's `KEYS` chunks, one per `SEQS` chunk or a single one
+    /// spanning every key.
+    pub animations: Vec<Handle<AnimationClip>>,
+    /// [`Self::animations`] by sequence name. The single clip of a file without sequences is
+    /// named `Animation0`.
+    pub named_animations: HashMap<String, Handle<AnimationClip>>,
+    /// A graph with every clip of [`Self::animations`] as a child of its root.
+    pub animation_graph: Option<Handle<AnimationGraph>>,
+    /// The node of each clip in [`Self::animation_graph`], by the clip's name.
+    pub named_animation_nodes: HashMap<String, AnimationNodeIndex>,
+    pub meshes: Vec<Handle<B3DMesh<M>>>,
+    /// [`Self::meshes`] by the name of the node they're on.
+    pub named_meshes: HashMap<String, Handle<B3DMesh<M>>>,
+    /// A material per brush, or none if [`B3DLoaderSettings::load_materials`] is off.
+    pub materials: Vec<Handle<M>>,
+    /// [`Self::materials`] by brush name.
+    pub named_materials: HashMap<String, Handle<M>>,
+    /// Every node in depth-first order, starting with the root.
+    pub nodes: Vec<Handle<B3DNode<M>>>,
+    /// [`Self::nodes`] by name, holding the first of nodes sharing a name.
+    pub named_nodes: HashMap<String, Handle<B3DNode<M>>>,
+    /// The colliders generated for nodes with geometry, see
+    /// [`B3DLoaderSettings::colliders`]. The entity of each node also holds its collider.
+    #[cfg(feature = "colliders")]
+    pub colliders: Vec<Handle<B3DCollider>>,
+    /// [`Self::colliders`] by node name.
+    #[cfg(feature = "colliders")]
+    pub named_colliders: HashMap<String, Handle<B3DCollider>>,
+    /// The parsed file, for the details the conversion drops such as brush `fx` flags and
+    /// sequences. Only kept when [`B3DLoaderSettings::include_source`] is set.
+    pub source: Option<b3d::B3D>,
 }
 
 /// A b3d node with all of its child nodes, its [`B3DMesh`] and [`Transform`]
-#[derive(Asset, Debug, TypePath)]
-pub struct B3DNode {
-    pub children: Vec<B3DNode>,
-    pub mesh: Option<Handle<B3DMesh>>,
+#[derive(Asset, Debug, Clone, TypePath)]
+pub struct B3DNode<M: B3DMaterial = StandardMaterial> {
+    pub name: String,
+    pub children: Vec<B3DNode<M>>,
+    pub mesh: Option<Handle<B3DMesh<M>>>,
     pub transform: Transform,
 }
 
-/// A b3d mesh, which may contists of a [`Mesh`] and an optional [`StandardMaterial`].
+/// A b3d mesh, made of one [`B3DPrimitive`] per brush its triangles use.
 #[derive(Asset, Debug, TypePath)]
-pub struct B3DMesh {
+pub struct B3DMesh<M: B3DMaterial = StandardMaterial> {
+    pub primitives: Vec<B3DPrimitive<M>>,
+}
+
+/// The triangles of a [`B3DMesh`] sharing a brush, as a [`Mesh`] and an optional material.
+#[derive(Debug, Clone)]
+pub struct B3DPrimitive<M: B3DMaterial = StandardMaterial> {
     pub mesh: Handle<Mesh>,
-    pub material: Option<Handle<StandardMaterial>>,
+    pub material: Option<Handle<M>>,
+    /// The brush's lightmap, a multiplied texture layer sampled with the second uv set.
+    pub lightmap: Option<Handle<Image>>,
+}
+
+/// The brush a mesh entity of a b3d scene is drawn with, for games that tag geometry through
+/// brush names or fx flags.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct B3DMaterialInfo {
+    pub brush_name: String,
+    /// The brush's fx flags, such as 1 for fullbright and 16 for double-sided.
+    pub fx_flags: u32,
+    /// The brush's blend mode, 1 for alpha, 2 for multiply and 3 for add.
+    pub blend: u32,
+    /// The files of the textures the brush draws with, from the first stage to the last.
+    pub texture_files: Vec<String>,
+}
+
+/// Labels that can be used to load parts of a b3d file, such as
+/// `asset_server.load(B3DAssetLabel::Animation("walk".into()).from_asset("model.b3d"))`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum B3DAssetLabel {
+    /// `Scene`: the whole file as a [`Scene`].
+    Scene,
+    /// `Scene{index}`: a child of the root node and its descendants as a [`Scene`], by the
+    /// index of the child.
+    NodeScene(usize),
+    /// `Node{index}`: a [`B3DNode`], numbering nodes in depth-first order from the root.
+    Node(usize),
+    /// `Mesh{index}`: a [`B3DMesh`], numbering nodes with geometry in depth-first order.
+    Mesh(usize),
+    /// `Mesh{mesh}/Primitive{primitive}`: the [`Mesh`] of one brush of a [`B3DMesh`].
+    Primitive { mesh: usize, primitive: usize },
+    /// `Mesh{index}/InverseBindposes`: the inverse bindposes of a skinned [`B3DMesh`].
+    InverseBindposes(usize),
+    /// `Texture{index}`: an [`Image`], by its index in the `TEXS` chunk.
+    Texture(usize),
+    /// `Material{index}`: a material, by the index of its brush in the `BRUS` chunk.
+    Material(usize),
+    /// `DefaultMaterial`: the material of primitives without a brush.
+    DefaultMaterial,
+    /// `Animation/{name}`: an [`AnimationClip`], by the name of its sequence. Files with keys but
+    /// no sequences get a single clip from frame 0 to their last key, labeled
+    /// `Animation/Animation0`.
+    Animation(String),
+    /// `AnimationGraph`: the [`AnimationGraph`] holding every clip.
+    AnimationGraph,
+    /// `Mesh{index}/Collider`: the [`B3DCollider`] of a node with geometry.
+    #[cfg(feature = "colliders")]
+    Collider(usize),
+}
+
+impl std::fmt::Display for B3DAssetLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            B3DAssetLabel::Scene => f.write_str("Scene"),
+            B3DAssetLabel::NodeScene(index) => write!(f, "Scene{}", index),
+            B3DAssetLabel::Node(index) => write!(f, "Node{}", index),
+            B3DAssetLabel::Mesh(index) => write!(f, "Mesh{}", index),
+            B3DAssetLabel::Primitive { mesh, primitive } => {
+                write!(f, "Mesh{}/Primitive{}", mesh, primitive)
+            }
+            B3DAssetLabel::InverseBindposes(mesh) => write!(f, "Mesh{}/InverseBindposes", mesh),
+            B3DAssetLabel::Texture(index) => write!(f, "Texture{}", index),
+            B3DAssetLabel::Material(index) => write!(f, "Material{}", index),
+            B3DAssetLabel::DefaultMaterial => f.write_str("DefaultMaterial"),
+            B3DAssetLabel::Animation(name) => write!(f, "Animation/{}", name),
+            B3DAssetLabel::AnimationGraph => f.write_str("AnimationGraph"),
+            #[cfg(feature = "colliders")]
+            B3DAssetLabel::Collider(mesh) => write!(f, "Mesh{}/Collider", mesh),
+        }
+    }
+}
+
+impl B3DAssetLabel {
+    /// Returns the path of this part of the b3d file at `path`.
+    pub fn from_asset(&self, path: impl Into<AssetPath<'static>>) -> AssetPath<'static> {
+        path.into().with_label(self.to_string())
+    }
 }
@@ -1,19 +1,44 @@
 use anyhow::Result;
+#[cfg(all(feature = "multi_threaded", not(target_arch = "wasm32")))]
+use bevy::tasks::AsyncComputeTaskPool;
 use bevy::{
+    animation::{AnimationTarget, AnimationTargetId, Interpolation, Keyframes, VariableCurve},
     asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, ReadAssetBytesError},
+    math::Affine2,
+    pbr::Lightmap,
     prelude::*,
     render::{
-        mesh::Indices,
+        camera::Exposure,
+        mesh::{
+            skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
+            Indices, VertexAttributeValues,
+        },
         render_asset::RenderAssetUsages,
-        render_resource::PrimitiveTopology,
+        render_resource::{Extent3d, Face, PrimitiveTopology, TextureDimension, TextureFormat},
         renderer::RenderDevice,
-        texture::{CompressedImageFormats, ImageSampler, ImageType, TextureError},
+        texture::{
+            CompressedImageFormats, ImageAddressMode, ImageFilterMode, ImageFormat, ImageSampler,
+            ImageSamplerDescriptor, ImageType, TextureError,
+        },
     },
+    tasks::futures_lite::StreamExt,
+    utils::{HashMap, HashSet, Instant},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    marker::PhantomData,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
-use std::path::Path;
 use thiserror::Error;
 
-use crate::B3D;
+#[cfg(feature = "colliders")]
+use crate::collider::{load_collider, matches_pattern, B3DCollider, B3DColliderShape};
+use crate::diagnostics::{LoadStats, LoadStatsQueue};
+use crate::env_map::{environment_map, B3DProbeMaps, B3DReflectionProbe};
+use crate::processor::read_processed;
+use crate::{B3DAssetLabel, B3DEnvironmentMap, B3DMaterial, B3DMaterialInfo, B3D};
 
 /// An error that occurs when loading a b3d file.
 #[non_exhaustive]
@@ -21,35 +46,198 @@ use crate::B3D;
 pub enum B3DError {
     #[error(transparent)]
     B3D(#[from] b3d::Error),
+    /// Failed to parse a b3d file, while reading the chunk described by `chunk`.
+    #[error("failed to parse {} in {chunk}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        /// The path of chunks down to the one being read and its offset, such as
+        /// `BB3D > NODE "ROOT" > MESH > VRTS at offset 1234`.
+        chunk: String,
+        #[source]
+        source: b3d::Error,
+    },
     /// Error when loading a texture. Might be due to a disabled image file format feature.
     #[error("You may need to add the feature for the file format: {0}")]
     ImageError(#[from] TextureError),
+    /// Failed to decode the texture at `path`. Might be due to a disabled image file format
+    /// feature.
+    #[error("failed to decode texture {}, you may need to add the feature for its file format: {source}", path.display())]
+    Texture {
+        path: PathBuf,
+        #[source]
+        source: TextureError,
+    },
     /// Failed to read bytes from an asset path.
     #[error("failed to read bytes from an asset path: {0}")]
     ReadAssetBytesError(#[from] ReadAssetBytesError),
     /// Failed to load a file.
     #[error("failed to load file: {0}")]
     Io(#[from] std::io::Error),
+    /// A file written by [`B3DGeometryCleanup`](crate::B3DGeometryCleanup) is cut short or
+    /// otherwise malformed.
+    #[error("invalid processed b3d file: {0}")]
+    Processed(&'static str),
 }
 
-pub struct B3DLoader {
+/// Settings for loading a b3d file, set with
+/// [`AssetServer::load_with_settings`](bevy::asset::AssetServer::load_with_settings).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct B3DLoaderSettings {
+    /// Inserts an [`AnimationPlayer`] and the file's [`AnimationGraph`] on the root of the scene,
+    /// so its clips can be played as soon as it's spawned.
+    pub add_animation_player: bool,
+    /// Reads node rotations as `x, y, z` Euler angles in radians instead of `w, x, y, z`
+    /// quaternions, for files written by tools that store them that way.
+    pub legacy_euler_rotations: bool,
+    /// Converts from Blitz3D's left-handed space into bevy's right-handed one by flipping the Z
+    /// axis of positions, normals, rotations and keys and reversing the winding of triangles.
+    /// Without it models come in mirrored.
+    pub convert_coordinates: bool,
+    /// Uniform factor applied to node positions, vertex positions and position keys, for
+    /// models whose units aren't meters. Skins and colliders are built from the scaled
+    /// positions, so they follow.
+    pub scale: f32,
+    /// Loads the textures and builds a material per brush. Otherwise no texture is read and
+    /// every primitive gets the default material, which suits headless servers and tests that
+    /// only need geometry and have no render device to pick compressed formats for.
+    pub load_materials: bool,
+    /// Builds animation clips out of the `KEYS` chunks.
+    pub load_animations: bool,
+    /// The frame rate clips are played at instead of the one of the `ANIM` chunk, for files
+    /// that have a wrong or zero one.
+    pub fps: Option<f32>,
+    /// Generates tangents for every mesh with texture coordinates, needed for normal maps. It's
+    /// the slowest part of building meshes, so levels without normal maps are better off
+    /// without it.
+    pub generate_tangents: bool,
+    /// Gives meshes normals, read from the file or computed if it has none. Without them meshes
+    /// can only be lit flatly or drawn unlit, and get no tangents.
+    pub load_normals: bool,
+    /// Reads the second set of texture coordinates, which lightmaps are sampled with. Brushes
+    /// lose their lightmaps without it.
+    pub load_secondary_uvs: bool,
+    /// Flips texture coordinates vertically, turning `v` into `1 - v`, for files written by
+    /// tools whose `v` points up. Blitz3D's points down the texture like bevy's, so it's off by
+    /// default.
+    pub flip_v: bool,
+    /// Gives meshes without normals faceted ones, splitting their vertices so each triangle
+    /// gets its own, instead of smooth ones averaged over the triangles sharing each vertex.
+    pub flat_normals: bool,
+    /// Where meshes and textures are kept once loaded. [`RenderAssetUsages::RENDER_WORLD`]
+    /// alone frees the CPU copies once they're uploaded, while tools that read the geometry
+    /// back, such as for picking or colliders built at runtime, need
+    /// [`RenderAssetUsages::MAIN_WORLD`].
+    pub asset_usages: RenderAssetUsages,
+    /// The sampler textures start from. Its address modes are replaced by those of each
+    /// texture's clamp flags.
+    pub sampler: ImageSamplerDescriptor,
+    /// Filters textures without the mipmap flag (8) nearest, keeping the blocky look low
+    /// resolution textures were drawn with.
+    pub nearest_unmipmapped: bool,
+    /// Filters every texture nearest and never samples their mipmaps, whatever their flags.
+    pub pixel_art: bool,
+    /// The alpha below which masked (4) textures are cut out. Their black texels are made
+    /// transparent, as Blitz3D does.
+    pub mask_cutoff: f32,
+    /// How metallic brushes with an environment mapped (64 or 128) texture are made, standing
+    /// in for the reflections Blitz3D drew with it. Their primitives get a
+    /// [`B3DEnvironmentMap`] describing the map.
+    pub env_map_metallic: f32,
+    /// Reflected by the primitives with an environment mapped texture instead of the scene's
+    /// environment.
+    pub reflection_probe: Option<B3DReflectionProbe>,
+    /// Keeps the parsed file as [`B3D::source`].
+    pub include_source: bool,
+    /// Looks for texture files that don't exist as written under names differing only in case,
+    /// or with one of [`Self::texture_extensions`] instead.
+    pub resolve_texture_paths: bool,
+    /// Extensions tried in order when resolving texture paths, without the dot.
+    pub texture_extensions: Vec<String>,
+    /// Drops the directories of texture files, which often point into the machine the model was
+    /// exported on, and only keeps their names.
+    pub strip_texture_directories: bool,
+    /// Directories relative to the asset root to look for textures in when they aren't next to
+    /// the model.
+    pub texture_search_directories: Vec<PathBuf>,
+    /// Shares one material between brushes differing only by name, and one mesh between nodes
+    /// with identical static geometry, so repeated props can be batched. Off by default, as
+    /// shared assets are only labeled by the index of their first brush or mesh: the
+    /// `Material{i}`, `Mesh{i}` and `Mesh{i}/Primitive{j}` labels of the others don't exist.
+    pub deduplicate: bool,
+    /// The collider generated for every node with geometry, or `None` to only generate them for
+    /// the nodes matching [`Self::collision_node_pattern`].
+    #[cfg(feature = "colliders")]
+    pub colliders: Option<B3DColliderShape>,
+    /// Nodes whose names match this pattern, where `*` stands for any run of characters and
+    /// case is ignored, are only there for collision: they get a collider, a trimesh unless
+    /// [`Self::colliders`] says otherwise, and their meshes are hidden.
+    #[cfg(feature = "colliders")]
+    pub collision_node_pattern: Option<String>,
+}
+
+impl Default for B3DLoaderSettings {
+    fn default() -> Self {
+        Self {
+            add_animation_player: false,
+            legacy_euler_rotations: false,
+            convert_coordinates: true,
+            scale: 1.0,
+            load_materials: true,
+            load_animations: true,
+            fps: None,
+            generate_tangents: true,
+            load_normals: true,
+            load_secondary_uvs: true,
+            flip_v: false,
+            flat_normals: false,
+            asset_usages: RenderAssetUsages::default(),
+            sampler: ImageSamplerDescriptor::linear(),
+            nearest_unmipmapped: false,
+            pixel_art: false,
+            mask_cutoff: 0.5,
+            env_map_metallic: 0.5,
+            reflection_probe: None,
+            include_source: false,
+            resolve_texture_paths: true,
+            texture_extensions: ["png", "jpg", "jpeg", "bmp", "tga", "dds"]
+                .map(String::from)
+                .to_vec(),
+            strip_texture_directories: true,
+            texture_search_directories: vec![],
+            deduplicate: false,
+            #[cfg(feature = "colliders")]
+            colliders: None,
+            #[cfg(feature = "colliders")]
+            collision_node_pattern: Some("*_coll".to_string()),
+        }
+    }
+}
+
+/// Loads b3d files as [`B3D`] assets, with materials of type `M`.
+pub struct B3DLoader<M = StandardMaterial> {
     pub(crate) supported_compressed_formats: CompressedImageFormats,
+    /// Used to list directories when resolving texture paths.
+    pub(crate) asset_server: AssetServer,
+    /// Where loads are reported, if [`B3DDiagnosticsPlugin`](crate::B3DDiagnosticsPlugin) was
+    /// added.
+    pub(crate) diagnostics: Option<LoadStatsQueue>,
+    pub(crate) marker: PhantomData<fn() -> M>,
 }
 
-impl AssetLoader for B3DLoader {
-    type Asset = B3D;
-    type Settings = ();
+impl<M: B3DMaterial> AssetLoader for B3DLoader<M> {
+    type Asset = B3D<M>;
+    type Settings = B3DLoaderSettings;
     type Error = B3DError;
 
     async fn load<'a>(
         &'a self,
         reader: &'a mut Reader<'_>,
-        _settings: &'a (),
+        settings: &'a B3DLoaderSettings,
         load_context: &'a mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        load_b3d(self, &bytes, load_context).await
+        load_b3d(self, &bytes, load_context, settings).await
     }
 
     fn extensions(&self) -> &[&str] {
@@ -57,7 +245,7 @@ impl AssetLoader for B3DLoader {
     }
 }
 
-impl FromWorld for B3DLoader {
+impl<M> FromWorld for B3DLoader<M> {
     fn from_world(world: &mut World) -> Self {
         let supported_compressed_formats = match world.get_resource::<RenderDevice>() {
             Some(render_device) => CompressedImageFormats::from_features(render_device.features()),
@@ -65,125 +253,582 @@ impl FromWorld for B3DLoader {
         };
         Self {
             supported_compressed_formats,
+            asset_server: world.resource::<AssetServer>().clone(),
+            diagnostics: world.get_resource::<LoadStatsQueue>().cloned(),
+            marker: PhantomData,
         }
     }
 }
 
 /// Loads an entire b3d file.
-async fn load_b3d<'a, 'b>(
-    loader: &B3DLoader,
+async fn load_b3d<'a, 'b, M: B3DMaterial>(
+    loader: &B3DLoader<M>,
     bytes: &'a [u8],
     load_context: &'a mut LoadContext<'b>,
-) -> Result<B3D, B3DError> {
-    let b3d = b3d::B3D::read(bytes)?;
-
-    let mut materials = vec![];
-    for (texture_index, texture) in b3d.textures.into_iter().enumerate() {
-        if let Ok(texture) = load_texture(
-            &texture,
-            load_context,
-            loader.supported_compressed_formats,
-            RenderAssetUsages::default(),
-        )
-        .await
-        {
-            let texture_handle =
-                load_context.add_labeled_asset(format!("Texture{}", texture_index), texture);
+    settings: &'a B3DLoaderSettings,
+) -> Result<B3D<M>, B3DError> {
+    let parse_start = Instant::now();
+    // Files written by `B3DGeometryCleanup` carry their primitives already built.
+    let (bytes, mut prebuilt) = match read_processed(bytes, settings)? {
+        Some((bytes, primitives)) => (bytes, primitives),
+        None => (bytes, vec![]),
+    };
+    let b3d = parse(bytes, load_context.path())?;
+    let parse_time = parse_start.elapsed();
 
-            let handle = load_context.add_labeled_asset(
-                format!("Material{}", texture_index),
-                StandardMaterial {
-                    base_color_texture: Some(texture_handle),
-                    ..Default::default()
-                },
+    let texture_start = Instant::now();
+
+    let mut textures = vec![];
+    let b3d_textures = if settings.load_materials {
+        &b3d.textures[..]
+    } else {
+        &[]
+    };
+    // Files are read one at a time, as reading them through the load context, which makes them
+    // dependencies of the model, borrows it mutably. Each is decoded, the slow part, on the
+    // compute task pool while the next ones are read.
+    let mut decodes = vec![];
+    for texture in b3d_textures {
+        let read = read_texture(texture, loader, load_context, settings).await;
+        let supported_compressed_formats = loader.supported_compressed_formats;
+        let sampler = texture_sampler(texture, settings);
+        let asset_usages = settings.asset_usages;
+        let masked = texture.flags & 4 != 0;
+        let decode = move || {
+            let (bytes, path) = read?;
+            decode_texture(
+                &bytes,
+                path,
+                supported_compressed_formats,
+                sampler,
+                asset_usages,
+                masked,
+            )
+        };
+        #[cfg(all(feature = "multi_threaded", not(target_arch = "wasm32")))]
+        decodes.push(AsyncComputeTaskPool::get().spawn(async move { decode() }));
+        // Tasks can't be awaited without threads, so decode in place.
+        #[cfg(not(all(feature = "multi_threaded", not(target_arch = "wasm32"))))]
+        decodes.push(decode());
+    }
+    let mut images = Vec::with_capacity(decodes.len());
+    for decode in decodes {
+        #[cfg(all(feature = "multi_threaded", not(target_arch = "wasm32")))]
+        images.push(decode.await);
+        #[cfg(not(all(feature = "multi_threaded", not(target_arch = "wasm32"))))]
+        images.push(decode);
+    }
+    for (texture_index, (texture, image)) in b3d_textures.iter().zip(images).enumerate() {
+        let image = image.unwrap_or_else(|err| {
+            warn!(
+                "Failed to load texture {:?} of {}, using a placeholder: {}",
+                texture.file.as_str(),
+                load_context.path().display(),
+                err
             );
-            materials.push(handle);
-        }
+            placeholder_texture(texture, settings)
+        });
+        let handle = load_context
+            .add_labeled_asset(B3DAssetLabel::Texture(texture_index).to_string(), image);
+        textures.push(Some(handle));
     }
 
-    info!("Mesh key_flags: {:#?}", b3d.node.key_flags);
+    let texture_time = texture_start.elapsed();
 
-    let mut meshes = vec![];
-    let (mesh, mesh_label) = load_mesh(&b3d.node.mesh, 0)?;
-    let mesh_handle = load_context.add_labeled_asset(mesh_label, mesh);
-    let mat_handle = load_context.get_label_handle("Material0");
-    let bmesh_handle = load_context.add_labeled_asset(
-        "B3DMesh0".to_owned(),
-        crate::B3DMesh {
-            mesh: mesh_handle,
-            material: Some(mat_handle),
-        },
-    );
-    meshes.push(bmesh_handle);
+    let mut materials: Vec<Handle<M>> = vec![];
+    let mut named_materials = HashMap::new();
+    let mut lightmaps: Vec<Option<Handle<Image>>> = vec![];
+    let brushes = if settings.load_materials {
+        &b3d.brushes[..]
+    } else {
+        &[]
+    };
+    let mut unique_brushes: HashMap<Vec<u32>, usize> = HashMap::new();
+    for (brush_index, brush) in brushes.iter().enumerate() {
+        if settings.deduplicate {
+            if let Some(&original) = unique_brushes.get(&brush_key(brush)) {
+                let handle = materials[original].clone();
+                named_materials.insert(brush.name.to_string(), handle.clone());
+                materials.push(handle);
+                lightmaps.push(lightmaps[original].clone());
+                continue;
+            }
+            unique_brushes.insert(brush_key(brush), brush_index);
+        }
+        let layers = brush.resolve_layers(&b3d.textures);
+        let material = M::from_brush(brush, &layers, &textures, settings);
+        let handle = load_context
+            .add_labeled_asset(B3DAssetLabel::Material(brush_index).to_string(), material);
+        named_materials.insert(brush.name.to_string(), handle.clone());
+        materials.push(handle);
+        lightmaps.push(
+            layers
+                .iter()
+                .find(|layer| is_lightmap(layer))
+                .and_then(|layer| texture_handle(layer, &textures)),
+        );
+    }
 
-    let nodes = vec![];
+    // One clip per `SEQS` chunk, or a single one from frame 0 to the last key.
+    let mut animations = vec![];
+    let mut named_animations = HashMap::new();
+    if let Some(animation) = b3d.animation().filter(|_| settings.load_animations) {
+        for b3d_clip in b3d.clips() {
+            let clip = load_animation(
+                &b3d.node,
+                b3d_clip.frames,
+                animation_fps(animation, settings),
+                settings,
+            );
+            let label = B3DAssetLabel::Animation(b3d_clip.name.clone());
+            let handle = load_context.add_labeled_asset(label.to_string(), clip);
+            named_animations.insert(b3d_clip.name, handle.clone());
+            animations.push(handle);
+        }
+    }
 
-    let scene = {
-        let mut err = None;
-        let mut world = World::default();
-        let mut scene_load_context = load_context.begin_labeled_asset();
+    // Every clip hangs off the root of one graph.
+    let mut animation_graph = None;
+    let mut named_animation_nodes = HashMap::new();
+    if !animations.is_empty() {
+        let mut graph = AnimationGraph::new();
+        for (b3d_clip, clip) in b3d.clips().into_iter().zip(&animations) {
+            let node = graph.add_clip(clip.clone(), 1.0, graph.root);
+            named_animation_nodes.insert(b3d_clip.name, node);
+        }
+        animation_graph =
+            Some(load_context.add_labeled_asset(B3DAssetLabel::AnimationGraph.to_string(), graph));
+    }
 
-        world
-            .spawn(SpatialBundle::INHERITED_IDENTITY)
-            .with_children(|parent| {
-                let result = load_node(&b3d.node, parent, &mut scene_load_context);
-                if result.is_err() {
-                    err = Some(result)
-                }
+    // Every node with geometry gets its own mesh, numbered in depth-first order, made of one
+    // primitive per brush.
+    let transforms: Vec<Transform> = b3d
+        .node
+        .iter()
+        .map(|node| node_transform(node, settings))
+        .collect();
+    let mesh_start = Instant::now();
+    let rest_globals = rest_globals(&b3d.node, &transforms);
+    let mut meshes: Vec<Handle<crate::B3DMesh<M>>> = vec![];
+    let mut named_meshes = HashMap::new();
+    let mut mesh_handles: Vec<Vec<_>> = vec![];
+    let mut skins = vec![];
+    let mut unique_meshes: HashMap<Vec<u32>, usize> = HashMap::new();
+    let mut default_material = None;
+    let probe_maps = settings
+        .reflection_probe
+        .as_ref()
+        .map(|probe| B3DProbeMaps {
+            diffuse_map: load_context.load(probe.diffuse_map.clone()),
+            specular_map: load_context.load(probe.specular_map.clone()),
+            intensity: probe.intensity,
+        });
+    for (index, (node_index, node)) in b3d
+        .node
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| has_geometry(node))
+        .enumerate()
+    {
+        let skin = load_skin(node, node_index, &rest_globals);
+        // Skinned meshes depend on the bones beneath them too, so only static ones are shared.
+        let key = (settings.deduplicate && skin.is_none()).then(|| mesh_key(&node.mesh));
+        if let Some(&original) = key.as_ref().and_then(|key| unique_meshes.get(key)) {
+            let handle = meshes[original].clone();
+            named_meshes.insert(node.name.to_string(), handle.clone());
+            meshes.push(handle);
+            mesh_handles.push(mesh_handles[original].clone());
+            skins.push(None);
+            continue;
+        }
+        if let Some(key) = key {
+            unique_meshes.insert(key, index);
+        }
+        let mut primitives = vec![];
+        let mut handles = vec![];
+        let mut built = prebuilt
+            .get_mut(index)
+            .map(std::mem::take)
+            .unwrap_or_default()
+            .into_iter();
+        for (primitive_index, (brush_id, indices)) in
+            brush_groups(&node.mesh).into_iter().enumerate()
+        {
+            let mesh = built
+                .next()
+                .unwrap_or_else(|| load_primitive(&node.mesh, indices, skin.as_ref(), settings));
+            let mesh_handle =
+                load_context.add_labeled_asset(primitive_label(index, primitive_index), mesh);
+            let material = brush_id
+                .get()
+                .and_then(|brush| materials.get(brush).cloned());
+            // Lightmaps are sampled with the second uv set.
+            let lightmap = brush_id
+                .get()
+                .and_then(|brush| lightmaps.get(brush).cloned().flatten())
+                .filter(|_| node.mesh.vertices.tex_coord_sets >= 2 && settings.load_secondary_uvs);
+            let material_handle = material.clone().unwrap_or_else(|| {
+                default_material
+                    .get_or_insert_with(|| {
+                        load_context.add_labeled_asset(
+                            B3DAssetLabel::DefaultMaterial.to_string(),
+                            M::default(),
+                        )
+                    })
+                    .clone()
             });
-        if let Some(Err(err)) = err {
-            return Err(err);
+            let brush = brush_id.get().and_then(|brush| b3d.brushes.get(brush));
+            let info = brush.map(|brush| material_info(brush, &b3d.textures));
+            let env_map = brush.and_then(|brush| {
+                environment_map(
+                    &brush.resolve_layers(&b3d.textures),
+                    &textures,
+                    probe_maps.as_ref(),
+                )
+            });
+            let primitive = crate::B3DPrimitive {
+                mesh: mesh_handle,
+                material,
+                lightmap,
+            };
+            primitives.push(primitive.clone());
+            handles.push((primitive, material_handle, info, env_map));
         }
+        let bmesh_handle = load_context.add_labeled_asset(
+            B3DAssetLabel::Mesh(index).to_string(),
+            crate::B3DMesh { primitives },
+        );
+        named_meshes.insert(node.name.to_string(), bmesh_handle.clone());
+        meshes.push(bmesh_handle);
+        mesh_handles.push(handles);
+        skins.push(skin.map(|skin| {
+            let inverse_bindposes = load_context.add_labeled_asset(
+                B3DAssetLabel::InverseBindposes(index).to_string(),
+                SkinnedMeshInverseBindposes::from(skin.inverse_bindposes),
+            );
+            (inverse_bindposes, skin.joints)
+        }));
+    }
+
+    let mesh_time = mesh_start.elapsed();
 
-        let loaded_scene = scene_load_context.finish(Scene::new(world), None);
-        load_context.add_loaded_labeled_asset("Scene", loaded_scene)
+    if let Some(queue) = &loader.diagnostics {
+        queue.0.lock().unwrap().push_back(LoadStats {
+            parse_time,
+            texture_time,
+            mesh_time,
+            meshes: meshes.len(),
+            materials: materials.len(),
+            animations: animations.len(),
+        });
+    }
+
+    // Colliders are attached to the entities of their nodes once the scene is spawned.
+    #[cfg(feature = "colliders")]
+    let mut colliders = vec![];
+    #[cfg(feature = "colliders")]
+    let mut named_colliders = HashMap::new();
+    #[cfg(feature = "colliders")]
+    let mut node_colliders = vec![];
+    #[cfg(feature = "colliders")]
+    for (index, (node_index, node)) in b3d
+        .node
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| has_geometry(node))
+        .enumerate()
+    {
+        let collision_only = settings
+            .collision_node_pattern
+            .as_ref()
+            .is_some_and(|pattern| matches_pattern(&node.name, pattern));
+        let shape = match settings.colliders {
+            Some(shape) => shape,
+            None if collision_only => B3DColliderShape::TriMesh,
+            None => continue,
+        };
+        let handle = load_context.add_labeled_asset(
+            B3DAssetLabel::Collider(index).to_string(),
+            load_collider(&node.mesh, shape, settings),
+        );
+        named_colliders.insert(node.name.to_string(), handle.clone());
+        colliders.push(handle.clone());
+        node_colliders.push((node_index, index, handle, collision_only));
+    }
+
+    // Every node becomes an asset holding its whole subtree, labeled by its depth-first index.
+    let mut nodes = vec![];
+    let mut named_nodes = HashMap::new();
+    let root = load_b3d_node(&b3d.node, &transforms, &meshes, &mut 0, &mut 0);
+    let mut stack = vec![&root];
+    while let Some(node) = stack.pop() {
+        let label = B3DAssetLabel::Node(nodes.len());
+        let handle = load_context.add_labeled_asset(label.to_string(), node.clone());
+        named_nodes
+            .entry(node.name.clone())
+            .or_insert_with(|| handle.clone());
+        nodes.push(handle);
+        stack.extend(node.children.iter().rev());
+    }
+
+    // The whole file, then every child of the root on its own, so files holding several props
+    // can be spawned piecewise.
+    let parts = SceneParts {
+        transforms: &transforms,
+        mesh_handles: &mesh_handles,
+        skins: &skins,
+        target_ids: (!animations.is_empty()).then(|| animation_target_ids(&b3d.node)),
+        animation_graph: animation_graph
+            .as_ref()
+            .filter(|_| settings.add_animation_player),
+        #[cfg(feature = "colliders")]
+        node_colliders: &node_colliders,
     };
+    let scene = load_context.add_labeled_asset(
+        B3DAssetLabel::Scene.to_string(),
+        Scene::new(load_scene(&b3d.node, 0, 0, &parts)?),
+    );
+    let mut scenes = vec![];
+    let mut named_scenes = HashMap::new();
+    let mut first_node = 1;
+    let mut first_mesh = usize::from(has_geometry(&b3d.node));
+    for (index, child) in b3d.node.children.iter().enumerate() {
+        let world = load_scene(child, first_node, first_mesh, &parts)?;
+        let label = B3DAssetLabel::NodeScene(index);
+        let handle = load_context.add_labeled_asset(label.to_string(), Scene::new(world));
+        named_scenes
+            .entry(child.name.to_string())
+            .or_insert_with(|| handle.clone());
+        scenes.push(handle);
+        first_node += child.iter().count();
+        first_mesh += child.iter().filter(|node| has_geometry(node)).count();
+    }
 
     Ok(B3D {
         scene,
+        scenes,
+        named_scenes,
+        animations,
+        named_animations,
+        animation_graph,
+        named_animation_nodes,
         materials,
+        named_materials,
         nodes,
+        named_nodes,
         meshes,
+        named_meshes,
+        #[cfg(feature = "colliders")]
+        colliders,
+        #[cfg(feature = "colliders")]
+        named_colliders,
+        source: settings.include_source.then_some(b3d),
     })
 }
 
-/// Loads a b3d node.
-fn load_node(
+/// What the scenes of a file are spawned out of, indexed like the whole file.
+struct SceneParts<'a, M: B3DMaterial> {
+    transforms: &'a [Transform],
+    mesh_handles: &'a [Vec<SpawnedPrimitive<M>>],
+    skins: &'a [Option<(Handle<SkinnedMeshInverseBindposes>, Vec<usize>)>],
+    /// The id every node is targeted by, if the file has clips.
+    target_ids: Option<Vec<AnimationTargetId>>,
+    animation_graph: Option<&'a Handle<AnimationGraph>>,
+    #[cfg(feature = "colliders")]
+    node_colliders: &'a [(usize, usize, Handle<B3DCollider>, bool)],
+}
+
+/// Spawns `b3d_node` and its descendants under a new root entity, `first_node` and `first_mesh`
+/// being the depth-first indices of the node and of the first mesh beneath it in the file.
+fn load_scene<M: B3DMaterial>(
+    b3d_node: &b3d::Node,
+    first_node: usize,
+    first_mesh: usize,
+    parts: &SceneParts<M>,
+) -> Result<World, B3DError> {
+    let mut err = None;
+    let mut world = World::default();
+    let mut next_mesh = first_mesh;
+    let mut node_entities = vec![];
+    let mut primitive_entities = vec![];
+
+    let root = world
+        .spawn(SpatialBundle::INHERITED_IDENTITY)
+        .with_children(|parent| {
+            let result = load_node(
+                b3d_node,
+                parent,
+                &parts.transforms[first_node..],
+                parts.mesh_handles,
+                &mut next_mesh,
+                &mut node_entities,
+                &mut primitive_entities,
+            );
+            if result.is_err() {
+                err = Some(result)
+            }
+        })
+        .id();
+    if let Some(Err(err)) = err {
+        return Err(err);
+    }
+    // The entity of the node at a depth-first index of the file, if it's in this scene.
+    let node_entity = |index: usize| {
+        index
+            .checked_sub(first_node)
+            .and_then(|index| node_entities.get(index))
+            .copied()
+    };
+
+    // Clips are played by the root, targeting nodes by their path of names like the curves do.
+    if let Some(target_ids) = &parts.target_ids {
+        for (&entity, &id) in node_entities.iter().zip(&target_ids[first_node..]) {
+            world
+                .entity_mut(entity)
+                .insert(AnimationTarget { id, player: root });
+        }
+    }
+    if let Some(graph) = parts.animation_graph {
+        world
+            .entity_mut(root)
+            .insert((AnimationPlayer::default(), graph.clone()));
+    }
+
+    #[cfg(feature = "colliders")]
+    for (node_index, mesh_index, handle, collision_only) in parts.node_colliders {
+        let Some(entity) = node_entity(*node_index) else {
+            continue;
+        };
+        world.entity_mut(entity).insert(handle.clone());
+        if *collision_only {
+            for &entity in &primitive_entities[mesh_index - first_mesh] {
+                world.entity_mut(entity).insert(Visibility::Hidden);
+            }
+        }
+    }
+
+    // Joints can only be bound once every node has an entity. Skins with joints outside the
+    // scene are left unbound.
+    for (skin, entities) in parts.skins[first_mesh..].iter().zip(&primitive_entities) {
+        let Some((inverse_bindposes, joints)) = skin else {
+            continue;
+        };
+        let Some(joints) = joints
+            .iter()
+            .map(|&joint| node_entity(joint))
+            .collect::<Option<Vec<Entity>>>()
+        else {
+            continue;
+        };
+        for &entity in entities {
+            world.entity_mut(entity).insert(SkinnedMesh {
+                inverse_bindposes: inverse_bindposes.clone(),
+                joints: joints.clone(),
+            });
+        }
+    }
+
+    Ok(world)
+}
+
+/// Builds the [`B3DNode`](crate::B3DNode) of `b3d_node`, taking the transforms and meshes of it
+/// and its descendants from `transforms` and `meshes` in depth-first order, starting at
+/// `next_node` and `next_mesh`.
+fn load_b3d_node<M: B3DMaterial>(
+    b3d_node: &b3d::Node,
+    transforms: &[Transform],
+    meshes: &[Handle<crate::B3DMesh<M>>],
+    next_node: &mut usize,
+    next_mesh: &mut usize,
+) -> crate::B3DNode<M> {
+    let transform = transforms[*next_node];
+    *next_node += 1;
+    let mesh = has_geometry(b3d_node).then(|| {
+        *next_mesh += 1;
+        meshes[*next_mesh - 1].clone()
+    });
+    let children = b3d_node
+        .children
+        .iter()
+        .map(|child| load_b3d_node(child, transforms, meshes, next_node, next_mesh))
+        .collect();
+    crate::B3DNode {
+        name: b3d_node.name.to_string(),
+        children,
+        mesh,
+        transform,
+    }
+}
+
+/// A primitive with the material its entity is spawned with and the brush it came from.
+type SpawnedPrimitive<M> = (
+    crate::B3DPrimitive<M>,
+    Handle<M>,
+    Option<B3DMaterialInfo>,
+    Option<B3DEnvironmentMap>,
+);
+
+/// Loads a b3d node, taking its transform from `transforms` and the primitives of every node
+/// with geometry from `mesh_handles`, both in depth-first order. The entities spawned for nodes
+/// and for the primitives of each mesh are recorded in the same order. Nodes without geometry
+/// or bones get an entity too, so gameplay code can parent things to pivots.
+fn load_node<M: B3DMaterial>(
     b3d_node: &b3d::Node,
     world_builder: &mut WorldChildBuilder,
-    load_context: &mut LoadContext<'_>,
+    transforms: &[Transform],
+    mesh_handles: &[Vec<SpawnedPrimitive<M>>],
+    next_mesh: &mut usize,
+    node_entities: &mut Vec<Entity>,
+    primitive_entities: &mut Vec<Vec<Entity>>,
 ) -> Result<(), B3DError> {
-    let transform = Transform {
-        translation: b3d_node.position.into(),
-        rotation: Quat::from_euler(
-            EulerRot::XYZ,
-            b3d_node.rotation[0],
-            b3d_node.rotation[1],
-            b3d_node.rotation[2],
-        ),
-        scale: b3d_node.scale.into(),
-    };
     let mut b3d_error = None;
+    let transform = transforms[node_entities.len()];
     let mut node = world_builder.spawn(SpatialBundle::from(transform));
+    node_entities.push(node.id());
 
     node.insert(node_name(b3d_node));
 
     node.with_children(|parent| {
-        // let mesh = &b3d_node.mesh;
-
-        let mesh_label = mesh_label(0);
-
-        let mut mesh_entity = parent.spawn(PbrBundle {
-            mesh: load_context.get_label_handle(mesh_label.to_owned()),
-            material: load_context.get_label_handle("Material0"),
-            ..Default::default()
-        });
-
-        mesh_entity.insert(Name::new(mesh_label));
+        if has_geometry(b3d_node) {
+            let mut entities = vec![];
+            for (primitive_index, (primitive, material, info, env_map)) in
+                mesh_handles[*next_mesh].iter().enumerate()
+            {
+                let mut primitive_entity = parent.spawn(MaterialMeshBundle::<M> {
+                    mesh: primitive.mesh.clone(),
+                    material: material.clone(),
+                    ..Default::default()
+                });
+                primitive_entity.insert(Name::new(primitive_label(*next_mesh, primitive_index)));
+                if let Some(info) = info {
+                    primitive_entity.insert(info.clone());
+                }
+                if let Some(env_map) = env_map {
+                    primitive_entity.insert(env_map.clone());
+                }
+                if let Some(image) = &primitive.lightmap {
+                    primitive_entity.insert(Lightmap {
+                        image: image.clone(),
+                        uv_rect: Rect::new(0.0, 0.0, 1.0, 1.0),
+                    });
+                }
+                entities.push(primitive_entity.id());
+            }
+            primitive_entities.push(entities);
+            *next_mesh += 1;
+        }
 
         // append other nodes
         for child in &b3d_node.children {
-            if let Err(err) = load_node(child, parent, load_context) {
+            if let Err(err) = load_node(
+                child,
+                parent,
+                transforms,
+                mesh_handles,
+                next_mesh,
+                node_entities,
+                primitive_entities,
+            ) {
                 b3d_error = Some(err);
                 return;
             }
@@ -197,99 +842,918 @@ fn load_node(
     }
 }
 
-fn load_mesh(b3d_mesh: &b3d::Mesh, index: u32) -> Result<(Mesh, String), B3DError> {
-    let mut mesh = Mesh::new(
-        PrimitiveTopology::TriangleList,
-        RenderAssetUsages::default(),
-    );
+/// Builds a clip out of the keys of `b3d_node` and its descendants between `frames`, played at
+/// `fps`. Curves target nodes by the path of names from the root, the way scenes name them.
+pub(crate) fn load_animation(
+    b3d_node: &b3d::Node,
+    frames: RangeInclusive<u32>,
+    fps: f32,
+    settings: &B3DLoaderSettings,
+) -> AnimationClip {
+    let mut clip = AnimationClip::default();
+    let mut path = vec![];
+    add_node_curves(&mut clip, b3d_node, &mut path, &frames, fps, settings);
+    clip.set_duration(frames.end().saturating_sub(*frames.start()) as f32 / fps);
+    clip
+}
 
-    if let Some(vertex_attribute) = b3d_mesh
-        .vertices
-        .vertices
+/// Returns the frame rate clips are played at: [`B3DLoaderSettings::fps`] if set, otherwise
+/// the one of the `ANIM` chunk or Blitz3D's default if it has none.
+pub(crate) fn animation_fps(animation: &b3d::Animation, settings: &B3DLoaderSettings) -> f32 {
+    settings
+        .fps
+        .filter(|&fps| fps > 0.0)
+        .unwrap_or_else(|| animation.fps_or_default())
+}
+
+/// Returns the id every node is targeted by in clips, in depth-first order.
+fn animation_target_ids(root: &b3d::Node) -> Vec<AnimationTargetId> {
+    fn visit(node: &b3d::Node, path: &mut Vec<Name>, ids: &mut Vec<AnimationTargetId>) {
+        path.push(node_name(node));
+        ids.push(AnimationTargetId::from_names(path.iter()));
+        for child in &node.children {
+            visit(child, path, ids);
+        }
+        path.pop();
+    }
+    let mut ids = vec![];
+    visit(root, &mut vec![], &mut ids);
+    ids
+}
+
+fn add_node_curves(
+    clip: &mut AnimationClip,
+    b3d_node: &b3d::Node,
+    path: &mut Vec<Name>,
+    frames: &RangeInclusive<u32>,
+    fps: f32,
+    settings: &B3DLoaderSettings,
+) {
+    path.push(node_name(b3d_node));
+    let target = AnimationTargetId::from_names(path.iter());
+    for keys in &b3d_node.keys {
+        let keys = keys_in_range(keys, frames);
+        let keyframe_timestamps: Vec<f32> = keys
+            .keys
+            .iter()
+            .map(|key| key.frame.saturating_sub(*frames.start()) as f32 / fps)
+            .collect();
+        for keyframes in keyframes(&keys, settings) {
+            clip.add_curve_to_target(
+                target,
+                VariableCurve {
+                    keyframe_timestamps: keyframe_timestamps.clone(),
+                    keyframes,
+                    interpolation: Interpolation::Linear,
+                },
+            );
+        }
+    }
+    for child in &b3d_node.children {
+        add_node_curves(clip, child, path, frames, fps, settings);
+    }
+    path.pop();
+}
+
+/// Returns the keys between `frames`, with keys sampled at either end if there are none there
+/// so the clip holds the pose it starts and ends on.
+fn keys_in_range(keys: &b3d::Keys, frames: &RangeInclusive<u32>) -> b3d::Keys {
+    let mut in_range: Vec<b3d::Key> = keys
+        .keys
         .iter()
-        .map(|v| v.position)
-        .collect::<Vec<_>>()
-        .into()
-    {
-        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertex_attribute);
+        .filter(|key| frames.contains(&key.frame))
+        .cloned()
+        .collect();
+    if !keys.keys.is_empty() {
+        if in_range.first().map(|key| key.frame) != Some(*frames.start()) {
+            in_range.extend(keys.sample(*frames.start() as f32));
+            in_range.rotate_right(1);
+        }
+        if in_range.last().map(|key| key.frame) != Some(*frames.end()) {
+            in_range.extend(keys.sample(*frames.end() as f32));
+        }
     }
+    b3d::Keys {
+        flags: keys.flags,
+        keys: in_range,
+    }
+}
 
-    if let Some(vertex_attribute) = b3d_mesh
-        .vertices
-        .vertices
+/// Converts the channels of a `KEYS` chunk selected by its flags into keyframes.
+fn keyframes(keys: &b3d::Keys, settings: &B3DLoaderSettings) -> Vec<Keyframes> {
+    if keys.keys.is_empty() {
+        return vec![];
+    }
+    let mut keyframes = vec![];
+    if keys.flags & 1 != 0 {
+        keyframes.push(Keyframes::Translation(
+            keys.keys
+                .iter()
+                .map(|key| position(key.position, settings))
+                .collect(),
+        ));
+    }
+    if keys.flags & 2 != 0 {
+        keyframes.push(Keyframes::Scale(
+            keys.keys.iter().map(|key| key.scale.into()).collect(),
+        ));
+    }
+    if keys.flags & 4 != 0 {
+        keyframes.push(Keyframes::Rotation(
+            keys.keys
+                .iter()
+                .map(|key| rotation(key.rotation, settings))
+                .collect(),
+        ));
+    }
+    keyframes
+}
+
+pub(crate) fn node_transform(b3d_node: &b3d::Node, settings: &B3DLoaderSettings) -> Transform {
+    let rotation = if settings.legacy_euler_rotations {
+        let rotation = Quat::from_euler(
+            EulerRot::XYZ,
+            b3d_node.rotation[0],
+            b3d_node.rotation[1],
+            b3d_node.rotation[2],
+        );
+        if settings.convert_coordinates {
+            // Mirroring the Z axis reverses rotations about X and Y.
+            Quat::from_xyzw(-rotation.x, -rotation.y, rotation.z, rotation.w)
+        } else {
+            rotation
+        }
+    } else {
+        rotation(b3d_node.rotation, settings)
+    };
+    Transform {
+        translation: position(b3d_node.position, settings),
+        rotation,
+        scale: b3d_node.scale.into(),
+    }
+}
+
+/// Converts a b3d position, scaling it and flipping its Z axis if coordinates are converted.
+pub(crate) fn position(position: [f32; 3], settings: &B3DLoaderSettings) -> Vec3 {
+    direction(position, settings) * settings.scale
+}
+
+/// Converts a b3d direction or normal, flipping its Z axis if coordinates are converted.
+fn direction(direction: [f32; 3], settings: &B3DLoaderSettings) -> Vec3 {
+    if settings.convert_coordinates {
+        b3d::coords::to_right_handed(direction).into()
+    } else {
+        direction.into()
+    }
+}
+
+/// Converts a b3d `w, x, y, z` rotation, mirroring it if coordinates are converted.
+fn rotation(rotation: [f32; 4], settings: &B3DLoaderSettings) -> Quat {
+    if settings.convert_coordinates {
+        Quat::from_array(b3d::coords::rotation_to_right_handed(rotation))
+    } else {
+        Quat::from_array(b3d::coords::rotation(rotation))
+    }
+}
+
+/// Returns the rest transform of every node relative to the root, in depth-first order, given
+/// the local `transforms` in the same order.
+fn rest_globals(root: &b3d::Node, transforms: &[Transform]) -> Vec<Mat4> {
+    fn visit(node: &b3d::Node, parent: Mat4, transforms: &[Transform], globals: &mut Vec<Mat4>) {
+        let global = parent * transforms[globals.len()].compute_matrix();
+        globals.push(global);
+        for child in &node.children {
+            visit(child, global, transforms, globals);
+        }
+    }
+    let mut globals = vec![];
+    visit(root, Mat4::IDENTITY, transforms, &mut globals);
+    globals
+}
+
+/// The joints skinning a mesh, and the influence of each on its vertices.
+pub(crate) struct Skin {
+    /// Depth-first indices of the joint nodes.
+    joints: Vec<usize>,
+    inverse_bindposes: Vec<Mat4>,
+    joint_indices: Vec<[u16; 4]>,
+    joint_weights: Vec<[f32; 4]>,
+}
+
+/// Builds the skin of the mesh on the node at depth-first index `node_index` out of the bones
+/// beneath it, stopping at nested meshes. Returns `None` if no bone has weights for it.
+///
+/// Each vertex keeps its four strongest weights. Vertices without any are bound to the mesh's
+/// own node, so they follow it as they do in Blitz3D.
+fn load_skin(node: &b3d::Node, node_index: usize, rest_globals: &[Mat4]) -> Option<Skin> {
+    let mut joints = vec![];
+    let mut next_index = node_index + 1;
+    collect_joints(node, &mut next_index, &mut joints);
+    if joints.is_empty() {
+        return None;
+    }
+
+    let vertex_count = node.mesh.vertices.vertices.len();
+    let mut influences: Vec<Vec<(u16, f32)>> = vec![vec![]; vertex_count];
+    for (joint, (_, bone)) in joints.iter().enumerate() {
+        for weight in &bone.bones {
+            if let Some(influence) = influences.get_mut(weight.vertex_id as usize) {
+                influence.push((joint as u16, weight.weight));
+            }
+        }
+    }
+    let mut joints: Vec<usize> = joints.into_iter().map(|(index, _)| index).collect();
+    let own_joint = joints.len() as u16;
+    let mut bound_to_node = false;
+
+    let mut joint_indices = vec![];
+    let mut joint_weights = vec![];
+    for mut influence in influences {
+        influence.sort_by(|a, b| b.1.total_cmp(&a.1));
+        influence.truncate(4);
+        let total: f32 = influence.iter().map(|(_, weight)| weight).sum();
+        let mut indices = [0; 4];
+        let mut weights = [0.0; 4];
+        if total > 0.0 {
+            for (i, (joint, weight)) in influence.into_iter().enumerate() {
+                indices[i] = joint;
+                weights[i] = weight / total;
+            }
+        } else {
+            bound_to_node = true;
+            indices[0] = own_joint;
+            weights[0] = 1.0;
+        }
+        joint_indices.push(indices);
+        joint_weights.push(weights);
+    }
+    if bound_to_node {
+        joints.push(node_index);
+    }
+
+    let mesh_global = rest_globals[node_index];
+    let inverse_bindposes = joints
         .iter()
-        .map(|v| v.normal)
-        .collect::<Vec<_>>()
-        .into()
-    {
-        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vertex_attribute);
+        .map(|&joint| rest_globals[joint].inverse() * mesh_global)
+        .collect();
+    Some(Skin {
+        joints,
+        inverse_bindposes,
+        joint_indices,
+        joint_weights,
+    })
+}
+
+/// Collects the descendants of `node` with bone weights along with their depth-first indices,
+/// counting from `next_index`. Nested meshes and their descendants are skipped.
+fn collect_joints<'a>(
+    node: &'a b3d::Node,
+    next_index: &mut usize,
+    joints: &mut Vec<(usize, &'a b3d::Node)>,
+) {
+    for child in &node.children {
+        let index = *next_index;
+        if has_geometry(child) {
+            *next_index += child.iter().count();
+            continue;
+        }
+        *next_index += 1;
+        if !child.bones.is_empty() {
+            joints.push((index, child));
+        }
+        collect_joints(child, next_index, joints);
     }
+}
 
-    if let Some(vertex_attribute) = b3d_mesh
-        .vertices
-        .vertices
+/// Turns `v` into `1 - v` if [`B3DLoaderSettings::flip_v`] asks for it.
+fn flip_v(uvs: Vec<[f32; 2]>, settings: &B3DLoaderSettings) -> Vec<[f32; 2]> {
+    if !settings.flip_v {
+        return uvs;
+    }
+    uvs.into_iter().map(|[u, v]| [u, 1.0 - v]).collect()
+}
+
+/// Builds the primitives of every mesh the way [`load_b3d`] does, in the same order, leaving
+/// the list of a mesh empty if it's shared with an earlier one.
+pub(crate) fn build_primitives(b3d: &b3d::B3D, settings: &B3DLoaderSettings) -> Vec<Vec<Mesh>> {
+    let transforms: Vec<Transform> = b3d
+        .node
         .iter()
-        .map(|v| v.tex_coords)
-        .collect::<Vec<_>>()
-        .into()
+        .map(|node| node_transform(node, settings))
+        .collect();
+    let rest_globals = rest_globals(&b3d.node, &transforms);
+    let mut unique_meshes = HashSet::new();
+    b3d.node
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| has_geometry(node))
+        .map(|(node_index, node)| {
+            let skin = load_skin(node, node_index, &rest_globals);
+            let key = (settings.deduplicate && skin.is_none()).then(|| mesh_key(&node.mesh));
+            if key.is_some_and(|key| !unique_meshes.insert(key)) {
+                return vec![];
+            }
+            brush_groups(&node.mesh)
+                .into_iter()
+                .map(|(_, indices)| load_primitive(&node.mesh, indices, skin.as_ref(), settings))
+                .collect()
+        })
+        .collect()
+}
+
+/// Builds the primitive drawing `indices` out of the vertices of `b3d_mesh` they use, bound to
+/// `skin` if it has one. Triangles with a corner past the last vertex are dropped.
+pub(crate) fn load_primitive(
+    b3d_mesh: &b3d::Mesh,
+    indices: Vec<u32>,
+    skin: Option<&Skin>,
+    settings: &B3DLoaderSettings,
+) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, settings.asset_usages);
+
+    // Every primitive of a mesh shares its vertices, so keep only the ones this one uses, in the
+    // order they're first used.
+    let vertex_count = b3d_mesh.vertices.vertices.len();
+    let mut remap = vec![u32::MAX; vertex_count];
+    let mut used: Vec<usize> = vec![];
+    let indices: Vec<u32> = indices
+        .chunks_exact(3)
+        .filter(|triangle| {
+            triangle
+                .iter()
+                .all(|&index| (index as usize) < vertex_count)
+        })
+        .flatten()
+        .map(|&index| {
+            let new_index = &mut remap[index as usize];
+            if *new_index == u32::MAX {
+                *new_index = used.len() as u32;
+                used.push(index as usize);
+            }
+            *new_index
+        })
+        .collect();
+
+    let positions: Vec<Vec3> = select(&b3d_mesh.vertices.positions(), &used)
+        .into_iter()
+        .map(|vertex| position(vertex, settings))
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+
+    if let Some(normals) = b3d_mesh
+        .vertices
+        .normals()
+        .filter(|_| settings.load_normals)
     {
-        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vertex_attribute);
+        let normals: Vec<Vec3> = select(&normals, &used)
+            .into_iter()
+            .map(|normal| direction(normal, settings))
+            .collect();
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
     }
 
-    if let Some(vertex_attribute) = b3d_mesh
-        .triangles
-        .iter()
-        .flat_map(|tri| tri.indices.iter())
-        .flat_map(|face| face.iter().copied())
-        .collect::<Vec<_>>()
-        .into()
+    // Blitz3D vertex colors are sRGB, the attribute is linear. StandardMaterial multiplies the
+    // base color by them whenever the mesh has them.
+    if let Some(colors) = b3d_mesh.vertices.colors() {
+        let colors: Vec<[f32; 4]> = select(&colors, &used)
+            .into_iter()
+            .map(|[red, green, blue, alpha]| {
+                Color::srgba(red, green, blue, alpha)
+                    .to_linear()
+                    .to_f32_array()
+            })
+            .collect();
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    }
+
+    if let Some(uvs) = b3d_mesh.vertices.uv_set(0) {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, flip_v(select(&uvs, &used), settings));
+    }
+
+    if let Some(uvs) = b3d_mesh
+        .vertices
+        .uv_set(1)
+        .filter(|_| settings.load_secondary_uvs)
     {
-        mesh.insert_indices(Indices::U32(vertex_attribute));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, flip_v(select(&uvs, &used), settings));
     }
 
-    if let Err(err) = mesh.generate_tangents() {
-        warn!(
-            "Failed to generate vertex tangents using the mikktspace algorithm: {:?}",
-            err
+    let indices = if settings.convert_coordinates {
+        indices
+            .chunks_exact(3)
+            .flat_map(|triangle| {
+                b3d::coords::triangle_to_right_handed([triangle[0], triangle[1], triangle[2]])
+            })
+            .collect()
+    } else {
+        indices
+    };
+    mesh.insert_indices(Indices::U32(indices));
+
+    if let Some(skin) = skin {
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_JOINT_INDEX,
+            VertexAttributeValues::Uint16x4(select(&skin.joint_indices, &used)),
         );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_JOINT_WEIGHT,
+            select(&skin.joint_weights, &used),
+        );
+    }
+
+    // Flat normals need a vertex per triangle corner, so the joints have to be in by now.
+    if !settings.load_normals {
+        // Tangents are built from the normals, so there are none either.
+        return mesh;
+    }
+    if !b3d_mesh.vertices.has_normals() {
+        if settings.flat_normals {
+            mesh.duplicate_vertices();
+            mesh.compute_flat_normals();
+        } else {
+            mesh.compute_smooth_normals();
+        }
+    }
+
+    // Meshes without texture coordinates can't have tangents, so they're skipped quietly.
+    if settings.generate_tangents && mesh.contains_attribute(Mesh::ATTRIBUTE_UV_0) {
+        if let Err(err) = mesh.generate_tangents() {
+            warn!(
+                "Failed to generate vertex tangents using the mikktspace algorithm: {:?}",
+                err
+            );
+        }
+    }
+
+    mesh
+}
+
+/// Picks the `values` of the vertices at `indices`.
+fn select<T: Copy>(values: &[T], indices: &[usize]) -> Vec<T> {
+    indices.iter().map(|&index| values[index]).collect()
+}
+
+impl B3DMaterial for StandardMaterial {
+    fn from_brush(
+        brush: &b3d::Brush,
+        layers: &[b3d::TextureLayer],
+        textures: &[Option<Handle<Image>>],
+        settings: &B3DLoaderSettings,
+    ) -> Self {
+        load_material(brush, layers, textures, settings)
+    }
+}
+
+/// Builds the material for a brush, using its first texture layer that isn't a lightmap if that
+/// texture loaded.
+pub(crate) fn load_material(
+    brush: &b3d::Brush,
+    layers: &[b3d::TextureLayer],
+    textures: &[Option<Handle<Image>>],
+    settings: &B3DLoaderSettings,
+) -> StandardMaterial {
+    let [red, green, blue, alpha] = brush.color;
+    // Environment maps aren't mapped by uv, so they're left to `env_map_metallic`.
+    let layer = layers
+        .iter()
+        .find(|layer| !is_lightmap(layer) && layer.env_map.is_none());
+    let env_mapped = layers.iter().any(|layer| layer.env_map.is_some());
+    let base_color_texture = layer.and_then(|layer| texture_handle(layer, textures));
+    let has_lightmap = layers.iter().any(|layer| is_lightmap(&layer));
+    let shininess = brush.shininess.clamp(0.0, 1.0);
+
+    StandardMaterial {
+        base_color: Color::srgba(red, green, blue, alpha),
+        base_color_texture,
+        // Blitz3D brushes have no specular highlight unless given some shininess, while
+        // environment maps reflect sharply whatever it is.
+        perceptual_roughness: if env_mapped {
+            (1.0 - shininess).min(0.2)
+        } else {
+            1.0 - shininess
+        },
+        reflectance: if env_mapped { 1.0 } else { 0.5 * shininess },
+        metallic: if env_mapped {
+            settings.env_map_metallic
+        } else {
+            0.0
+        },
+        alpha_mode: alpha_mode(brush, layer, settings.mask_cutoff),
+        uv_transform: layer.map_or(Affine2::IDENTITY, |layer| uv_transform(layer.texture)),
+        // Cancel out the default camera exposure, so a white lightmap texel leaves the surface
+        // as bright as Blitz3D's multiply blend does.
+        lightmap_exposure: if has_lightmap {
+            Exposure::default().exposure().recip()
+        } else {
+            1.0
+        },
+        // Fullbright brushes (fx flag 1) ignore lighting.
+        unlit: brush.fx & 1 != 0,
+        // Fx flag 16 disables backface culling.
+        double_sided: brush.fx & 16 != 0,
+        cull_mode: (brush.fx & 16 == 0).then_some(Face::Back),
+        ..Default::default()
+    }
+}
+
+/// Describes the brush for gameplay code, see [`B3DMaterialInfo`].
+fn material_info(brush: &b3d::Brush, textures: &[b3d::Texture]) -> B3DMaterialInfo {
+    B3DMaterialInfo {
+        brush_name: brush.name.to_string(),
+        fx_flags: brush.fx,
+        blend: brush.blend,
+        texture_files: brush
+            .resolve_layers(textures)
+            .iter()
+            .map(|layer| layer.texture.file.to_string())
+            .collect(),
+    }
+}
+
+/// Returns whether the layer is a lightmap, multiplied in with the second uv set.
+pub(crate) fn is_lightmap(layer: &&b3d::TextureLayer) -> bool {
+    layer.uses_secondary_uv && layer.blend == b3d::TextureBlend::Multiply
+}
+
+pub(crate) fn texture_handle(
+    layer: &b3d::TextureLayer,
+    textures: &[Option<Handle<Image>>],
+) -> Option<Handle<Image>> {
+    textures.get(layer.texture_id.get()?).cloned().flatten()
+}
+
+/// Converts a texture's position, scale and rotation into a uv transform, the same way the
+/// glTF export maps them onto `KHR_texture_transform`.
+pub(crate) fn uv_transform(texture: &b3d::Texture) -> Affine2 {
+    let scale = Vec2::from(texture.scale.map(|s| if s != 0.0 { 1.0 / s } else { 1.0 }));
+    let translation = -Vec2::from(texture.position);
+    Affine2::from_scale_angle_translation(scale, -texture.rotation.to_radians(), translation)
+}
+
+/// Picks the alpha mode matching the brush's blend mode, alpha and fx flags, and the flags of
+/// the texture it draws with. Masked textures are cut off below `mask_cutoff`.
+fn alpha_mode(
+    brush: &b3d::Brush,
+    layer: Option<&b3d::TextureLayer>,
+    mask_cutoff: f32,
+) -> AlphaMode {
+    let texture_flags = layer.map_or(0, |layer| layer.texture.flags);
+    match brush.blend {
+        2 => AlphaMode::Multiply,
+        3 => AlphaMode::Add,
+        _ if texture_flags & 4 != 0 => AlphaMode::Mask(mask_cutoff),
+        _ if brush.color[3] < 1.0 || brush.fx & 32 != 0 || texture_flags & 2 != 0 => {
+            AlphaMode::Blend
+        }
+        _ => AlphaMode::Opaque,
     }
+}
 
-    Ok((mesh, mesh_label(index)))
+/// Parses the b3d file at `path`, telling which chunk it was reading if it fails.
+pub(crate) fn parse(bytes: &[u8], path: &Path) -> Result<b3d::B3D, B3DError> {
+    // The chunks enclosing the last one read and itself, as `(tag, position, next)`.
+    let open_chunks = Arc::new(Mutex::new(Vec::<(b3d::Tag, u64, u64)>::new()));
+    let options = b3d::ParseOptions::default().with_progress({
+        let open_chunks = open_chunks.clone();
+        move |_, _, chunk| {
+            let mut open_chunks = open_chunks.lock().unwrap();
+            while open_chunks
+                .last()
+                .is_some_and(|&(_, _, next)| next <= chunk.position)
+            {
+                open_chunks.pop();
+            }
+            open_chunks.push((chunk.tag, chunk.position, chunk.next));
+        }
+    });
+    b3d::B3D::read_with_options(bytes, &options).map_err(|source| B3DError::Parse {
+        path: path.to_owned(),
+        chunk: chunk_path(bytes, &open_chunks.lock().unwrap()),
+        source,
+    })
 }
 
-/// Loads a b3d texture as a bevy [`Image`] and returns it together with its label.
-async fn load_texture<'a>(
+/// Describes the last of `open_chunks` by the chunks it's nested in, naming nodes.
+fn chunk_path(bytes: &[u8], open_chunks: &[(b3d::Tag, u64, u64)]) -> String {
+    let Some(&(_, position, _)) = open_chunks.last() else {
+        return "the file header".to_string();
+    };
+    let labels: Vec<String> = open_chunks
+        .iter()
+        .map(|&(tag, position, _)| {
+            if tag != b3d::Tag::NODE {
+                return tag.to_string();
+            }
+            // The name is the first field of a node, right after its header.
+            let name = bytes
+                .get(position as usize + 8..)
+                .and_then(|name| name.split(|&byte| byte == 0).next())
+                .unwrap_or_default();
+            format!("NODE {:?}", String::from_utf8_lossy(name))
+        })
+        .collect();
+    format!("{} at offset {}", labels.join(" > "), position)
+}
+
+/// Reads the image file of a b3d texture, returning its bytes and the path it was found at.
+async fn read_texture<'a>(
     b3d_texture: &b3d::Texture,
+    loader: &B3DLoader<impl B3DMaterial>,
     load_context: &mut LoadContext<'a>,
+    settings: &B3DLoaderSettings,
+) -> Result<(Vec<u8>, PathBuf), B3DError> {
+    let file = texture_file(&b3d_texture.file, settings);
+    let mut directories = vec![load_context.path().parent().unwrap().to_owned()];
+    directories.extend(settings.texture_search_directories.iter().cloned());
+
+    // Look in each directory in turn, reporting the error from the first if none has the file.
+    // Bytes read through the load context make the file a loader dependency, so editing a
+    // texture reloads the whole model when watching for changes. Missing textures aren't
+    // tracked, so adding one later needs the model itself to be reloaded.
+    let mut found = None;
+    let mut first_err = None;
+    for directory in directories {
+        let image_path = directory.join(&file);
+        match load_context.read_asset_bytes(image_path.clone()).await {
+            Ok(bytes) => {
+                found = Some((bytes, image_path));
+                break;
+            }
+            Err(err) => {
+                first_err.get_or_insert(err);
+            }
+        }
+        if !settings.resolve_texture_paths {
+            continue;
+        }
+        if let Some(resolved) =
+            resolve_texture_path(&image_path, loader, load_context, settings).await
+        {
+            let bytes = load_context.read_asset_bytes(resolved.clone()).await?;
+            found = Some((bytes, resolved));
+            break;
+        }
+    }
+    let Some((bytes, image_path)) = found else {
+        return Err(first_err.unwrap().into());
+    };
+
+    Ok((bytes, image_path))
+}
+
+/// Decodes the image file read from `path`, making its black texels transparent if it's
+/// `masked`.
+pub(crate) fn decode_texture(
+    bytes: &[u8],
+    path: PathBuf,
     supported_compressed_formats: CompressedImageFormats,
-    render_asset_usages: RenderAssetUsages,
+    sampler: ImageSampler,
+    asset_usages: RenderAssetUsages,
+    masked: bool,
 ) -> Result<Image, B3DError> {
-    let parent = load_context.path().parent().unwrap();
-    let image_path = parent.join(&b3d_texture.file);
-    let bytes = load_context.read_asset_bytes(image_path.clone()).await?;
-
-    let extension = Path::new(&b3d_texture.file)
-        .extension()
-        .unwrap()
-        .to_str()
-        .unwrap();
-    let image_type = ImageType::Extension(extension);
-
-    Ok(Image::from_buffer(
-        &bytes,
-        image_type,
+    let mut image = Image::from_buffer(
+        bytes,
+        image_type(&path, bytes),
         supported_compressed_formats,
         true,
-        ImageSampler::Default,
-        render_asset_usages,
-    )?)
+        sampler,
+        asset_usages,
+    )
+    .map_err(|source| B3DError::Texture { path, source })?;
+    if masked {
+        mask_black(&mut image);
+    }
+    Ok(image)
+}
+
+/// Makes the black texels of a masked (4) texture transparent, the way Blitz3D masks them.
+/// Compressed textures are left as they are.
+pub(crate) fn mask_black(image: &mut Image) {
+    if image.texture_descriptor.format != TextureFormat::Rgba8UnormSrgb {
+        let Some(mut converted) = image.convert(TextureFormat::Rgba8UnormSrgb) else {
+            return;
+        };
+        converted.sampler = image.sampler.clone();
+        *image = converted;
+    }
+    for texel in image.data.chunks_exact_mut(4) {
+        if texel[..3] == [0, 0, 0] {
+            texel[3] = 0;
+        }
+    }
+}
+
+/// Picks the format of an image from its first bytes, which catches files with a missing or
+/// wrong extension, or from its extension for formats without a signature such as TGA.
+pub(crate) fn image_type<'a>(path: &'a Path, bytes: &[u8]) -> ImageType<'a> {
+    let format = match bytes {
+        [0x89, b'P', b'N', b'G', ..] => ImageFormat::Png,
+        [0xff, 0xd8, 0xff, ..] => ImageFormat::Jpeg,
+        [b'B', b'M', ..] => ImageFormat::Bmp,
+        [b'D', b'D', b'S', b' ', ..] => ImageFormat::Dds,
+        [b'G', b'I', b'F', b'8', ..] => ImageFormat::Gif,
+        _ => {
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            return ImageType::Extension(extension.unwrap_or_default());
+        }
+    };
+    ImageType::Format(format)
+}
+
+/// Turns the file of a texture into a relative path with `/` separators, dropping any drive
+/// letter. With [`B3DLoaderSettings::strip_texture_directories`] only the file name is kept, the
+/// way Blitz3D looks for textures next to the model.
+fn texture_file(file: &str, settings: &B3DLoaderSettings) -> PathBuf {
+    let file = file.replace('\\', "/");
+    let file = match file.split_once(':') {
+        Some((drive, rest)) if drive.len() == 1 => rest,
+        _ => &file,
+    };
+    let file = file.trim_start_matches('/');
+    if settings.strip_texture_directories {
+        PathBuf::from(file.rsplit('/').next().unwrap_or(file))
+    } else {
+        PathBuf::from(file)
+    }
+}
+
+/// Looks through the directory of `path` for a file with the same name in any case, or with one
+/// of the extensions in `settings` instead, preferring the earliest of them.
+async fn resolve_texture_path(
+    path: &Path,
+    loader: &B3DLoader<impl B3DMaterial>,
+    load_context: &LoadContext<'_>,
+    settings: &B3DLoaderSettings,
+) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?.to_lowercase();
+    let stem = path.file_stem()?.to_str()?.to_lowercase();
+    let source = loader
+        .asset_server
+        .get_source(load_context.asset_path().source().clone())
+        .ok()?;
+    let mut entries = source.reader().read_directory(path.parent()?).await.ok()?;
+
+    let mut best: Option<(usize, PathBuf)> = None;
+    while let Some(entry) = entries.next().await {
+        let Some(name) = entry.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let name = name.to_lowercase();
+        let rank = if name == file_name {
+            0
+        } else if let Some(index) = settings
+            .texture_extensions
+            .iter()
+            .position(|extension| name == format!("{}.{}", stem, extension.to_lowercase()))
+        {
+            index + 1
+        } else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|(best, _)| rank < *best) {
+            best = Some((rank, entry));
+        }
+    }
+    best.map(|(_, path)| path)
+}
+
+/// Builds a magenta and black checkerboard to stand in for a texture that failed to load.
+fn placeholder_texture(b3d_texture: &b3d::Texture, settings: &B3DLoaderSettings) -> Image {
+    const SIZE: u32 = 8;
+    let data = (0..SIZE * SIZE)
+        .flat_map(|texel| {
+            if (texel % SIZE + texel / SIZE).is_multiple_of(2) {
+                [255, 0, 255, 255]
+            } else {
+                [0, 0, 0, 255]
+            }
+        })
+        .collect();
+    let mut image = Image::new(
+        Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        settings.asset_usages,
+    );
+    image.sampler = sampler(b3d_texture, &ImageSamplerDescriptor::nearest());
+    image
+}
+
+/// Builds the sampler for a texture out of [`B3DLoaderSettings::sampler`], filtering it nearest
+/// when the settings ask for it.
+pub(crate) fn texture_sampler(
+    b3d_texture: &b3d::Texture,
+    settings: &B3DLoaderSettings,
+) -> ImageSampler {
+    let nearest =
+        settings.pixel_art || (settings.nearest_unmipmapped && b3d_texture.flags & 8 == 0);
+    if nearest {
+        sampler(
+            b3d_texture,
+            &ImageSamplerDescriptor {
+                mag_filter: ImageFilterMode::Nearest,
+                min_filter: ImageFilterMode::Nearest,
+                mipmap_filter: ImageFilterMode::Nearest,
+                lod_max_clamp: if settings.pixel_art {
+                    0.0
+                } else {
+                    settings.sampler.lod_max_clamp
+                },
+                ..settings.sampler.clone()
+            },
+        )
+    } else {
+        sampler(b3d_texture, &settings.sampler)
+    }
+}
+
+/// Builds the sampler for a texture out of `base`, repeating unless the texture's clamp-U (16)
+/// or clamp-V (32) flags are set. Textures without the mipmap flag (8) only sample their full
+/// resolution level, as Blitz3D draws them.
+fn sampler(b3d_texture: &b3d::Texture, base: &ImageSamplerDescriptor) -> ImageSampler {
+    let address_mode = |clamp: bool| {
+        if clamp {
+            ImageAddressMode::ClampToEdge
+        } else {
+            ImageAddressMode::Repeat
+        }
+    };
+    ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: address_mode(b3d_texture.flags & 16 != 0),
+        address_mode_v: address_mode(b3d_texture.flags & 32 != 0),
+        lod_max_clamp: if b3d_texture.flags & 8 != 0 {
+            base.lod_max_clamp
+        } else {
+            0.0
+        },
+        ..base.clone()
+    })
+}
+
+/// Returns what makes the material of a brush, so that brushes differing only by name can share
+/// one.
+fn brush_key(brush: &b3d::Brush) -> Vec<u32> {
+    let mut key: Vec<u32> = brush.color.iter().map(|c| c.to_bits()).collect();
+    key.extend([brush.shininess.to_bits(), brush.blend, brush.fx]);
+    key.extend(brush.texture_id.iter().map(|id| id.raw()));
+    key
+}
+
+/// Returns the content of a mesh, so that nodes with identical geometry can share one.
+fn mesh_key(mesh: &b3d::Mesh) -> Vec<u32> {
+    let vertices = &mesh.vertices;
+    let mut key = vec![
+        mesh.brush_id.raw(),
+        vertices.flags,
+        vertices.tex_coord_sets,
+        vertices.vertices.len() as u32,
+    ];
+    for vertex in &vertices.vertices {
+        let floats = vertex
+            .position
+            .iter()
+            .chain(&vertex.normal)
+            .chain(&vertex.color)
+            .chain(vertex.tex_coords.iter().flatten());
+        key.extend(floats.map(|f| f.to_bits()));
+    }
+    for tris in &mesh.triangles {
+        key.extend([tris.brush_id.raw(), tris.indices.len() as u32]);
+        key.extend(tris.indices.iter().flatten());
+    }
+    key
+}
+
+/// Returns whether the node has a mesh worth building.
+fn has_geometry(node: &b3d::Node) -> bool {
+    !node.mesh.vertices.vertices.is_empty() && !node.mesh.triangles.is_empty()
+}
+
+/// Groups the triangles of `mesh` by brush, in the order brushes are first used.
+pub(crate) fn brush_groups(mesh: &b3d::Mesh) -> Vec<(b3d::BrushId, Vec<u32>)> {
+    let mut groups: Vec<(b3d::BrushId, Vec<u32>)> = vec![];
+    for tris in &mesh.triangles {
+        let brush_id = tris.brush_id.or(mesh.brush_id);
+        let indices = tris.indices.iter().flatten().copied();
+        match groups.iter_mut().find(|(id, _)| *id == brush_id) {
+            Some((_, group)) => group.extend(indices),
+            None => groups.push((brush_id, indices.collect())),
+        }
+    }
+    groups
 }
 
-fn mesh_label(index: u32) -> String {
-    format!("Mesh{}", index)
+fn primitive_label(mesh: usize, primitive: usize) -> String {
+    B3DAssetLabel::Primitive { mesh, primitive }.to_string()
 }
 
+/// Names the entity of `node` after it, so attachment points such as empty `muzzle` or
+/// `hand_r_socket` pivots can be found by the name they have in the file.
 fn node_name(node: &b3d::Node) -> Name {
-    let name = format!("B3DNode{}", node.name);
-    Name::new(name)
+    Name::new(node.name.to_string())
 }
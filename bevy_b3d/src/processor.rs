@@ -0,0 +1,273 @@
+use bevy::asset::{
+    io::Writer,
+    meta::{AssetAction, AssetMeta},
+    processor::{Process, ProcessContext, ProcessError},
+    AsyncWriteExt,
+};
+use bevy::prelude::*;
+use bevy::render::{
+    mesh::{Indices, MeshVertexAttribute, VertexAttributeValues},
+    render_resource::{PrimitiveTopology, VertexFormat},
+};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+use crate::loader::{build_primitives, parse};
+use crate::{B3DError, B3DLoader, B3DLoaderSettings, B3DMaterial};
+
+/// Starts the files written by [`B3DGeometryCleanup`], where b3d files start with `BB3D`.
+const MAGIC: &[u8; 4] = b"B3DP";
+
+/// The primitives of each mesh of a file, in depth-first order.
+type Primitives = Vec<Vec<Mesh>>;
+
+/// Every attribute the loader builds primitives with, in the order they're written.
+const ATTRIBUTES: [MeshVertexAttribute; 8] = [
+    Mesh::ATTRIBUTE_POSITION,
+    Mesh::ATTRIBUTE_NORMAL,
+    Mesh::ATTRIBUTE_COLOR,
+    Mesh::ATTRIBUTE_UV_0,
+    Mesh::ATTRIBUTE_UV_1,
+    Mesh::ATTRIBUTE_JOINT_INDEX,
+    Mesh::ATTRIBUTE_JOINT_WEIGHT,
+    Mesh::ATTRIBUTE_TANGENT,
+];
+
+/// Settings for cleaning up the geometry of a b3d file, set in its `.meta` file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct B3DGeometryCleanupSettings {
+    /// Merges the vertices of each mesh that lie within this distance of each other and share
+    /// every other attribute, or leaves them as they are if `None`. Zero merges exact duplicates.
+    pub weld_tolerance: Option<f32>,
+    /// Removes triangles whose corners coincide.
+    pub remove_degenerate_triangles: bool,
+    /// Reorders triangles and vertices so the GPU's post-transform cache is hit more often.
+    pub optimize_vertex_cache: bool,
+    /// The settings the processed file is loaded with.
+    pub loader: B3DLoaderSettings,
+}
+
+impl Default for B3DGeometryCleanupSettings {
+    fn default() -> Self {
+        Self {
+            weld_tolerance: Some(0.0),
+            remove_degenerate_triangles: true,
+            optimize_vertex_cache: true,
+            loader: B3DLoaderSettings::default(),
+        }
+    }
+}
+
+/// An asset processor that welds, prunes and reorders the geometry of b3d files ahead of time,
+/// then builds their primitives with [`B3DGeometryCleanupSettings::loader`]: coordinates
+/// converted, scaled, with normals and tangents generated. The loader takes those primitives as
+/// they are, so only nodes, materials and animations are still read from the b3d file at
+/// runtime.
+///
+/// Registered by [`B3DPlugin`](crate::B3DPlugin), and used for every b3d file with
+/// `app.set_default_asset_processor::<B3DGeometryCleanup>("b3d")` once asset processing is
+/// enabled.
+pub struct B3DGeometryCleanup<M = StandardMaterial>(PhantomData<fn() -> M>);
+
+impl<M> Default for B3DGeometryCleanup<M> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<M: B3DMaterial> Process for B3DGeometryCleanup<M> {
+    type Settings = B3DGeometryCleanupSettings;
+    type OutputLoader = B3DLoader<M>;
+
+    async fn process<'a>(
+        &'a self,
+        context: &'a mut ProcessContext<'_>,
+        meta: AssetMeta<(), Self>,
+        writer: &'a mut Writer,
+    ) -> Result<B3DLoaderSettings, ProcessError> {
+        let AssetAction::Process { settings, .. } = meta.asset else {
+            return Err(ProcessError::WrongMetaType);
+        };
+        let mut b3d = parse(context.asset_bytes(), context.path().path())
+            .map_err(|err| ProcessError::AssetTransformError(Box::new(err)))?;
+        if let Some(tolerance) = settings.weld_tolerance {
+            b3d.weld_vertices(tolerance);
+        }
+        if settings.remove_degenerate_triangles {
+            b3d.remove_degenerate_triangles();
+        }
+        if settings.optimize_vertex_cache {
+            b3d.optimize_vertex_cache();
+        }
+        let primitives = build_primitives(&b3d, &settings.loader);
+        writer
+            .write_all(&write_processed(&b3d.to_bytes(), &primitives))
+            .await
+            .map_err(|err| ProcessError::AssetSaveError(Box::new(err)))?;
+        Ok(settings.loader)
+    }
+}
+
+/// Writes the b3d file `b3d` followed by the `primitives` of each of its meshes.
+fn write_processed(b3d: &[u8], primitives: &Primitives) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    write_len(&mut bytes, b3d.len());
+    bytes.extend_from_slice(b3d);
+    write_len(&mut bytes, primitives.len());
+    for meshes in primitives {
+        write_len(&mut bytes, meshes.len());
+        for mesh in meshes {
+            for attribute in &ATTRIBUTES {
+                match mesh.attribute(attribute.id).and_then(attribute_bytes) {
+                    Some(data) => {
+                        bytes.push(1);
+                        write_len(&mut bytes, data.len());
+                        bytes.extend(data);
+                    }
+                    None => bytes.push(0),
+                }
+            }
+            // Flat shaded primitives have a vertex per corner instead of indices.
+            match mesh.indices() {
+                Some(indices) => {
+                    bytes.push(1);
+                    write_len(&mut bytes, indices.len());
+                    for index in indices.iter() {
+                        bytes.extend((index as u32).to_le_bytes());
+                    }
+                }
+                None => bytes.push(0),
+            }
+        }
+    }
+    bytes
+}
+
+/// Splits a file written by [`B3DGeometryCleanup`] into its b3d file and the primitives of each
+/// of its meshes, or returns `None` if it's a plain b3d file.
+pub(crate) fn read_processed<'a>(
+    bytes: &'a [u8],
+    settings: &B3DLoaderSettings,
+) -> Result<Option<(&'a [u8], Primitives)>, B3DError> {
+    let Some(mut rest) = bytes.strip_prefix(MAGIC) else {
+        return Ok(None);
+    };
+    let reader = &mut rest;
+    let b3d_len = read_len(reader)?;
+    let b3d = take(reader, b3d_len)?;
+    let mut primitives = vec![];
+    for _ in 0..read_len(reader)? {
+        let mut meshes = vec![];
+        for _ in 0..read_len(reader)? {
+            let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, settings.asset_usages);
+            for attribute in ATTRIBUTES {
+                if take(reader, 1)?[0] == 0 {
+                    continue;
+                }
+                let len = read_len(reader)?;
+                let values = attribute_values(attribute.format, take(reader, len)?)?;
+                mesh.insert_attribute(attribute, values);
+            }
+            let vertex_count = mesh
+                .attribute(Mesh::ATTRIBUTE_POSITION)
+                .map_or(0, |positions| positions.len());
+            if mesh
+                .attributes()
+                .any(|(_, values)| values.len() != vertex_count)
+            {
+                return Err(B3DError::Processed(
+                    "vertex attributes of different lengths",
+                ));
+            }
+            if take(reader, 1)?[0] != 0 {
+                let len = read_len(reader)?;
+                let indices: Vec<u32> = take(reader, len.saturating_mul(4))?
+                    .chunks_exact(4)
+                    .map(|index| u32::from_le_bytes([index[0], index[1], index[2], index[3]]))
+                    .collect();
+                if indices.iter().any(|&index| index as usize >= vertex_count) {
+                    return Err(B3DError::Processed("vertex index out of range"));
+                }
+                mesh.insert_indices(Indices::U32(indices));
+            }
+            meshes.push(mesh);
+        }
+        primitives.push(meshes);
+    }
+    Ok(Some((b3d, primitives)))
+}
+
+fn write_len(bytes: &mut Vec<u8>, len: usize) {
+    bytes.extend((len as u32).to_le_bytes());
+}
+
+fn read_len(reader: &mut &[u8]) -> Result<usize, B3DError> {
+    let len = take(reader, 4)?;
+    Ok(u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize)
+}
+
+/// Takes the next `len` bytes off `reader`.
+fn take<'a>(reader: &mut &'a [u8], len: usize) -> Result<&'a [u8], B3DError> {
+    if reader.len() < len {
+        return Err(B3DError::Processed("file is cut short"));
+    }
+    let (taken, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(taken)
+}
+
+/// The little endian bytes of `values`, if they're of a format the loader builds.
+fn attribute_bytes(values: &VertexAttributeValues) -> Option<Vec<u8>> {
+    let bytes = match values {
+        VertexAttributeValues::Float32x2(values) => values
+            .iter()
+            .flatten()
+            .flat_map(|c| c.to_le_bytes())
+            .collect(),
+        VertexAttributeValues::Float32x3(values) => values
+            .iter()
+            .flatten()
+            .flat_map(|c| c.to_le_bytes())
+            .collect(),
+        VertexAttributeValues::Float32x4(values) => values
+            .iter()
+            .flatten()
+            .flat_map(|c| c.to_le_bytes())
+            .collect(),
+        VertexAttributeValues::Uint16x4(values) => values
+            .iter()
+            .flatten()
+            .flat_map(|c| c.to_le_bytes())
+            .collect(),
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+/// Reads back the values [`attribute_bytes`] wrote for an attribute of `format`.
+fn attribute_values(format: VertexFormat, data: &[u8]) -> Result<VertexAttributeValues, B3DError> {
+    if !data.len().is_multiple_of(format.size() as usize) {
+        return Err(B3DError::Processed("vertex attribute of the wrong size"));
+    }
+    Ok(match format {
+        VertexFormat::Float32x2 => VertexAttributeValues::Float32x2(floats(data)),
+        VertexFormat::Float32x3 => VertexAttributeValues::Float32x3(floats(data)),
+        VertexFormat::Float32x4 => VertexAttributeValues::Float32x4(floats(data)),
+        VertexFormat::Uint16x4 => VertexAttributeValues::Uint16x4(
+            data.chunks_exact(8)
+                .map(|v| std::array::from_fn(|i| u16::from_le_bytes([v[2 * i], v[2 * i + 1]])))
+                .collect(),
+        ),
+        _ => return Err(B3DError::Processed("unexpected vertex attribute format")),
+    })
+}
+
+fn floats<const N: usize>(data: &[u8]) -> Vec<[f32; N]> {
+    data.chunks_exact(4 * N)
+        .map(|v| {
+            std::array::from_fn(|i| {
+                f32::from_le_bytes([v[4 * i], v[4 * i + 1], v[4 * i + 2], v[4 * i + 3]])
+            })
+        })
+        .collect()
+}